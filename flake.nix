@@ -100,6 +100,11 @@
               default = 3000;
               description = "Port for the backend to listen on";
             };
+            bindAddress = mkOption {
+              type = types.str;
+              default = "0.0.0.0";
+              description = "Address for the backend to bind to";
+            };
             corsOrigins = mkOption {
               type = types.listOf types.str;
               default = [
@@ -156,7 +161,8 @@
               };
               environment = {
                 PORT = toString cfg.port;
-                CORS_ORIGINS = concatStringsSep "," cfg.corsOrigins;
+                BIND_ADDR = cfg.bindAddress;
+                CORS_ALLOWED_ORIGINS = concatStringsSep "," cfg.corsOrigins;
                 NODE_ENV = "production";
                 DATA_DIR = cfg.dataDir;
               };
@@ -0,0 +1,63 @@
+import { beforeAll, expect, test } from "bun:test";
+import { TestApp, createTestApp, request } from "./test-utils/app";
+import {
+  TEST_PASSWORD,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "./test-utils/db";
+import type { LogMailer } from "./utils/email";
+
+describeWithDb("sign-up emails", () => {
+  let app: TestApp;
+  let sent: LogMailer["sent"];
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    app = await createTestApp();
+    ({ sent } = (await import("./utils/email")).mailer as LogMailer);
+  });
+
+  const signUp = (email: string) =>
+    request(app, "/auth/api/sign-up/email", {
+      method: "POST",
+      body: { name: "newcomer", email, password: TEST_PASSWORD },
+    });
+
+  test("a malformed email is rejected", async () => {
+    for (const email of ["not-an-email", "two@@example.com", ""]) {
+      const res = await signUp(email);
+      expect(res.status).toBe(400);
+    }
+  });
+
+  test("a taken email is a conflict", async () => {
+    const user = await createTestUser("first");
+    const res = await signUp(user.email);
+    expect(res.status).toBe(409);
+
+    // Emails are compared case-insensitively
+    expect((await signUp(user.email.toUpperCase())).status).toBe(409);
+  });
+
+  test("the emailed link verifies the address", async () => {
+    const user = await createTestUser("unverified");
+    sent.length = 0;
+
+    const res = await request(app, "/auth/api/send-verification-email", {
+      method: "POST",
+      body: { email: user.email },
+    });
+    expect(res.status).toBe(200);
+    expect(sent).toHaveLength(1);
+    expect(sent[0].to).toBe(user.email);
+
+    const link = sent[0].text.match(/\/auth\/api\/verify-email\?\S+/)?.[0];
+    expect(link).toBeDefined();
+    // Redirects to the callback URL once verified
+    expect((await request(app, link!)).status).toBeLessThan(400);
+
+    const me = await (await request(app, "/me", { user })).json();
+    expect(me.emailVerified).toBe(true);
+  });
+});
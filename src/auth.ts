@@ -1,20 +1,110 @@
 import { betterAuth } from "better-auth";
 import { mongodbAdapter } from "better-auth/adapters/mongodb";
-import { openAPI, admin } from "better-auth/plugins";
+import { openAPI, admin, bearer } from "better-auth/plugins";
+import {
+  createAuthMiddleware,
+  APIError,
+  getSessionFromCtx,
+} from "better-auth/api";
 import { connectToDatabase } from "./db/connect";
 import { sendEmail } from "./utils/email";
+import { deleteUserContent } from "./utils/account";
+import { recordSignup } from "./utils/signup-limit";
+import { recordAdminAction } from "./utils/audit";
+import { hashUserPassword, verifyUserPassword } from "./utils/password";
+import { readSessionToken } from "./utils/session-errors";
+import {
+  CHECKS_TOKEN_CLAIMS,
+  claimsMatch,
+  tokenClaims,
+} from "./utils/token-audience";
+import { refreshTokens } from "./utils/refresh-plugin";
+import { mongoRefreshTokenStore } from "./utils/refresh-tokens";
+import type { RefreshToken } from "./db/models";
 
 const { db } = await connectToDatabase(); // Connect to MongoDB
 
+// Sessions are the short-lived access token. They are not extended while
+// in use; clients exchange their refresh token at /refresh for a new one.
+const ACCESS_TOKEN_EXPIRES_IN = parseInt(
+  process.env.ACCESS_TOKEN_EXPIRES_IN || String(60 * 15)
+); // 15 minutes
+const EMAIL_VERIFICATION_EXPIRES_IN = parseInt(
+  process.env.EMAIL_VERIFICATION_EXPIRES_IN || String(60 * 60)
+); // Verification links are valid for 1 hour
+
+// Routes that start a session, where a leftover foreign cookie is ignored
+const SIGN_IN_PREFIXES = ["/sign-in", "/sign-up", "/callback"];
+
+/**
+ * Rejects a session token issued for another app (see AUTH_ISSUER and
+ * AUTH_AUDIENCE). Runs before every auth route and `auth.api` call, which
+ * includes the session lookups done by the API routes and sockets.
+ */
+async function rejectForeignSession(path: string, headers?: Headers) {
+  if (!CHECKS_TOKEN_CLAIMS || !headers) return;
+  if (SIGN_IN_PREFIXES.some((prefix) => path.startsWith(prefix))) return;
+
+  const token = readSessionToken(headers);
+  if (!token) return;
+  const session = await db
+    .collection("session")
+    .findOne({ token }, { projection: { issuer: 1, audience: 1 } });
+  if (session && !claimsMatch(session)) {
+    throw new APIError("UNAUTHORIZED", {
+      message: "Session was issued for another application",
+    });
+  }
+}
+
+// Admin plugin routes that change users, by the action they're audited as
+const AUDITED_ADMIN_PATHS: Record<string, string> = {
+  "/admin/create-user": "user.create",
+  "/admin/update-user": "user.update",
+  "/admin/set-role": "user.set_role",
+  "/admin/ban-user": "user.ban",
+  "/admin/unban-user": "user.unban",
+  "/admin/set-user-password": "user.set_password",
+  "/admin/impersonate-user": "user.impersonate",
+  "/admin/revoke-user-session": "user.revoke_session",
+  "/admin/revoke-user-sessions": "user.revoke_sessions",
+  "/admin/remove-user": "user.remove",
+};
+// Request fields worth keeping in the audit entry (never passwords)
+const AUDITED_ADMIN_FIELDS = ["role", "banReason", "banExpiresIn"];
+
 export const auth = betterAuth({
   // baseURL: "http://localhost:3000/auth/api", // Your app's root URL (update for production)
   basePath: "/auth/api", // Matches your mounting; defaults to "/api/auth" otherwise
   database: mongodbAdapter(db),
-  plugins: [openAPI(), admin()],
+  // bearer() also accepts the session token as `Authorization: Bearer`, for
+  // clients (such as socket handshakes) that can't rely on cookies
+  plugins: [
+    openAPI(),
+    admin(),
+    bearer(),
+    refreshTokens(
+      mongoRefreshTokenStore(db.collection<RefreshToken>("refresh_tokens"))
+    ),
+  ],
   trustedOrigins: ["http://localhost:5173", "https://my-website.space"],
+  session: {
+    additionalFields: {
+      // Recorded at creation from AUTH_ISSUER and AUTH_AUDIENCE
+      issuer: { type: "string", required: false, input: false },
+      audience: { type: "string", required: false, input: false },
+    },
+    expiresIn: ACCESS_TOKEN_EXPIRES_IN,
+    // Never reached before a session expires, so sessions don't slide
+    updateAge: ACCESS_TOKEN_EXPIRES_IN,
+  },
   user: {
     deleteUser: {
       enabled: true,
+      // Runs for both DELETE /me and better-auth's own delete-user route
+      beforeDelete: async (user) => {
+        await deleteUserContent(user.id);
+      },
     },
 
     additionalFields: {
@@ -31,6 +121,15 @@ export const auth = betterAuth({
         type: "number",
         required: false,
       },
+      chessRating: {
+        type: "number",
+        required: false,
+        default: 1200,
+      },
+      chessPeakRating: {
+        type: "number",
+        required: false,
+      },
       draughtsWins: {
         type: "number",
         required: false,
@@ -56,6 +155,15 @@ export const auth = betterAuth({
         type: "string",
         required: false,
       },
+      // Self-managed profile fields
+      displayName: {
+        type: "string",
+        required: false,
+      },
+      bio: {
+        type: "string",
+        required: false,
+      },
       // Ban fields from admin plugin
       banned: {
         type: "boolean",
@@ -99,7 +207,60 @@ export const auth = betterAuth({
       },
     },
   },
+  hooks: {
+    before: createAuthMiddleware(async (ctx) => {
+      await rejectForeignSession(ctx.path, ctx.headers);
+
+      // better-auth reports a taken email as 422, clients expect a conflict
+      if (ctx.path !== "/sign-up/email") return;
+      const email = String(ctx.body?.email ?? "").toLowerCase();
+      if (email && (await db.collection("user").findOne({ email }))) {
+        throw new APIError("CONFLICT", {
+          message: "An account with this email already exists",
+        });
+      }
+    }),
+    after: createAuthMiddleware(async (ctx) => {
+      const action = AUDITED_ADMIN_PATHS[ctx.path];
+      if (!action || ctx.context.returned instanceof APIError) return;
+
+      const session = await getSessionFromCtx(ctx).catch(() => null);
+      if (!session) return;
+
+      // New users only have an ID once created
+      const userId =
+        ctx.body?.userId ?? (ctx.context.returned as any)?.user?.id;
+      const details = Object.fromEntries(
+        AUDITED_ADMIN_FIELDS.filter((key) => ctx.body?.[key] !== undefined).map(
+          (key) => [key, ctx.body[key]]
+        )
+      );
+      await recordAdminAction(
+        session.user.id,
+        action,
+        userId ? { type: "user", id: String(userId) } : null,
+        Object.keys(details).length > 0 ? details : undefined
+      );
+    }),
+  },
+  databaseHooks: {
+    session: {
+      create: {
+        before: async (session) => ({
+          data: { ...session, ...tokenClaims() },
+        }),
+      },
+    },
+    user: {
+      create: {
+        after: async () => {
+          recordSignup();
+        },
+      },
+    },
+  },
   emailVerification: {
+    expiresIn: EMAIL_VERIFICATION_EXPIRES_IN,
     sendVerificationEmail: async ({ user, url }) => {
       await sendEmail({
         to: user.email,
@@ -111,6 +272,15 @@ export const auth = betterAuth({
   },
   emailAndPassword: {
     enabled: true,
+    // better-auth's scrypt hashing, plus PASSWORD_PEPPER when it is set
+    password: { hash: hashUserPassword, verify: verifyUserPassword },
+    sendResetPassword: async ({ user, url }) => {
+      await sendEmail({
+        to: user.email,
+        subject: "Reset your password",
+        text: `Click the link to reset your password: ${url}\n\nIf you didn't ask for this, you can ignore this email.`,
+      });
+    },
   },
   socialProviders: {
     google: {
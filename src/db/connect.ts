@@ -1,6 +1,16 @@
-import { MongoClient, Db, Collection } from "mongodb";
-import { Comment, Reaction, FlashcardDeck } from "./models";
+import { MongoClient, MongoClientOptions, Db, Collection } from "mongodb";
+import {
+  Comment,
+  Reaction,
+  CommentReaction,
+  FlashcardDeck,
+  MediaItem,
+  Report,
+  ChessGameRecord,
+  AdminAuditEntry,
+} from "./models";
 import { UserDataSchema } from "../routes/connections";
+import { logSlowQueries, SLOW_QUERY_MS } from "./slow-queries";
 
 // Define the user type with all our custom fields
 import { ObjectId } from "mongodb";
@@ -10,32 +20,74 @@ export type UserDocument = (typeof UserDataSchema)["static"] & {
   _id: ObjectId;
 };
 
-const uri = process.env.MONGO_URI as string;
-if (!uri) throw new Error("MONGO_URI not set in .env");
+const envInt = (name: string, fallback: number) =>
+  parseInt(process.env[name] || String(fallback));
+
+// Connection pool settings, overridable per deployment
+const poolOptions: MongoClientOptions = {
+  maxPoolSize: envInt("MONGO_MAX_POOL_SIZE", 20),
+  minPoolSize: envInt("MONGO_MIN_POOL_SIZE", 2),
+  maxIdleTimeMS: envInt("MONGO_MAX_IDLE_TIME_MS", 60_000),
+  // How long an operation waits for a free connection before failing
+  waitQueueTimeoutMS: envInt("MONGO_WAIT_QUEUE_TIMEOUT_MS", 10_000),
+  serverSelectionTimeoutMS: envInt("MONGO_SERVER_SELECTION_TIMEOUT_MS", 10_000),
+  // Command events are only needed to time queries
+  monitorCommands: SLOW_QUERY_MS > 0,
+};
 
 let client: MongoClient;
 let db: Db;
 let userCollection: Collection<UserDocument>;
 let commentsCollection: Collection<Comment>;
 let reactionsCollection: Collection<Reaction>;
+let commentReactionsCollection: Collection<CommentReaction>;
 let flashcardsCollection: Collection<FlashcardDeck>;
+let mediaCollection: Collection<MediaItem>;
+let reportsCollection: Collection<Report>;
+let chessGamesCollection: Collection<ChessGameRecord>;
+let adminAuditCollection: Collection<AdminAuditEntry>;
 
 export async function connectToDatabase() {
   if (!client) {
-    client = new MongoClient(uri);
+    const uri = process.env.MONGO_URI;
+    if (!uri) throw new Error("MONGO_URI not set in .env");
+    client = new MongoClient(uri, poolOptions);
+    if (SLOW_QUERY_MS > 0) logSlowQueries(client);
     await client.connect();
     db = client.db();
     userCollection = db.collection<UserDocument>("user");
     commentsCollection = db.collection<Comment>("comments");
     reactionsCollection = db.collection<Reaction>("reactions");
+    commentReactionsCollection =
+      db.collection<CommentReaction>("comment_reactions");
     flashcardsCollection = db.collection<FlashcardDeck>("flashcards");
+    mediaCollection = db.collection<MediaItem>("media");
+    reportsCollection = db.collection<Report>("reports");
+    chessGamesCollection = db.collection<ChessGameRecord>("chess_games");
+    adminAuditCollection = db.collection<AdminAuditEntry>("admin_audit");
     console.log("Connected to MongoDB");
   }
   return {
+    client,
     db,
     userCollection,
     commentsCollection,
     reactionsCollection,
+    commentReactionsCollection,
     flashcardsCollection,
+    mediaCollection,
+    reportsCollection,
+    chessGamesCollection,
+    adminAuditCollection,
   };
 }
+
+/**
+ * Closes the MongoDB connection pool, waiting for in-flight operations.
+ */
+export async function closeDatabase() {
+  if (client) {
+    await client.close();
+    console.log("Disconnected from MongoDB");
+  }
+}
@@ -0,0 +1,100 @@
+import { beforeAll, expect, test } from "bun:test";
+import type { Db } from "mongodb";
+import { describeWithDb, resetTestDatabase } from "../test-utils/db";
+
+describeWithDb("runMigrations", () => {
+  let db: Db;
+
+  // Migrated from empty, since the database is dropped first
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+  });
+
+  const indexes = async (collection: string) =>
+    (await db.collection(collection).indexes()).map((index) => ({
+      key: index.key,
+      unique: index.unique ?? false,
+    }));
+
+  test("builds the indexes on an empty database", async () => {
+    expect(await indexes("reactions")).toEqual(
+      expect.arrayContaining([
+        { key: { blogId: 1, userId: 1 }, unique: true },
+        { key: { blogId: 1, type: 1, createdAt: -1, _id: -1 }, unique: false },
+        { key: { userId: 1 }, unique: false },
+      ])
+    );
+    expect(await indexes("comment_reactions")).toContainEqual({
+      key: { commentId: 1, userId: 1 },
+      unique: true,
+    });
+    expect(await indexes("comments")).toContainEqual({
+      key: { blogId: 1, createdAt: -1, _id: -1 },
+      unique: false,
+    });
+    expect(await indexes("refresh_tokens")).toContainEqual({
+      key: { tokenHash: 1 },
+      unique: true,
+    });
+  });
+
+  test("records each migration once and skips them next time", async () => {
+    const { runMigrations } = await import("./migrations");
+    const applied = async () =>
+      (await db.collection("migrations").find().sort({ _id: 1 }).toArray()).map(
+        (m) => m._id
+      );
+
+    const ids = await applied();
+    expect(ids[0]).toBe("001-comment-indexes");
+    expect(ids).toContain("007-refresh-token-indexes");
+
+    await runMigrations(db);
+    expect(await applied()).toEqual(ids);
+  });
+
+  // Index names used by the winning plan, wherever they sit in the tree
+  const plannedIndexes = async (
+    collection: string,
+    filter: object,
+    sort: Record<string, 1 | -1> = {}
+  ) => {
+    const plan = await db
+      .collection(collection)
+      .find(filter)
+      .sort(sort)
+      .explain("queryPlanner");
+    const winning = JSON.stringify(plan.queryPlanner.winningPlan);
+    return [...winning.matchAll(/"indexName":"([^"]+)"/g)].map((m) => m[1]);
+  };
+
+  test("reaction lookups are planned on their indexes", async () => {
+    // Busy post "a" and busy user "b", so the wrong index would lose
+    const seed = (target: "blogId" | "commentId") =>
+      Array.from({ length: 100 }, (_, i) => [
+        { [target]: "a", userId: `u${i}`, type: i % 2 ? "like" : "dislike" },
+        { [target]: `p${i}`, userId: "b", type: "like" },
+      ])
+        .flat()
+        .map((reaction) => ({ ...reaction, createdAt: new Date() }));
+    await db.collection("reactions").insertMany(seed("blogId"));
+    await db.collection("comment_reactions").insertMany(seed("commentId"));
+
+    expect(
+      await plannedIndexes("reactions", { blogId: "a", userId: "b" })
+    ).toEqual(["blogId_1_userId_1"]);
+    expect(
+      await plannedIndexes(
+        "reactions",
+        { blogId: "a", type: "like" },
+        { createdAt: -1, _id: -1 }
+      )
+    ).toEqual(["blogId_1_type_1_createdAt_-1__id_-1"]);
+    expect(await plannedIndexes("reactions", { userId: "b" })).toEqual([
+      "userId_1",
+    ]);
+    expect(
+      await plannedIndexes("comment_reactions", { commentId: "c", userId: "b" })
+    ).toEqual(["commentId_1_userId_1"]);
+  });
+});
@@ -0,0 +1,150 @@
+import { Db } from "mongodb";
+
+// Set RUN_MIGRATIONS=false to skip them, e.g. when a deploy step runs them
+const RUN_MIGRATIONS = process.env.RUN_MIGRATIONS !== "false";
+
+interface Migration {
+  id: string; // Recorded once applied, never rename
+  up: (db: Db) => Promise<void>;
+}
+
+/**
+ * Deletes all but the oldest document for each value of `key`, so a unique
+ * index can be built over data written before it existed.
+ */
+async function removeDuplicates(db: Db, collection: string, key: string[]) {
+  const duplicates = db.collection(collection).aggregate<{ ids: any[] }>([
+    { $sort: { _id: 1 } },
+    {
+      $group: {
+        _id: Object.fromEntries(key.map((field) => [field, `$${field}`])),
+        ids: { $push: "$_id" },
+        count: { $sum: 1 },
+      },
+    },
+    { $match: { count: { $gt: 1 } } },
+  ]);
+  for await (const { ids } of duplicates) {
+    await db.collection(collection).deleteMany({ _id: { $in: ids.slice(1) } });
+  }
+}
+
+// Applied in order; append new migrations to the end
+const MIGRATIONS: Migration[] = [
+  {
+    id: "001-comment-indexes",
+    up: async (db) => {
+      await db.collection("comments").createIndexes([
+        { key: { blogId: 1, createdAt: -1, _id: -1 } },
+        { key: { authorId: 1, createdAt: -1, _id: -1 } },
+        { key: { parentId: 1 } },
+      ]);
+    },
+  },
+  {
+    id: "002-unique-reactions",
+    up: async (db) => {
+      // One reaction per user and target, toggling relies on it
+      await removeDuplicates(db, "reactions", ["blogId", "userId"]);
+      await db
+        .collection("reactions")
+        .createIndex({ blogId: 1, userId: 1 }, { unique: true });
+      await removeDuplicates(db, "comment_reactions", ["commentId", "userId"]);
+      await db
+        .collection("comment_reactions")
+        .createIndex({ commentId: 1, userId: 1 }, { unique: true });
+    },
+  },
+  {
+    id: "003-chess-game-indexes",
+    up: async (db) => {
+      await db.collection("chess_games").createIndexes([
+        { key: { "white.username": 1, createdAt: -1, _id: -1 } },
+        { key: { "black.username": 1, createdAt: -1, _id: -1 } },
+        { key: { "white.userId": 1 } },
+        { key: { "black.userId": 1 } },
+      ]);
+    },
+  },
+  {
+    id: "004-ownership-indexes",
+    up: async (db) => {
+      await db.collection("reports").createIndexes([
+        { key: { status: 1, createdAt: -1 } },
+        { key: { reporterId: 1 } },
+      ]);
+      await db.collection("flashcards").createIndex({ userId: 1 });
+      await db.collection("media").createIndex({ uploadedBy: 1 });
+    },
+  },
+  {
+    id: "005-hot-path-indexes",
+    up: async (db) => {
+      // Per-post and per-comment lookups by user are covered by the
+      // unique indexes from 002; these cover the remaining frequent queries
+      await db.collection("reactions").createIndexes([
+        { key: { blogId: 1, type: 1, createdAt: -1, _id: -1 } },
+        { key: { userId: 1 } },
+      ]);
+      await db.collection("comment_reactions").createIndex({ userId: 1 });
+      await db
+        .collection("comments")
+        .createIndex({ accepted: 1, createdAt: 1 });
+      await db.collection("user").createIndexes([
+        { key: { name: 1 } },
+        { key: { chessRating: -1 }, sparse: true },
+      ]);
+    },
+  },
+  {
+    id: "006-admin-audit-indexes",
+    up: async (db) => {
+      await db.collection("admin_audit").createIndexes([
+        { key: { createdAt: -1, _id: -1 } },
+        { key: { adminId: 1, createdAt: -1, _id: -1 } },
+      ]);
+    },
+  },
+  {
+    id: "007-refresh-token-indexes",
+    up: async (db) => {
+      await db.collection("refresh_tokens").createIndexes([
+        { key: { tokenHash: 1 }, unique: true },
+        { key: { family: 1 } },
+        // Expired tokens are removed by MongoDB
+        { key: { expiresAt: 1 }, expireAfterSeconds: 0 },
+      ]);
+    },
+  },
+];
+
+/**
+ * Applies every migration not yet recorded in the `migrations` collection,
+ * in order. Throws (failing startup) if one fails; it is retried on the
+ * next start since only successful migrations are recorded.
+ */
+export async function runMigrations(db: Db) {
+  if (!RUN_MIGRATIONS) return;
+
+  const applied = db.collection<{ _id: string; appliedAt: Date }>(
+    "migrations"
+  );
+  const done = new Set(
+    (await applied.find({}, { projection: { _id: 1 } }).toArray()).map(
+      (m) => m._id
+    )
+  );
+
+  for (const migration of MIGRATIONS) {
+    if (done.has(migration.id)) continue;
+    try {
+      await migration.up(db);
+    } catch (error: any) {
+      throw new Error(
+        `Database migration ${migration.id} failed: ${error?.message ?? error}`
+      );
+    }
+    await applied.insertOne({ _id: migration.id, appliedAt: new Date() });
+    console.log(`Applied database migration ${migration.id}`);
+  }
+}
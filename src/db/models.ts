@@ -21,13 +21,106 @@ export interface Comment {
   content: string;
   accepted: boolean;
   createdAt: Date;
+  parentId?: string | null; // Comment being replied to, null for top-level
+  deleted?: boolean; // Tombstoned: content removed but replies kept
+  editedAt?: Date; // Set when the author or an admin edits the content
 }
 
+export interface ChessGamePlayer {
+  userId: string;
+  username: string;
+}
+
+export interface ChessMoveTime {
+  thinkMs: number; // How long the mover took
+  clock: number; // Seconds left on the mover's clock after the move
+}
+
+// A finished game between two users (games against the engine aren't kept)
+export interface ChessGameRecord {
+  _id?: ObjectId;
+  white: ChessGamePlayer;
+  black: ChessGamePlayer;
+  winner: "white" | "black" | null; // Null for a draw
+  reason: string; // e.g. checkmate, resignation, timeout
+  moves: string[]; // SAN, in order
+  moveTimes?: ChessMoveTime[]; // One per move; missing on older games
+  opening?: { eco: string; name: string } | null; // Missing on older games
+  variant?: "standard" | "custom"; // Missing means standard
+  initialFen?: string; // Starting position, missing means the standard one
+  finalFen: string;
+  timeControl: number; // Seconds per side
+  isPrivate: boolean;
+  startedAt: Date;
+  createdAt: Date; // When the game ended
+}
+
+export type ReportTargetType = "post" | "comment";
+
+export interface Report {
+  _id?: ObjectId;
+  targetType: ReportTargetType;
+  targetId: string; // Blog post ID (filename) or comment ObjectId string
+  reporterId: string;
+  reason: string;
+  status: "open" | "resolved";
+  createdAt: Date;
+  resolvedAt?: Date;
+  resolvedBy?: string; // User ID of the admin who resolved it
+  action?: "dismiss" | "delete_content";
+}
+
+export interface MediaItem {
+  _id?: ObjectId;
+  filename: string; // Stored under DATA_DIR/media
+  mimeType: string;
+  size: number; // Bytes
+  originalName?: string;
+  uploadedBy: string; // User ID of the admin who uploaded it
+  createdAt: Date;
+}
+
+export type AuditTargetType = "post" | "comment" | "report" | "user" | "media";
+
+export interface AdminAuditEntry {
+  _id?: ObjectId;
+  adminId: string; // User ID of the admin who acted
+  action: string; // e.g. "post.edit", "user.set_role"
+  targetType: AuditTargetType | null;
+  targetId: string | null;
+  details?: Record<string, unknown>;
+  createdAt: Date;
+}
+
+// Refresh tokens are stored hashed. Rotating one marks it used and issues
+// the next in the same family, so reusing an old one revokes the family.
+export interface RefreshToken {
+  _id?: ObjectId;
+  tokenHash: string; // SHA-256 of the token handed to the client
+  family: string; // Shared by every token rotated from the same sign-in
+  userId: string;
+  sessionToken: string; // Access token (session) issued alongside it
+  createdAt: Date;
+  expiresAt: Date;
+  usedAt: Date | null; // Set once exchanged at /refresh
+  revokedAt: Date | null;
+}
+
+export type ReactionType = "like" | "dislike";
+
 export interface Reaction {
   _id?: ObjectId;
   blogId: string;
   userId: string;
-  type: "like" | "dislike";
+  type: ReactionType;
+  createdAt: Date;
+}
+
+export interface CommentReaction {
+  _id?: ObjectId;
+  commentId: string;
+  userId: string;
+  type: ReactionType;
   createdAt: Date;
 }
 
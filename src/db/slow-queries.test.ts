@@ -0,0 +1,54 @@
+import {
+  type Mock,
+  afterEach,
+  beforeEach,
+  describe,
+  expect,
+  spyOn,
+  test,
+} from "bun:test";
+import { EventEmitter } from "node:events";
+import type { MongoClient } from "mongodb";
+import { SLOW_QUERY_MS, logSlowQueries } from "./slow-queries";
+
+describe("logSlowQueries", () => {
+  let client: EventEmitter;
+  let warn: Mock<typeof console.warn>;
+
+  beforeEach(() => {
+    client = new EventEmitter();
+    logSlowQueries(client as unknown as MongoClient);
+    warn = spyOn(console, "warn").mockImplementation(() => {});
+  });
+
+  afterEach(() => {
+    warn.mockRestore();
+  });
+
+  let requestId = 0;
+  const run = (
+    command: Record<string, unknown>,
+    duration: number,
+    outcome = "commandSucceeded"
+  ) => {
+    const id = ++requestId;
+    const commandName = Object.keys(command)[0];
+    client.emit("commandStarted", { requestId: id, commandName, command });
+    client.emit(outcome, { requestId: id, commandName, duration });
+  };
+
+  test("warns about commands at or over the threshold", () => {
+    run({ find: "comments", filter: {} }, SLOW_QUERY_MS + 0.4);
+    run({ aggregate: "user", pipeline: [] }, SLOW_QUERY_MS - 1);
+    expect(warn.mock.calls).toEqual([
+      [`Slow query (ok): find comments took ${SLOW_QUERY_MS}ms`],
+    ]);
+  });
+
+  test("labels failures and commands without a collection", () => {
+    run({ ping: 1 }, SLOW_QUERY_MS * 2, "commandFailed");
+    expect(warn.mock.calls).toEqual([
+      [`Slow query (failed): ping took ${SLOW_QUERY_MS * 2}ms`],
+    ]);
+  });
+});
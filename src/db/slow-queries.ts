@@ -0,0 +1,43 @@
+import {
+  MongoClient,
+  CommandStartedEvent,
+  CommandSucceededEvent,
+  CommandFailedEvent,
+} from "mongodb";
+
+// Operations slower than this are logged; 0 turns slow-query logging off
+export const SLOW_QUERY_MS = parseInt(process.env.SLOW_QUERY_MS || "100");
+
+/**
+ * Logs a warning for every database command that takes at least
+ * SLOW_QUERY_MS, labelled with the command and collection, e.g.
+ * `find comments`. The client must be created with `monitorCommands`.
+ */
+export function logSlowQueries(client: MongoClient) {
+  // Finished events don't carry the command, so remember each label
+  const labels = new Map<number, string>();
+
+  client.on("commandStarted", (event: CommandStartedEvent) => {
+    const target = event.command[event.commandName];
+    labels.set(
+      event.requestId,
+      typeof target === "string"
+        ? `${event.commandName} ${target}`
+        : event.commandName
+    );
+  });
+
+  const finished = (
+    event: CommandSucceededEvent | CommandFailedEvent,
+    outcome: string
+  ) => {
+    const label = labels.get(event.requestId) ?? event.commandName;
+    labels.delete(event.requestId);
+    if (event.duration < SLOW_QUERY_MS) return;
+    console.warn(
+      `Slow query (${outcome}): ${label} took ${Math.round(event.duration)}ms`
+    );
+  };
+  client.on("commandSucceeded", (event) => finished(event, "ok"));
+  client.on("commandFailed", (event) => finished(event, "failed"));
+}
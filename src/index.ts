@@ -1,30 +1,95 @@
 // Backend: src/index.ts (main entry point with Socket.IO CORS added)
 
 import { Elysia } from "elysia";
-import { connectToDatabase } from "./db/connect";
+import { connectToDatabase, closeDatabase } from "./db/connect";
+import { runMigrations } from "./db/migrations";
 import { openapi } from "@elysiajs/openapi";
 import { cors } from "@elysiajs/cors";
 import { Server } from "socket.io";
 import { Server as Engine } from "@socket.io/bun-engine";
 
 import { auth, OpenAPI } from "./auth";
-import { setupChess } from "./webhooks/chess"; // Modularized chess logic
+import {
+  setupChess,
+  startChessSweeper,
+  startChessMatchmaker,
+  shutdownChess,
+} from "./webhooks/chess"; // Modularized chess logic
 import { setupConnections } from "./webhooks/connections"; // Modularized connections logic
+import { setupComments } from "./webhooks/comments"; // Live blog comments
+import { setupChessFeed } from "./webhooks/chess-feed"; // Public move feed
 import { setupQuizspire } from "./webhooks/quizspire"; // Modularized quizspire logic
-import { websocket, engine } from "./webhooks/index"; // Boilerplate/objects
-import { avatarRoutes } from "./routes/avatar";
-import { blogRoutes } from "./routes/blog";
-import { imageRoutes } from "./routes/images";
-import { profileRoutes, adminProfileRoutes } from "./routes/profile";
-import { leaderboardsRoutes } from "./routes/leaderboards";
-import { connectionsRoutes } from "./routes/connections"; // Admin routes for connections
-import { notificationsRoutes } from "./routes/notifications";
-import { quizspireRoutes } from "./routes/quizspire";
-import { foldersRoutes } from "./routes/folders";
+import {
+  websocket,
+  engine,
+  PING_INTERVAL_MS,
+  PING_TIMEOUT_MS,
+} from "./webhooks/index"; // Boilerplate/objects
+import { healthRoutes } from "./routes/health";
+import { authValidateRoutes } from "./routes/sessions";
+import { v1Routes } from "./routes/v1";
+import { v2Routes } from "./routes/v2";
+import { startPostScheduler } from "./routes/blog";
+import {
+  corsAllowedOrigins,
+  corsAllowedMethods,
+  corsAllowedHeaders,
+  corsAllowCredentials,
+  isAllowedSocketOrigin,
+} from "./utils/cors";
+import {
+  recordHttpRequest,
+  trackSocketConnections,
+  metricsResponse,
+} from "./utils/metrics";
+import { readFileSync } from "node:fs";
 import { rateLimit } from "elysia-rate-limit";
 import { ip } from "elysia-ip";
+import { signupRateLimit } from "./utils/signup-limit";
+import { compression } from "./utils/compression";
+import { bodyLimit, MAX_UPLOAD_BODY_BYTES } from "./utils/body-limit";
+import { withCookieOnlyLogin } from "./utils/cookie-login";
+import { withRequestTimeout } from "./utils/request-timeout";
+import { specServers } from "./utils/spec-servers";
+
+const { db } = await connectToDatabase(); // Connect to MongoDB on startup
+await runMigrations(db); // Create missing indexes, aborts startup on failure
+
+const HEALTH_PATHS = ["/health", "/ready"];
 
-await connectToDatabase(); // Connect to MongoDB on startup
+// Requests to /api/<path> without a version are redirected here
+const DEFAULT_API_VERSION = "v1";
+
+// From PUBLIC_BASE_URL, for the specs' "Try it out"
+const servers = specServers();
+
+// Each API version gets its own spec and docs UI. The openapi plugin only
+// excludes exact paths, so these lists are filled in once every route is
+// registered (the plugin reads them lazily when a spec is requested).
+const specExclusions: Record<"unversioned" | "v1" | "v2", string[]> = {
+  unversioned: [],
+  v1: [],
+  v2: [],
+};
+
+// Wrapped so the plugin isn't deduplicated with the unversioned spec
+const versionedSpec = async (version: "v1" | "v2") =>
+  new Elysia().use(
+    openapi({
+      path: `/api/${version}/openapi`,
+      documentation: {
+        info: {
+          title: `API ${version}`,
+          version,
+          description: `Endpoints under /api/${version}. Unversioned /api/<path> requests are redirected (308) to /api/${DEFAULT_API_VERSION}/<path>. Auth, health checks and Socket.IO namespaces (/sockets/*) are not versioned.`,
+        },
+        servers,
+        components: await OpenAPI.components,
+        paths: await OpenAPI.getPaths(),
+      },
+      exclude: { paths: specExclusions[version] },
+    })
+  );
 
 const app = new Elysia()
   // Derive IP early (adds { ip } to context)
@@ -34,68 +99,223 @@ const app = new Elysia()
       duration: 60_000, // 1 minute window
       max: 200, // 200 reqs per window
       generator: (req, server, { ip }) => ip, // Custom generator as getting IP on BunJS is different
+      skip: (req) => HEALTH_PATHS.includes(new URL(req.url).pathname), // Never rate limit monitoring
     })
   ) // Global: 100 reqs/min per IP
+  .use(signupRateLimit) // Accounts created per IP per hour
+  .use(bodyLimit) // 413 for oversized request bodies
   .use(
     openapi({
       documentation: {
+        servers,
         components: await OpenAPI.components,
         paths: await OpenAPI.getPaths(),
       },
+      exclude: { paths: specExclusions.unversioned },
     })
   )
+  .use(await versionedSpec("v1"))
+  .use(await versionedSpec("v2"))
   .use(
     cors({
-      origin: process.env.CORS_ORIGINS
-        ? process.env.CORS_ORIGINS.split(",")
-        : ["http://localhost:5173"], // Allow requests from your frontend
-      methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS"], // Allow specific HTTP methods
-      allowedHeaders: ["Content-Type", "Authorization"], // Allow specific headers
-      credentials: true, // Allow cookies or credentials if needed
+      origin: corsAllowedOrigins, // Allow requests from your frontend
+      methods: corsAllowedMethods, // Allow specific HTTP methods
+      allowedHeaders: corsAllowedHeaders, // Allow specific headers
+      credentials: corsAllowCredentials, // Allow cookies or credentials if needed
+      exposeHeaders: ["Link", "X-Total-Count", "X-Next-Cursor"], // Paging headers
     })
   )
-  .mount("/", auth.handler)
-  .use(avatarRoutes) // Mounts the avatar routes
-  .use(blogRoutes) // Mounts the blog routes
-  .use(imageRoutes) // Mounts the image routes
-  .use(profileRoutes) // Mounts the profile routes
-  .use(adminProfileRoutes) // Mounts the admin profile routes
-  .use(leaderboardsRoutes) // Mounts the leaderboards routes
-  .use(connectionsRoutes) // Mounts the connections admin routes
-  .use(notificationsRoutes) // Mounts the notifications routes
-  .use(quizspireRoutes) // Mounts the quizspire routes
-  .use(foldersRoutes) // Mounts the folders routes
-  .get("/", () => "hi")
-  .get("/me", async ({ request: { headers } }) => {
-    return await auth.api.getSession({ headers });
-  });
+  .use(compression) // gzip/brotli for larger JSON and text responses
+  .use(healthRoutes) // Mounts the health check routes
+  .use(authValidateRoutes) // Session check, unversioned like auth
+  .mount("/", withCookieOnlyLogin(auth.handler)) // ?cookie=true hides tokens
+  .use(v1Routes) // Unversioned paths, kept for existing clients (same as v1)
+  .group("/api/v1", (app) => app.use(v1Routes))
+  .group("/api/v2", (app) => app.use(v2Routes))
+  .all("/api/*", ({ params, request }) => {
+    // Paths without a version go to the default version, v1
+    const path = params["*"];
+    if (/^v\d+(\/|$)/.test(path)) {
+      return new Response("Not found", { status: 404 });
+    }
+    const { search } = new URL(request.url);
+    return Response.redirect(
+      new URL(`/api/${DEFAULT_API_VERSION}/${path}${search}`, request.url),
+      308
+    );
+  })
+  .get("/", () => "hi");
+
+for (const route of app.routes) {
+  const version = route.path.match(/^\/api\/(v\d+)\//)?.[1];
+  for (const [key, excluded] of Object.entries(specExclusions)) {
+    const inSpec =
+      key === "unversioned" ? !route.path.startsWith("/api/") : key === version;
+    if (!inSpec) excluded.push(route.path);
+  }
+}
 const io = new Server({
   cors: {
-    origin: "http://localhost:5173",
-    credentials: true,
+    origin: corsAllowedOrigins,
+    methods: corsAllowedMethods,
+    credentials: corsAllowCredentials,
   },
 });
 io.bind(engine);
 
 setupChess(io.of("/sockets/chess")); // Set up chess handlers on /sockets/chess
+const chessSweeper = startChessSweeper(); // Periodically remove abandoned chess games
+const chessMatchmaker = startChessMatchmaker(); // Pair queued players as rating bands widen
+const postScheduler = startPostScheduler(); // Publish scheduled drafts when due
 setupConnections(io.of("/sockets/connection")); // Set up connections on /sockets/connection
 setupQuizspire(io.of("/sockets/quizspire")); // Set up quizspire handlers on /sockets/quizspire
+setupComments(io.of("/sockets/comments")); // Push new blog comments on /sockets/comments
+setupChessFeed(io.of("/sockets/chess-feed")); // Stream public chess moves on /sockets/chess-feed
+
+// Count connected clients per namespace for /metrics
+trackSocketConnections(io.of("/sockets/chess"));
+trackSocketConnections(io.of("/sockets/connection"));
+trackSocketConnections(io.of("/sockets/quizspire"));
+trackSocketConnections(io.of("/sockets/comments"));
+trackSocketConnections(io.of("/sockets/chess-feed"));
+
+/**
+ * Loads the TLS certificate and key when TLS_CERT_PATH and TLS_KEY_PATH are
+ * set. Returns undefined to serve plain HTTP. Fails startup if only one is
+ * set or either file can't be read.
+ */
+function loadTls() {
+  const certPath = process.env.TLS_CERT_PATH;
+  const keyPath = process.env.TLS_KEY_PATH;
+  if (!certPath && !keyPath) return undefined;
+  if (!certPath || !keyPath) {
+    throw new Error(
+      "TLS_CERT_PATH and TLS_KEY_PATH must both be set to enable HTTPS"
+    );
+  }
 
-export default {
+  const readPem = (path: string, name: string) => {
+    try {
+      return readFileSync(path, "utf8");
+    } catch (error: any) {
+      throw new Error(
+        `Failed to load TLS ${name} from ${path}: ${error.message}`
+      );
+    }
+  };
+
+  return {
+    cert: readPem(certPath, "certificate"),
+    key: readPem(keyPath, "private key"),
+  };
+}
+
+const tls = loadTls();
+const hostname = process.env.BIND_ADDR || "0.0.0.0";
+
+// When set, /metrics is only served on this (internal) port
+const METRICS_PORT = process.env.METRICS_PORT
+  ? parseInt(process.env.METRICS_PORT)
+  : undefined;
+
+const server = Bun.serve({
+  hostname,
   port: parseInt(process.env.PORT || "3000"),
-  idleTimeout: 30, // Adjust based on your needs (must exceed pingInterval)
-  fetch(req: Request, server: any) {
+  tls,
+  maxRequestBodySize: MAX_UPLOAD_BODY_BYTES, // Also caps chunked bodies
+  // Must exceed the socket heartbeat, Bun caps it at 255 seconds
+  idleTimeout: Math.min(
+    255,
+    Math.max(30, Math.ceil((PING_INTERVAL_MS + PING_TIMEOUT_MS) / 1000) + 5)
+  ),
+  async fetch(req: Request, server: any) {
     const url = new URL(req.url);
     if (url.pathname.startsWith("/sockets/")) {
+      if (!isAllowedSocketOrigin(req)) {
+        return new Response("Origin not allowed", { status: 403 });
+      }
       return engine.handleRequest(req, server);
-    } else {
-      return app.handle(req); // Elysia handles non-Socket.IO requests
     }
+    if (url.pathname === "/metrics" && METRICS_PORT === undefined) {
+      return metricsResponse();
+    }
+
+    // Elysia handles non-Socket.IO requests. Socket.IO polling and
+    // upgrades are exempt from the timeout, long polls wait on purpose
+    const start = performance.now();
+    const response = await withRequestTimeout(app.handle(req));
+    recordHttpRequest(
+      req.method,
+      response.status,
+      (performance.now() - start) / 1000
+    );
+    return response;
   },
   websocket,
-};
+});
+
+const metricsServer =
+  METRICS_PORT !== undefined
+    ? Bun.serve({
+        hostname: process.env.METRICS_BIND_ADDR || hostname,
+        port: METRICS_PORT,
+        fetch(req: Request) {
+          return new URL(req.url).pathname === "/metrics"
+            ? metricsResponse()
+            : new Response("Not found", { status: 404 });
+        },
+      })
+    : undefined;
+
+const SHUTDOWN_TIMEOUT_MS = parseInt(
+  process.env.SHUTDOWN_TIMEOUT_MS || "10000"
+); // Max time to wait for in-flight requests before exiting anyway
+
+let shuttingDown = false;
+
+/**
+ * Stops accepting connections, tells chess players the server is going away,
+ * lets in-flight requests finish and closes the database connection.
+ */
+async function shutdown(signal: string) {
+  if (shuttingDown) return;
+  shuttingDown = true;
+  console.log(`${signal} received, shutting down gracefully...`);
+
+  const forceExit = setTimeout(() => {
+    console.error(
+      `Shutdown did not finish within ${SHUTDOWN_TIMEOUT_MS}ms, forcing exit`
+    );
+    process.exit(1);
+  }, SHUTDOWN_TIMEOUT_MS);
+
+  try {
+    clearInterval(chessSweeper);
+    clearInterval(chessMatchmaker);
+    clearInterval(postScheduler);
+    metricsServer?.stop(true);
+    shutdownChess();
+    const drained = server.stop(); // Stop accepting, wait for in-flight requests
+    io.close(); // Disconnect all sockets so the drain can complete
+    await drained;
+    await closeDatabase();
+    console.log("Shutdown complete");
+  } catch (error) {
+    console.error("Error during shutdown:", error);
+  } finally {
+    clearTimeout(forceExit);
+    process.exit(0);
+  }
+}
+
+process.on("SIGTERM", () => shutdown("SIGTERM"));
+process.on("SIGINT", () => shutdown("SIGINT"));
 
-console.log(`🦊 Elysia is running at http://localhost:3000`);
-console.log(
-  `📚 OpenAPI documentation available at http://localhost:3000/openapi`
-);
+const baseUrl = `${tls ? "https" : "http"}://${server.hostname}:${server.port}`;
+console.log(`🦊 Elysia is running at ${baseUrl}`);
+console.log(`📚 OpenAPI documentation available at ${baseUrl}/openapi`);
+if (metricsServer) {
+  console.log(
+    `📈 Metrics available at http://${metricsServer.hostname}:${metricsServer.port}/metrics`
+  );
+}
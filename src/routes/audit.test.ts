@@ -0,0 +1,62 @@
+import { beforeAll, expect, test } from "bun:test";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+
+describeWithDb("/admin/audit", () => {
+  let app: TestApp;
+  let admin: TestUser;
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    app = await createTestApp();
+    admin = await createTestUser("auditor", { role: "admin" });
+  });
+
+  const auditLog = async (query = "") =>
+    (await request(app, `/admin/audit${query}`, { user: admin })).json();
+
+  test("creating and editing a post is audited", async () => {
+    await request(app, "/blog/admin/posts/import", {
+      method: "POST",
+      user: admin,
+      body: { posts: [{ title: "Audited", content: "Watched closely" }] },
+    });
+    await request(app, "/blog/admin/posts/audited", {
+      method: "PUT",
+      user: admin,
+      body: { version: 1, title: "Audited Again" },
+    });
+
+    const { entries } = await auditLog();
+    expect(entries).toHaveLength(2);
+    expect(entries[0]).toMatchObject({
+      adminId: admin.id,
+      action: "post.edit",
+      targetType: "post",
+      targetId: "audited",
+      details: { version: 2 },
+    });
+    expect(entries[1]).toMatchObject({
+      adminId: admin.id,
+      action: "post.import",
+      targetType: null,
+      details: { created: ["audited"] },
+    });
+
+    const imports = await auditLog("?action=post.import");
+    expect(imports.entries.map((e: { id: string }) => e.id)).toEqual([
+      entries[1].id,
+    ]);
+  });
+
+  test("only admins can read the log", async () => {
+    const user = await createTestUser("curious");
+    expect((await request(app, "/admin/audit", { user })).status).toBe(403);
+    expect((await request(app, "/admin/audit")).status).toBe(401);
+  });
+});
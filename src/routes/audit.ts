@@ -0,0 +1,99 @@
+import { Elysia, t } from "elysia";
+import { auth } from "../auth";
+import { ApiError, handleApiError } from "../utils/errors";
+import {
+  PaginationQuerySchema,
+  parseLimit,
+  decodeCursor,
+  encodeCursor,
+  setPaginationHeaders,
+} from "../utils/pagination";
+import { findAdminActions, countAdminActions } from "../utils/audit";
+
+const AuditEntrySchema = t.Object({
+  id: t.String(),
+  adminId: t.String(),
+  action: t.String(),
+  targetType: t.Nullable(
+    t.Union([
+      t.Literal("post"),
+      t.Literal("comment"),
+      t.Literal("report"),
+      t.Literal("user"),
+      t.Literal("media"),
+    ])
+  ),
+  targetId: t.Nullable(t.String()),
+  details: t.Nullable(t.Record(t.String(), t.Unknown())),
+  createdAt: t.Date(),
+});
+
+export const adminAuditRoutes = new Elysia({ prefix: "/admin/audit" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers });
+    if (!session) {
+      throw new ApiError(401, "Unauthorized: Admin access required");
+    }
+    if (!session.user.role?.includes("admin")) {
+      throw new ApiError(403, "Forbidden: Admin access required");
+    }
+    return { currentUser: session.user };
+  })
+  .onError(handleApiError)
+  .get(
+    "/",
+    async ({ query, request, set }) => {
+      const limit = parseLimit(query.limit);
+      const filters = { adminId: query.adminId, action: query.action };
+
+      const [page, total] = await Promise.all([
+        findAdminActions(filters, {
+          limit,
+          cursor: query.after ? decodeCursor(query.after) : undefined,
+          offset: Math.max(0, parseInt(query.offset || "0") || 0),
+        }),
+        countAdminActions(filters),
+      ]);
+
+      const entries = page.slice(0, limit);
+      const last = entries[entries.length - 1];
+      const nextCursor =
+        page.length > limit && last
+          ? encodeCursor(last.createdAt, last._id.toString())
+          : null;
+      setPaginationHeaders({ request, set }, { total, limit, nextCursor });
+
+      return {
+        entries: entries.map(({ _id, details, ...entry }) => ({
+          ...entry,
+          id: _id.toString(),
+          details: details ?? null,
+        })),
+        nextCursor,
+      };
+    },
+    {
+      query: t.Composite([
+        PaginationQuerySchema,
+        t.Object({
+          adminId: t.Optional(
+            t.String({ description: "Only actions by this admin" })
+          ),
+          action: t.Optional(
+            t.String({ description: "Only this action, e.g. `post.edit`" })
+          ),
+        }),
+      ]),
+      response: t.Object({
+        entries: t.Array(AuditEntrySchema),
+        nextCursor: t.Nullable(t.String()),
+      }),
+      detail: {
+        summary: "Review the admin audit log - Admin only",
+        tags: ["admin"],
+        security: [{ session: [] }],
+        description:
+          "Lists admin actions newest first: post edits, imports and featuring, comment moderation, report resolutions, profile verifications, media uploads, counter repairs and better-auth admin actions such as role changes and bans. Pass `nextCursor` back as `after` for the next page.",
+      },
+    }
+  );
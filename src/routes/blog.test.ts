@@ -0,0 +1,332 @@
+import { beforeAll, expect, test } from "bun:test";
+import { existsSync } from "node:fs";
+import path from "node:path";
+import type { Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TEST_BLOGS_DIR,
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { writeTestPost } from "../test-utils/posts";
+
+describeWithDb("/blog", () => {
+  let db: Db;
+  let app: TestApp;
+  let admin: TestUser;
+  let blog: typeof import("./blog");
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    admin = await createTestUser("admin", { role: "admin" });
+    blog = await import("./blog");
+  });
+
+  const hasPostFile = (id: string) =>
+    existsSync(path.join(TEST_BLOGS_DIR, `${id}.md`));
+
+  const importPosts = (posts: object[], strict?: boolean) =>
+    request(app, "/blog/admin/posts/import", {
+      method: "POST",
+      user: admin,
+      body: { posts, strict },
+    });
+
+  const editPost = (id: string, body: object) =>
+    request(app, `/blog/admin/posts/${id}`, {
+      method: "PUT",
+      user: admin,
+      body,
+    });
+
+  let reactedAt = Date.parse("2024-01-01T00:00:00Z");
+  const react = (blogId: string, user: TestUser, type: string) =>
+    db.collection("reactions").insertOne({
+      blogId,
+      userId: user.id,
+      type,
+      createdAt: new Date((reactedAt += 1000)),
+    });
+
+  const getPost = async (id: string) =>
+    (await request(app, `/blog/${id}`)).json();
+
+  test("editing a post re-renders it on the next read", async () => {
+    await writeTestPost("cached", { content: "First version" });
+    expect((await getPost("cached")).blog.content).toContain("First version");
+
+    const res = await editPost("cached", {
+      version: 1,
+      markdownContent: "Second version",
+    });
+    expect(res.status).toBe(200);
+
+    const { content } = (await getPost("cached")).blog;
+    expect(content).toContain("Second version");
+    expect(content).not.toContain("First version");
+  });
+
+  test("featuring a post unfeatures the previous one", async () => {
+    await writeTestPost("old-news", { featured: true });
+    await writeTestPost("big-news");
+
+    const res = await request(app, "/blog/admin/featured", {
+      method: "PUT",
+      user: admin,
+      body: { id: "big-news" },
+    });
+    expect(await res.json()).toEqual({ id: "big-news" });
+    expect((await (await request(app, "/blog/featured")).json()).id).toBe(
+      "big-news"
+    );
+
+    const posts = await blog.getBlogsFromFiles(true);
+    const featured = posts.filter((p) => p.featured).map((p) => p.id);
+    expect(featured).toEqual(["big-news"]);
+    // Both posts changed, so both versions were bumped
+    for (const id of ["old-news", "big-news"]) {
+      expect(posts.find((p) => p.id === id)?.version).toBe(2);
+    }
+  });
+
+  test("importing a clean batch creates every post", async () => {
+    const res = await importPosts([
+      { title: "Hello, World!", content: "The very first post." },
+      { title: "Second Post", content: "More words.", tags: ["Intro"] },
+    ]);
+    expect(await res.json()).toEqual({
+      created: ["hello-world", "second-post"],
+      skipped: [],
+    });
+
+    const posts = await blog.getBlogsFromFiles();
+    const second = posts.find((p) => p.id === "second-post");
+    expect(second?.snippet).toBe("More words.");
+    expect(second?.tags).toEqual(["intro"]);
+    expect(hasPostFile("hello-world")).toBe(true);
+  });
+
+  test("importing skips duplicates unless strict", async () => {
+    await writeTestPost("taken");
+    const batch = [
+      { title: "Taken", content: "Clashes with a file" },
+      { title: "Fresh", content: "New" },
+      { title: "fresh", content: "Clashes within the batch" },
+    ];
+
+    const strict = await importPosts(batch, true);
+    expect(strict.status).toBe(409);
+    expect(hasPostFile("fresh")).toBe(false);
+
+    const res = await importPosts(batch);
+    expect(await res.json()).toEqual({
+      created: ["fresh"],
+      skipped: [
+        { title: "Taken", id: "taken", reason: "duplicate" },
+        { title: "fresh", id: "fresh", reason: "duplicate" },
+      ],
+    });
+    expect(hasPostFile("fresh")).toBe(true);
+  });
+
+  test("related posts rank more shared tags first", async () => {
+    await writeTestPost("rust-web", { tags: ["rust", "web", "wasm"] });
+    await writeTestPost("two-shared", {
+      tags: ["Rust", "web"],
+      createdAt: "2023-01-01T00:00:00Z",
+    });
+    await writeTestPost("one-shared", {
+      tags: ["rust"],
+      createdAt: "2025-01-01T00:00:00Z",
+    });
+    await writeTestPost("unrelated", { tags: ["cooking"] });
+    await writeTestPost("hidden", { tags: ["rust", "web"], draft: true });
+
+    const res = await request(app, "/blog/rust-web/related");
+    const related = (await res.json()).map((p: { id: string }) => p.id);
+    // An older post sharing two tags beats a newer one sharing one
+    expect(related).toEqual(["two-shared", "one-shared"]);
+  });
+
+  test("an edit based on a stale version is refused", async () => {
+    await writeTestPost("contested", { title: "Original" });
+    const first = await editPost("contested", { version: 1, title: "Mine" });
+    expect(await first.json()).toMatchObject({ version: 2 });
+
+    // A second editor still holding version 1
+    const stale = await editPost("contested", { version: 1, title: "Theirs" });
+    expect(stale.status).toBe(409);
+    expect((await stale.json()).error.code).toBe("version_conflict");
+
+    const current = await editPost("contested", { version: 2, title: "Both" });
+    expect(await current.json()).toMatchObject({ version: 3 });
+    expect((await getPost("contested")).blog.title).toBe("Both");
+  });
+
+  test("scheduled drafts are published once due", async () => {
+    const publishAt = "2030-06-01T12:00:00Z";
+    await writeTestPost("scheduled", { draft: true, publishAt });
+    await writeTestPost("later", {
+      draft: true,
+      publishAt: "2030-06-02T12:00:00Z",
+    });
+    await writeTestPost("unscheduled", { draft: true });
+
+    expect(await blog.publishScheduledPosts(new Date(publishAt))).toEqual([
+      "scheduled",
+    ]);
+    const posts = await blog.getBlogsFromFiles(true);
+    const drafts = posts.filter((p) => p.draft).map((p) => p.id).sort();
+    expect(drafts).toEqual(["later", "unscheduled"]);
+    expect(posts.find((p) => p.id === "scheduled")?.version).toBe(2);
+  });
+
+  test("a batch returns found posts in order and lists the rest", async () => {
+    await writeTestPost("batch-a");
+    await writeTestPost("batch-b");
+    await writeTestPost("batch-draft", { draft: true });
+
+    const batch = (ids: string[]) =>
+      request(app, "/blog/batch", { method: "POST", body: { ids } });
+    const res = await batch(["batch-b", "missing", "batch-a", "batch-b"]);
+    const body = await res.json();
+    expect(body.posts.map((p: { id: string }) => p.id)).toEqual([
+      "batch-b",
+      "batch-a",
+    ]);
+    expect(body.notFound).toEqual(["missing"]);
+    expect((await (await batch(["batch-draft"])).json()).notFound).toEqual([
+      "batch-draft",
+    ]);
+
+    // At most 50 IDs per request
+    const ids = (n: number) => Array.from({ length: n }, (_, i) => `p${i}`);
+    expect((await batch(ids(50))).status).toBe(200);
+    expect((await batch(ids(51))).status).toBe(422);
+  });
+
+  test("the preview renders exactly like the published post", async () => {
+    const markdown = [
+      "See [the docs](https://example.com).",
+      "",
+      "```js",
+      "const x = 1;",
+      "```",
+    ].join("\n");
+    const preview = (user?: TestUser) =>
+      request(app, "/blog/admin/preview", {
+        method: "POST",
+        user,
+        body: { markdownContent: markdown },
+      });
+    expect((await preview()).status).toBe(403);
+
+    const { content, snippet } = await (await preview(admin)).json();
+    expect(content).toContain(
+      '<p>See <a href="https://example.com">the docs</a>.</p>'
+    );
+    expect(content).toContain('<pre><code class="language-js">');
+    expect(content).toContain('<span class="token keyword">const</span>');
+    expect(snippet).toBe("See the docs.");
+
+    await writeTestPost("previewed", { content: markdown });
+    expect((await getPost("previewed")).blog.content).toBe(content);
+  });
+
+  test("admins see who liked and disliked a post", async () => {
+    await writeTestPost("reacted");
+    const [early, late, critic] = await Promise.all(
+      ["early", "late", "critic"].map((name) => createTestUser(name))
+    );
+    await react("reacted", early, "like");
+    await react("reacted", critic, "dislike");
+    await react("reacted", late, "like");
+
+    const reactions = (query = "", user = admin) =>
+      request(app, `/blog/admin/posts/reacted/reactions${query}`, { user });
+    const first = await (await reactions("?limit=1")).json();
+    expect(first).toMatchObject({ likes: 2, dislikes: 1 });
+    expect(first.likedBy.users).toEqual([
+      expect.objectContaining({ userId: late.id, name: "late" }),
+    ]);
+    expect(first.dislikedBy).toMatchObject({
+      users: [{ userId: critic.id, name: "critic" }],
+      nextCursor: null,
+    });
+
+    // The like list pages on its own cursor
+    const cursor = first.likedBy.nextCursor;
+    const second = await (
+      await reactions(`?limit=1&likesAfter=${cursor}`)
+    ).json();
+    expect(second.likedBy).toMatchObject({
+      users: [{ userId: early.id, name: "early" }],
+      nextCursor: null,
+    });
+
+    expect((await reactions("", early)).status).toBe(403);
+    const missing = await request(app, "/blog/admin/posts/nope/reactions", {
+      user: admin,
+    });
+    expect(missing.status).toBe(404);
+  });
+
+  test("the caller's reactions are looked up in bulk", async () => {
+    const reader = await createTestUser("bulk-reader");
+    const other = await createTestUser("bulk-other");
+    await react("bulk-liked", reader, "like");
+    await react("bulk-disliked", reader, "dislike");
+    await react("bulk-untouched", other, "like"); // Someone else's
+
+    const lookup = (ids: string[], user?: TestUser) =>
+      request(app, "/blog/reactions", { method: "POST", user, body: { ids } });
+    const res = await lookup(
+      ["bulk-liked", "bulk-disliked", "bulk-untouched"],
+      reader
+    );
+    expect(await res.json()).toEqual({
+      "bulk-liked": "like",
+      "bulk-disliked": "dislike",
+      "bulk-untouched": null,
+    });
+
+    expect((await lookup(["bulk-liked"])).status).toBe(401);
+    const ids = Array.from({ length: 101 }, (_, i) => `p${i}`);
+    expect((await lookup(ids, reader)).status).toBe(400);
+  });
+
+  test("concurrent reactions are all counted once", async () => {
+    await writeTestPost("popular");
+    const fans = await Promise.all(
+      Array.from({ length: 10 }, (_, i) => createTestUser(`fan${i}`))
+    );
+    const click = (user: TestUser, type: string) =>
+      request(app, "/blog/popular/reaction", {
+        method: "PATCH",
+        user,
+        body: { type },
+      });
+
+    const results = await Promise.all([
+      ...fans.map((fan) => click(fan, "like")),
+      click(fans[0], "dislike"), // Races the same user's like
+    ]);
+    // A click that keeps losing its race is refused, never half-applied
+    for (const res of results) expect([200, 409]).toContain(res.status);
+
+    const stored = await db
+      .collection("reactions")
+      .find({ blogId: "popular" })
+      .toArray();
+    const users = stored.map((r) => r.userId);
+    expect(new Set(users).size).toBe(users.length);
+    expect(users.sort()).toEqual(fans.map((fan) => fan.id).sort());
+
+    const { likes, dislikes } = (await getPost("popular")).blog;
+    expect(likes + dislikes).toBe(10);
+    expect(dislikes).toBe(stored.filter((r) => r.type === "dislike").length);
+  });
+});
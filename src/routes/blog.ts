@@ -5,13 +5,43 @@ import { marked } from "marked";
 import { markedHighlight } from "marked-highlight";
 import Prism from "prismjs";
 import { readdirSync, existsSync } from "node:fs";
+import { writeFile, unlink } from "node:fs/promises";
+import { createHash } from "node:crypto";
 import matter from "gray-matter";
 import { rateLimit } from "elysia-rate-limit";
 import { Collection, ObjectId } from "mongodb";
-import { Reaction, Comment } from "../db/models";
-import sanitizeHtml from "sanitize-html";
+import { Reaction, Comment, ReactionType } from "../db/models";
+import { ApiError, FieldErrors, handleApiError } from "../utils/errors";
+import {
+  PaginationQuerySchema,
+  paginate,
+  parseLimit,
+  decodeCursor,
+  encodeCursor,
+  mongoCursorFilter,
+  setPaginationHeaders,
+  MAX_PAGE_SIZE,
+} from "../utils/pagination";
+import {
+  validateParentComment,
+  removeComment,
+  buildCommentTree,
+  renderCommentContent,
+  withCommentReactions,
+  MAX_COMMENT_DEPTH,
+  MAX_COMMENT_LENGTH,
+} from "../utils/comments";
+import { toggleReaction } from "../utils/reactions";
+import { broadcastComment } from "../webhooks/comments";
+import { withIdempotency } from "../utils/idempotency";
+import { generateSnippet } from "../utils/snippet";
+import { parsePostDate } from "../utils/post-date";
+import { recordAdminAction } from "../utils/audit";
+import { etagFor, matchesEtag, notModified } from "../utils/etag";
+import { BLOGS_DIR, postPath } from "../utils/post-files";
 
-const { commentsCollection, reactionsCollection } = await connectToDatabase();
+const { commentsCollection, reactionsCollection, userCollection } =
+  await connectToDatabase();
 marked.use(
   markedHighlight({
     highlight: (code, lang) => {
@@ -21,17 +51,91 @@ marked.use(
   })
 );
 
-const getBlogsFromFiles = async () => {
-  const files = readdirSync("src/blogs").filter((f) => f.endsWith(".md"));
+// Bump when the marked setup changes so cached HTML is re-rendered
+const RENDERER_VERSION = 1;
+// Rendered posts kept in memory; 0 turns the cache off
+const POST_HTML_CACHE_SIZE = parseInt(
+  process.env.POST_HTML_CACHE_SIZE || "200"
+);
+
+// Keyed by a hash of the markdown, so edits (admin or by hand) miss it
+const postHtmlCache = new Map<string, string>();
+
+/**
+ * Renders post markdown to HTML with highlighted code blocks, as shown on
+ * the post page, in feeds and in admin previews. Results are cached unless
+ * `cache` is false (previews would otherwise push out real posts).
+ */
+export async function renderPostContent(markdown: string, cache = true) {
+  if (!cache || POST_HTML_CACHE_SIZE <= 0) return await marked(markdown);
+
+  const key = `${RENDERER_VERSION}:${createHash("sha256")
+    .update(markdown)
+    .digest("base64url")}`;
+  const cached = postHtmlCache.get(key);
+  if (cached !== undefined) {
+    // Re-insert so the least recently used entry is evicted first
+    postHtmlCache.delete(key);
+    postHtmlCache.set(key, cached);
+    return cached;
+  }
+
+  const html = await marked(markdown);
+  postHtmlCache.set(key, html);
+  if (postHtmlCache.size > POST_HTML_CACHE_SIZE) {
+    postHtmlCache.delete(postHtmlCache.keys().next().value!);
+  }
+  return html;
+}
+
+const POST_SCHEDULER_INTERVAL_MS = 60 * 1000;
+
+const postVersion = (data: Record<string, any>): number =>
+  Number.isInteger(data.version) ? data.version : 1;
+
+// Tags are matched case-insensitively, so they're stored lowercased
+const parseTags = (tags: unknown): string[] =>
+  Array.isArray(tags)
+    ? [
+        ...new Set(
+          tags
+            .filter((tag): tag is string => typeof tag === "string")
+            .map((tag) => tag.trim().toLowerCase())
+            .filter(Boolean)
+        ),
+      ]
+    : [];
+
+/**
+ * Whether readers can see a post: not a draft and, if it has a `publishAt`,
+ * that time has passed.
+ */
+export const isPublished = (
+  post: { draft: boolean; publishAt: Date | null },
+  now = new Date()
+) => !post.draft && (!post.publishAt || post.publishAt <= now);
+
+// Drafts and posts scheduled for later are hidden from readers
+export const getBlogsFromFiles = async (includeDrafts = false) => {
+  const files = readdirSync(BLOGS_DIR).filter((f) => f.endsWith(".md"));
   const blogs = [];
   for (const name of files) {
     const id = name.slice(0, -3);
-    const filePath = `src/blogs/${name}`;
+    const filePath = `${BLOGS_DIR}/${name}`;
     try {
       const file = Bun.file(filePath);
       const markdown = await file.text();
       const { data, content } = matter(markdown);
-      if (!data.title || !data.snippet || !data.createdAt || !data.updatedAt) {
+      const createdAt = parsePostDate(data.createdAt);
+      const updatedAt = parsePostDate(data.updatedAt);
+      const publishAt = data.publishAt ? parsePostDate(data.publishAt) : null;
+      if (
+        !data.title ||
+        !data.snippet ||
+        !createdAt ||
+        !updatedAt ||
+        (data.publishAt && !publishAt)
+      ) {
         console.warn(`Invalid frontmatter in ${filePath}`);
         continue;
       }
@@ -39,20 +143,29 @@ const getBlogsFromFiles = async () => {
         id,
         title: data.title,
         snippet: data.snippet,
-        createdAt: new Date(data.createdAt),
-        updatedAt: new Date(data.updatedAt),
+        createdAt,
+        updatedAt,
         markdownContent: content,
+        draft: data.draft === true,
+        featured: data.featured === true,
+        publishAt,
+        tags: parseTags(data.tags),
+        // Bumped on every admin edit, posts written by hand start at 1
+        version: postVersion(data),
       });
     } catch (error) {
       console.error(`Error reading ${filePath}:`, error);
       continue;
     }
   }
-  return blogs;
+  const now = new Date();
+  return includeDrafts ? blogs : blogs.filter((b) => isPublished(b, now));
 };
 
-const getBlogsWithCounts = async () => {
-  const blogs = await getBlogsFromFiles();
+// When `ids` is given only those posts are returned (and counted)
+const getBlogsWithCounts = async (ids?: string[]) => {
+  let blogs = await getBlogsFromFiles();
+  if (ids) blogs = blogs.filter((b) => ids.includes(b.id));
   const blogIds = blogs.map((b) => b.id);
   const reactions = await reactionsCollection
     .find({ blogId: { $in: blogIds } })
@@ -87,7 +200,262 @@ const BlogIndexSchema = t.Object({
   createdAt: t.Date(),
   updatedAt: t.Date(),
   markdownContent: t.String(),
+  tags: t.Array(t.String()),
+  featured: t.Boolean(),
+  version: t.Integer(),
+});
+
+const MAX_BATCH_POSTS = 50;
+const DEFAULT_RELATED_POSTS = 5;
+const MAX_RELATED_POSTS = 20;
+const MAX_IMPORT_POSTS = 100;
+const MAX_REACTION_LOOKUPS = 100;
+const MAX_TITLE_LENGTH = 200;
+const MAX_SNIPPET_LENGTH = 500;
+const MAX_POST_CONTENT_LENGTH = 200_000; // Characters of markdown
+const POST_ID_PATTERN = /^[\w-]+$/;
+
+// Edits to the same post run one at a time so version checks can't race
+const postLocks = new Map<string, Promise<unknown>>();
+
+function withPostLock<T>(id: string, fn: () => Promise<T>): Promise<T> {
+  const run = (postLocks.get(id) ?? Promise.resolve()).then(fn, fn);
+  const settled = run.catch(() => {});
+  postLocks.set(id, settled);
+  settled.then(() => {
+    if (postLocks.get(id) === settled) postLocks.delete(id);
+  });
+  return run;
+}
+
+const ReactorPageSchema = t.Object({
+  users: t.Array(
+    t.Object({
+      userId: t.String(),
+      name: t.Nullable(t.String()),
+      reactedAt: t.Date(),
+    })
+  ),
+  nextCursor: t.Nullable(t.String()),
+});
+
+/**
+ * One page of the users who gave a post a reaction of `type`, newest first,
+ * with names joined from the user collection.
+ */
+async function findReactors(
+  blogId: string,
+  type: ReactionType,
+  limit: number,
+  after?: string
+) {
+  let filter: any = { blogId, type };
+  if (after) {
+    const cursor = decodeCursor(after);
+    if (!ObjectId.isValid(cursor.id)) {
+      throw new ApiError(400, "Invalid cursor");
+    }
+    filter = {
+      ...filter,
+      ...mongoCursorFilter(cursor, (id) => new ObjectId(id)),
+    };
+  }
+
+  // Fetch one extra to know whether there is another page
+  const page = await reactionsCollection
+    .find(filter)
+    .sort({ createdAt: -1, _id: -1 })
+    .limit(limit + 1)
+    .toArray();
+  const reactions = page.slice(0, limit);
+
+  const userIds = reactions
+    .map((r) => r.userId)
+    .filter((id) => ObjectId.isValid(id))
+    .map((id) => new ObjectId(id));
+  const users = await userCollection
+    .find({ _id: { $in: userIds } }, { projection: { name: 1 } })
+    .toArray();
+  const names = new Map(users.map((u) => [u._id.toString(), u.name]));
+
+  const last = reactions[reactions.length - 1];
+  return {
+    users: reactions.map((r) => ({
+      userId: r.userId,
+      name: names.get(r.userId) ?? null,
+      reactedAt: r.createdAt,
+    })),
+    nextCursor:
+      page.length > limit && last
+        ? encodeCursor(last.createdAt, last._id!.toString())
+        : null,
+  };
+}
+
+const ImportedPostSchema = t.Object({
+  title: t.String({ minLength: 1, maxLength: MAX_TITLE_LENGTH }),
+  snippet: t.Optional(
+    t.String({
+      minLength: 1,
+      maxLength: MAX_SNIPPET_LENGTH,
+      description: "Generated from the content when omitted",
+    })
+  ),
+  content: t.String({
+    maxLength: MAX_POST_CONTENT_LENGTH,
+    description: "Markdown body",
+  }),
+  createdAt: t.Optional(t.String({ format: "date-time" })),
+  tags: t.Optional(t.Array(t.String())),
 });
+type ImportedPost = typeof ImportedPostSchema.static;
+
+// Post IDs are filenames, so imported titles become kebab-case slugs
+const slugify = (title: string) =>
+  title
+    .toLowerCase()
+    .normalize("NFKD")
+    .replace(/[^a-z0-9]+/g, "-")
+    .replace(/^-+|-+$/g, "");
+
+/**
+ * Writes imported posts as markdown files, see the import route for the
+ * duplicate and strict-mode rules.
+ */
+async function importPosts(posts: ImportedPost[], strict?: boolean) {
+  // Work out every post's ID first so strict mode can fail up front
+  const seen = new Set<string>();
+  const planned: { id: string; post: ImportedPost }[] = [];
+  const skipped: { title: string; id: string; reason: string }[] = [];
+  for (const post of posts) {
+    const id = slugify(post.title);
+    if (!id) {
+      skipped.push({ title: post.title, id, reason: "invalid_title" });
+    } else if (seen.has(id) || existsSync(postPath(id))) {
+      skipped.push({ title: post.title, id, reason: "duplicate" });
+    } else {
+      planned.push({ id, post });
+    }
+    seen.add(id);
+  }
+  if (strict && skipped.length > 0) {
+    throw new ApiError(
+      409,
+      `Import aborted, ${skipped.length} post(s) conflict: ${skipped
+        .map((s) => s.id || s.title)
+        .join(", ")}`
+    );
+  }
+
+  const created: string[] = [];
+  const now = new Date().toISOString();
+  try {
+    for (const { id, post } of planned) {
+      const createdAt = post.createdAt
+        ? new Date(post.createdAt).toISOString()
+        : now;
+      const markdown = matter.stringify(post.content, {
+        title: post.title,
+        snippet: post.snippet ?? generateSnippet(post.content),
+        createdAt,
+        updatedAt: createdAt,
+        ...(post.tags?.length && { tags: parseTags(post.tags) }),
+      });
+      try {
+        // "wx" refuses to overwrite a post created since the check above
+        await writeFile(postPath(id), markdown, { flag: "wx" });
+      } catch (error: any) {
+        if (error?.code !== "EEXIST") throw error;
+        if (strict) throw new ApiError(409, `Post ${id} already exists`);
+        skipped.push({ title: post.title, id, reason: "duplicate" });
+        continue;
+      }
+      created.push(id);
+    }
+  } catch (error) {
+    // All or nothing: remove what this request already wrote
+    await Promise.all(
+      created.map((id) => unlink(postPath(id)).catch(() => {}))
+    );
+    throw error;
+  }
+
+  return { created, skipped };
+}
+
+/**
+ * Publishes drafts whose `publishAt` has passed by clearing their draft flag.
+ * Returns the IDs of the posts published.
+ */
+export async function publishScheduledPosts(now = new Date()) {
+  const due = (await getBlogsFromFiles(true)).filter(
+    (b) => b.draft && b.publishAt && b.publishAt <= now
+  );
+
+  const published: string[] = [];
+  for (const { id } of due) {
+    const filePath = postPath(id);
+    await withPostLock(id, async () => {
+      // Re-read in case an admin changed the post since it was listed
+      const { data, content } = matter(await Bun.file(filePath).text());
+      const publishAt = parsePostDate(data.publishAt);
+      if (data.draft !== true || !publishAt || publishAt > now) return;
+
+      await Bun.write(
+        filePath,
+        matter.stringify(content, {
+          ...data,
+          draft: false,
+          version: postVersion(data) + 1,
+        })
+      );
+      published.push(id);
+    }).catch((error) => {
+      console.error(`Error publishing scheduled post ${id}:`, error);
+    });
+  }
+
+  if (published.length > 0) {
+    console.log(`Published scheduled posts: ${published.join(", ")}`);
+  }
+  return published;
+}
+
+/**
+ * Marks `id` as the featured post and unmarks any other, or clears the
+ * featured post when `id` is null. Returns false if the post doesn't exist.
+ */
+async function setFeaturedPost(id: string | null) {
+  // Not a valid post ID, so this never queues behind a post's own edits
+  return withPostLock(":featured", async () => {
+    const posts = await getBlogsFromFiles(true);
+    if (id !== null && !posts.some((b) => b.id === id)) return false;
+
+    for (const post of posts) {
+      const featured = post.id === id;
+      if (post.featured === featured) continue;
+
+      const filePath = postPath(post.id);
+      await withPostLock(post.id, async () => {
+        const { data, content } = matter(await Bun.file(filePath).text());
+        if (featured) data.featured = true;
+        else delete data.featured;
+        await Bun.write(
+          filePath,
+          matter.stringify(content, {
+            ...data,
+            version: postVersion(data) + 1,
+          })
+        );
+      });
+    }
+    return true;
+  });
+}
+
+export function startPostScheduler() {
+  return setInterval(() => publishScheduledPosts(), POST_SCHEDULER_INTERVAL_MS);
+}
 
 const BlogDetailSchema = t.Object({
   id: t.String(),
@@ -99,93 +467,139 @@ const BlogDetailSchema = t.Object({
   commentCount: t.Number(),
   createdAt: t.Date(),
   updatedAt: t.Date(),
+  tags: t.Array(t.String()),
+  featured: t.Boolean(),
+  version: t.Integer(),
 });
 
-const CommentSchema = t.Object({
+export const CommentSchema = t.Object({
   _id: t.Optional(t.String()),
   blogId: t.String(),
   authorId: t.String(),
   content: t.String(),
   accepted: t.Boolean(),
   createdAt: t.Date(),
+  parentId: t.Optional(t.Nullable(t.String())),
+  deleted: t.Optional(t.Boolean()),
+  editedAt: t.Optional(t.Date()),
+  likes: t.Optional(t.Number()),
+  dislikes: t.Optional(t.Number()),
+  userReaction: t.Optional(
+    t.Nullable(t.Union([t.Literal("like"), t.Literal("dislike")]))
+  ),
 });
 
+const CommentThreadSchema = t.Recursive((Self) =>
+  t.Object({
+    ...CommentSchema.properties,
+    replies: t.Array(Self),
+  })
+);
+
 export const blogRoutes = new Elysia({ prefix: "/blog" })
   .derive(async ({ request: { headers } }) => {
     const session = await auth.api.getSession({ headers }).catch(() => null);
     return { currentUser: session?.user };
   })
-  .onError(({ error: err, set }) => {
-    if (err instanceof Error) {
-      if (err.message === "Unauthorized") {
-        set.status = 401;
-        return { message: "Unauthorized" };
-      }
-      if (err.message === "Blog post not found") {
-        set.status = 404;
-        return { message: "Blog post not found" };
-      }
-      if (err.message === "Comment content is required") {
-        set.status = 400;
-        return { message: "Comment content is required" };
-      }
-      if (err.message === "Forbidden: Admin access required") {
-        set.status = 403;
-        return { message: "Forbidden: Admin access required" };
-      }
-      if (err.message === "Comment ID is required") {
-        set.status = 400;
-        return { message: "Comment ID is required" };
-      }
-      if (err.message === "Invalid comment ID format") {
-        set.status = 400;
-        return { message: "Invalid comment ID format" };
-      }
-      if (err.message === "Comment not found") {
-        set.status = 404;
-        return { message: "Comment not found" };
-      }
-      if (err.message === "Comment is already accepted") {
-        set.status = 400;
-        return { message: "Comment is already accepted" };
-      }
-      if (err.message === "Comment is already denied") {
-        set.status = 400;
-        return { message: "Comment is already denied" };
-      }
-      if (err.message === "Action must be 'accept', 'deny', or 'delete'") {
-        set.status = 400;
-        return { message: "Action must be 'accept', 'deny', or 'delete'" };
-      }
-      console.error("Unhandled error:", err.message);
-    }
-    set.status = 500;
-    return { message: "Internal server error" };
-  })
+  .onError(handleApiError)
   .get(
     "/",
-    async () => {
-      return await getBlogsWithCounts();
+    async ({ query, set, request }) => {
+      const blogs = await getBlogsWithCounts();
+      if (!query.limit && !query.after && !query.offset) {
+        return blogs;
+      }
+
+      // Paged: newest first, with the next page's cursor in a header so the
+      // response body keeps the same shape
+      blogs.sort(
+        (a, b) =>
+          b.createdAt.getTime() - a.createdAt.getTime() ||
+          (b.id < a.id ? -1 : b.id > a.id ? 1 : 0)
+      );
+      const { items, nextCursor } = paginate(blogs, query);
+      if (nextCursor) set.headers["x-next-cursor"] = nextCursor;
+      setPaginationHeaders(
+        { request, set },
+        { total: blogs.length, limit: parseLimit(query.limit), nextCursor }
+      );
+      return items;
     },
     {
+      query: PaginationQuerySchema,
       response: t.Array(BlogIndexSchema),
       detail: {
         summary: "Get all blog posts with comment counts",
         tags: ["blogs"],
+        description:
+          "Returns every post when called without parameters. Pass `limit` to page newest first; the `X-Next-Cursor` response header holds the cursor for the next page, passed back as `after`. Cursor paging is preferred over `offset`, which can repeat or skip posts added while paging.",
       },
     }
   )
   .model({ BlogIndexSchema: BlogIndexSchema })
+  .post(
+    "/batch",
+    async ({ body: { ids } }) => {
+      const unique = [...new Set(ids)];
+      const found = new Map(
+        (await getBlogsWithCounts(unique)).map((b) => [b.id, b])
+      );
+
+      // Keep the order the ids were requested in
+      return {
+        posts: unique.flatMap((id) => found.get(id) ?? []),
+        notFound: unique.filter((id) => !found.has(id)),
+      };
+    },
+    {
+      body: t.Object({
+        ids: t.Array(t.String(), {
+          minItems: 1,
+          maxItems: MAX_BATCH_POSTS,
+          description: `Post IDs to fetch, at most ${MAX_BATCH_POSTS}`,
+        }),
+      }),
+      response: t.Object({
+        posts: t.Array(BlogIndexSchema),
+        notFound: t.Array(t.String()),
+      }),
+      detail: {
+        summary: "Get several blog posts by ID",
+        tags: ["blogs"],
+        description:
+          "Returns the requested posts (with reaction and comment counts) in the order asked for, plus the IDs that don't match a published post. Duplicate IDs are ignored. At most 50 IDs per request.",
+      },
+    }
+  )
+  .get(
+    "/featured",
+    async () => {
+      const featured = (await getBlogsFromFiles()).find((b) => b.featured);
+      if (!featured) throw new ApiError(404, "No featured post");
+
+      const [blog] = await getBlogsWithCounts([featured.id]);
+      return blog;
+    },
+    {
+      response: BlogIndexSchema,
+      detail: {
+        summary: "Get the featured blog post",
+        tags: ["blogs"],
+        description:
+          "Returns the post an admin has featured, with reaction and comment counts. 404 when no post is featured or the featured post is a draft or scheduled for later.",
+      },
+    }
+  )
   .get(
     "/:id",
-    async ({ params: { id } }) => {
+    async ({ params: { id }, currentUser, request, set }) => {
       const blogs = await getBlogsFromFiles();
       const blog = blogs.find((b) => b.id === id);
       if (!blog) {
-        throw new Error("Blog post not found");
+        throw new ApiError(404, "Blog post not found");
       }
 
-      const content = await marked(blog.markdownContent);
+      const content = await renderPostContent(blog.markdownContent);
 
       const reactions = await reactionsCollection
         .find({ blogId: id })
@@ -194,7 +608,10 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
       const dislikes = reactions.filter((r) => r.type === "dislike").length;
 
       const comments = (
-        await commentsCollection.find({ blogId: id }).toArray()
+        await withCommentReactions(
+          await commentsCollection.find({ blogId: id }).toArray(),
+          currentUser?.id
+        )
       ).map((c) => ({
         ...c,
         _id: c._id.toString(),
@@ -209,7 +626,14 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
         commentCount,
       };
 
-      return { blog: blogWithCounts, comments };
+      // Tagged on the whole body since comments and counts change too
+      const result = { blog: blogWithCounts, comments };
+      const etag = etagFor(result);
+      if (matchesEtag(request.headers.get("if-none-match"), etag)) {
+        return notModified(etag);
+      }
+      set.headers["etag"] = etag;
+      return result;
     },
     {
       params: t.Object({
@@ -225,10 +649,162 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
       detail: {
         summary: "Get blog post by ID with its comments",
         tags: ["blogs"],
+        description:
+          "Sends an `ETag` covering the whole response. Repeat requests with it in `If-None-Match` get an empty 304 while the post, its counts and its comments are unchanged.",
       },
     }
   )
   .model({ BlogDetailSchema: BlogDetailSchema })
+  .get(
+    "/:id/related",
+    async ({ params: { id }, query }) => {
+      const blogs = await getBlogsFromFiles();
+      const blog = blogs.find((b) => b.id === id);
+      if (!blog) {
+        throw new ApiError(404, "Blog post not found");
+      }
+
+      const limit = Math.min(
+        parseInt(query.limit || String(DEFAULT_RELATED_POSTS)) ||
+          DEFAULT_RELATED_POSTS,
+        MAX_RELATED_POSTS
+      );
+      const tags = new Set(blog.tags);
+      const related = blogs
+        .filter((b) => b.id !== id)
+        .map((b) => ({
+          id: b.id,
+          createdAt: b.createdAt,
+          shared: b.tags.filter((tag) => tags.has(tag)).length,
+        }))
+        .filter((b) => b.shared > 0)
+        .sort(
+          (a, b) =>
+            b.shared - a.shared || b.createdAt.getTime() - a.createdAt.getTime()
+        )
+        .slice(0, Math.max(1, limit))
+        .map((b) => b.id);
+
+      const found = new Map(
+        (await getBlogsWithCounts(related)).map((b) => [b.id, b])
+      );
+      return related.flatMap((relatedId) => found.get(relatedId) ?? []);
+    },
+    {
+      params: t.Object({
+        id: t.String({
+          description: "Blog post ID (filename without .md)",
+        }),
+      }),
+      query: t.Object({
+        limit: t.Optional(
+          t.String({ description: `Posts to return, max ${MAX_RELATED_POSTS}` })
+        ),
+      }),
+      response: t.Array(BlogIndexSchema),
+      detail: {
+        summary: "Get posts related to a blog post by shared tags",
+        tags: ["blogs"],
+        description: `Returns up to \`limit\` (default ${DEFAULT_RELATED_POSTS}) other published posts sharing at least one tag with the post, the most shared tags first and then newest first. Tags are compared case-insensitively. Empty when the post has no tags.`,
+      },
+    }
+  )
+  .get(
+    "/:id/comments",
+    async ({ params: { id }, query, currentUser, request, set }) => {
+      const limit = parseLimit(query.limit);
+
+      const asTree = query.format === "tree";
+
+      // In tree mode pages are made of top-level comments with their replies
+      let filter: any = { blogId: id };
+      if (asTree) filter.parentId = null;
+      const countFilter = filter;
+      if (query.after) {
+        const cursor = decodeCursor(query.after);
+        if (!ObjectId.isValid(cursor.id)) {
+          throw new ApiError(400, "Invalid cursor");
+        }
+        filter = {
+          ...filter,
+          ...mongoCursorFilter(cursor, (id) => new ObjectId(id)),
+        };
+      }
+
+      let find = commentsCollection
+        .find(filter)
+        .sort({ createdAt: -1, _id: -1 });
+      if (!query.after && query.offset) {
+        find = find.skip(Math.max(0, parseInt(query.offset) || 0));
+      }
+      // Fetch one extra to know whether there is another page
+      const [page, total] = await Promise.all([
+        find.limit(limit + 1).toArray(),
+        commentsCollection.countDocuments(countFilter),
+      ]);
+
+      const hasMore = page.length > limit;
+      const comments = await withCommentReactions(
+        page.slice(0, limit),
+        currentUser?.id
+      );
+      const last = comments[comments.length - 1];
+      const nextCursor =
+        hasMore && last
+          ? encodeCursor(last.createdAt, last._id.toString())
+          : null;
+      setPaginationHeaders(
+        { request, set },
+        { total, limit, nextCursor }
+      );
+
+      if (asTree) {
+        const replies = await withCommentReactions(
+          await commentsCollection
+            .find({ blogId: id, parentId: { $ne: null } })
+            .toArray(),
+          currentUser?.id
+        );
+        return { comments: buildCommentTree(comments, replies), nextCursor };
+      }
+
+      return {
+        comments: comments.map((c) => ({ ...c, _id: c._id.toString() })),
+        nextCursor,
+      };
+    },
+    {
+      params: t.Object({
+        id: t.String({
+          description: "Blog post ID (filename without .md)",
+        }),
+      }),
+      query: t.Composite([
+        PaginationQuerySchema,
+        t.Object({
+          format: t.Optional(
+            t.Union([t.Literal("flat"), t.Literal("tree")], {
+              description: "Flat list with parent IDs (default) or nested",
+            })
+          ),
+        }),
+      ]),
+      response: t.Object({
+        comments: t.Union([
+          t.Array(CommentSchema),
+          t.Array(CommentThreadSchema),
+        ]),
+        nextCursor: t.Nullable(t.String()),
+      }),
+      detail: {
+        summary: "Get a page of comments on a blog post, newest first",
+        tags: ["blogs", "comments"],
+        description: `Pass \`nextCursor\` back as \`after\` to fetch the next page. Cursor paging is preferred over \`offset\`, which can repeat or skip comments posted while paging.
+
+With \`format=tree\`, each page holds top-level comments with their replies nested under \`replies\` (oldest first, up to ${MAX_COMMENT_DEPTH} levels deep). Deleted comments that still have replies are returned with \`deleted: true\` and empty content.`,
+      },
+    }
+  )
   .use(
     rateLimit({
       duration: 120_000, // 2 minutes (in ms) - adjust as needed
@@ -246,34 +822,59 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
   )
   .post(
     "/:id/comment",
-    async ({ params: { id }, body: { content }, currentUser }) => {
+    async ({
+      params: { id },
+      body: { content, parentId },
+      currentUser,
+      request: { headers },
+      set,
+    }) => {
       if (!currentUser) {
-        throw new Error("Unauthorized");
+        throw new ApiError(401, "Unauthorized");
       }
-      if (
-        !content ||
-        typeof content !== "string" ||
-        content.trim().length === 0
-      ) {
-        throw new Error("Comment content is required");
+      const errors = new FieldErrors();
+      if (!content?.trim()) errors.add("content", "Content is required");
+      if (content?.length > MAX_COMMENT_LENGTH) {
+        errors.add(
+          "content",
+          `Content must be at most ${MAX_COMMENT_LENGTH} characters`
+        );
       }
+      errors.throwIfAny();
 
-      if (!existsSync(`src/blogs/${id}.md`)) {
-        throw new Error("Blog post not found");
+      if (!existsSync(postPath(id))) {
+        throw new ApiError(404, "Blog post not found");
       }
 
-      const renderedContent = await marked(content.trim());
-      const sanitizedContent = sanitizeHtml(renderedContent);
+      if (parentId) {
+        await validateParentComment(id, parentId);
+      }
 
-      const { insertedId } = await commentsCollection.insertOne({
-        blogId: id,
-        authorId: currentUser.id,
-        content: sanitizedContent,
-        accepted: false,
-        createdAt: new Date(),
-      });
+      return withIdempotency(
+        { headers, set, userId: currentUser.id, scope: `comment:${id}` },
+        async () => {
+          const sanitizedContent = await renderCommentContent(content);
+
+          const comment = {
+            blogId: id,
+            authorId: currentUser.id,
+            content: sanitizedContent,
+            accepted: false,
+            createdAt: new Date(),
+            parentId: parentId ?? null,
+          };
+          const { insertedId } = await commentsCollection.insertOne(comment);
+
+          broadcastComment("comment", {
+            ...comment,
+            _id: insertedId.toString(),
+            likes: 0,
+            dislikes: 0,
+          });
 
-      return { success: true, commentId: insertedId.toString() };
+          return { success: true, commentId: insertedId.toString() };
+        }
+      );
     },
     {
       params: t.Object({
@@ -283,8 +884,11 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
       }),
       body: t.Object({
         content: t.String({
-          description: "Comment content",
+          description: `Comment content, max ${MAX_COMMENT_LENGTH} characters`,
         }),
+        parentId: t.Optional(
+          t.String({ description: "ID of the comment being replied to" })
+        ),
       }),
       response: t.Object({
         success: t.Boolean(),
@@ -294,6 +898,47 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
         summary: "Add a comment to a blog post (unpublished by default)",
         tags: ["blogs", "comments"],
         security: [{ session: [] }],
+        description:
+          "Send an `Idempotency-Key` header to make retries safe: repeating a request with the same key within 24 hours returns the first response (marked with `Idempotent-Replayed: true`) instead of posting the comment again.",
+      },
+    }
+  )
+  .post(
+    "/reactions",
+    async ({ body: { ids }, currentUser }) => {
+      if (!currentUser) {
+        throw new ApiError(401, "Unauthorized");
+      }
+      if (ids.length > MAX_REACTION_LOOKUPS) {
+        throw new ApiError(
+          400,
+          `At most ${MAX_REACTION_LOOKUPS} post IDs can be looked up at once`
+        );
+      }
+
+      const reactions = await reactionsCollection
+        .find({ blogId: { $in: ids }, userId: currentUser.id })
+        .toArray();
+      const byPost = new Map(reactions.map((r) => [r.blogId, r.type]));
+
+      return Object.fromEntries(ids.map((id) => [id, byPost.get(id) ?? null]));
+    },
+    {
+      body: t.Object({
+        ids: t.Array(t.String(), {
+          description: `Post IDs, at most ${MAX_REACTION_LOOKUPS}`,
+        }),
+      }),
+      response: t.Record(
+        t.String(),
+        t.Nullable(t.Union([t.Literal("like"), t.Literal("dislike")]))
+      ),
+      detail: {
+        summary: "Get the current user's reactions on several blog posts",
+        tags: ["blogs", "reactions"],
+        security: [{ session: [] }],
+        description:
+          "Maps each requested post ID to `like`, `dislike` or null (no reaction), so the frontend doesn't need to call `/:id/reaction` per post. More than 100 IDs is a 400.",
       },
     }
   )
@@ -301,7 +946,7 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
     "/:id/reaction",
     async ({ params: { id }, currentUser }) => {
       if (!currentUser) {
-        throw new Error("Unauthorized");
+        throw new ApiError(401, "Unauthorized");
       }
 
       const reaction = await reactionsCollection.findOne({
@@ -336,48 +981,21 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
     "/:id/reaction",
     async ({ params: { id }, body: { type }, currentUser }) => {
       if (!currentUser) {
-        throw new Error("Unauthorized");
+        throw new ApiError(401, "Unauthorized");
       }
 
-      const filePath = `src/blogs/${id}.md`;
+      const filePath = postPath(id);
       const file = Bun.file(filePath);
       if (!(await file.exists())) {
-        throw new Error("Blog post not found");
+        throw new ApiError(404, "Blog post not found");
       }
 
-      const existingReaction = await reactionsCollection.findOne({
-        blogId: id,
-        userId: currentUser.id,
-      });
-
-      let operation: any;
-
-      if (!existingReaction) {
-        // Insert new reaction
-        operation = {
-          insertOne: {
-            document: {
-              blogId: id,
-              userId: currentUser.id,
-              type,
-              createdAt: new Date(),
-            },
-          },
-        };
-      } else if (existingReaction.type === type) {
-        // Toggle off
-        operation = { deleteOne: { filter: { _id: existingReaction._id } } };
-      } else {
-        // Switch type
-        operation = {
-          updateOne: {
-            filter: { _id: existingReaction._id },
-            update: { $set: { type } },
-          },
-        };
-      }
-
-      await reactionsCollection.bulkWrite([operation]);
+      await toggleReaction(
+        reactionsCollection,
+        { blogId: id },
+        currentUser.id,
+        type
+      );
 
       return { success: true };
     },
@@ -406,7 +1024,7 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
       // Check if user is admin
       if (!currentUser?.role?.includes("admin")) {
         set.status = 403;
-        throw new Error("Forbidden: Admin access required");
+        throw new ApiError(403, "Forbidden: Admin access required");
       }
 
       const pendingComments = await commentsCollection
@@ -434,17 +1052,17 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
       // Check if user is admin
       if (!currentUser?.role?.includes("admin")) {
         set.status = 403;
-        throw new Error("Forbidden: Admin access required");
+        throw new ApiError(403, "Forbidden: Admin access required");
       }
 
       if (!commentId) {
         set.status = 400;
-        throw new Error("Comment ID is required");
+        throw new ApiError(400, "Comment ID is required");
       }
 
       if (!action || !["accept", "deny", "delete"].includes(action)) {
         set.status = 400;
-        throw new Error("Action must be 'accept', 'deny', or 'delete'");
+        throw new ApiError(400, "Action must be 'accept', 'deny', or 'delete'");
       }
 
       let parsedCommentId: ObjectId;
@@ -452,7 +1070,7 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
         parsedCommentId = new ObjectId(commentId);
       } catch (error) {
         set.status = 400;
-        throw new Error("Invalid comment ID format");
+        throw new ApiError(400, "Invalid comment ID format");
       }
 
       const comment = await commentsCollection.findOne({
@@ -461,7 +1079,7 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
 
       if (!comment) {
         set.status = 404;
-        throw new Error("Comment not found");
+        throw new ApiError(404, "Comment not found");
       }
 
       let updateOperation: any;
@@ -471,7 +1089,7 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
         case "accept":
           if (comment.accepted) {
             set.status = 400;
-            throw new Error("Comment is already accepted");
+            throw new ApiError(400, "Comment is already accepted");
           }
           updateOperation = { $set: { accepted: true } };
           success = true;
@@ -480,21 +1098,27 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
         case "deny":
           if (!comment.accepted) {
             set.status = 400;
-            throw new Error("Comment is already denied");
+            throw new ApiError(400, "Comment is already denied");
           }
           updateOperation = { $set: { accepted: false } };
           success = true;
           break;
 
-        case "delete":
-          const deleteResult = await commentsCollection.deleteOne({
-            _id: parsedCommentId,
-          });
-          return { success: deleteResult.deletedCount === 1 };
+        case "delete": {
+          // Comments with replies are tombstoned to keep the thread intact
+          const removed = await removeComment(parsedCommentId);
+          if (removed) {
+            await recordAdminAction(currentUser.id, "comment.delete", {
+              type: "comment",
+              id: commentId,
+            });
+          }
+          return { success: removed };
+        }
 
         default:
           set.status = 400;
-          throw new Error("Invalid action");
+          throw new ApiError(400, "Invalid action");
       }
 
       if (updateOperation) {
@@ -505,9 +1129,18 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
 
         if (result.matchedCount === 0) {
           set.status = 404;
-          throw new Error("Comment not found");
+          throw new ApiError(404, "Comment not found");
         }
 
+        broadcastComment("comment_updated", {
+          ...comment,
+          ...updateOperation.$set,
+          _id: comment._id.toString(),
+        });
+        await recordAdminAction(currentUser.id, `comment.${action}`, {
+          type: "comment",
+          id: commentId,
+        });
         return { success };
       }
 
@@ -541,10 +1174,298 @@ export const blogRoutes = new Elysia({ prefix: "/blog" })
 Admin moderation actions for comments:
 - **accept**: Sets comment accepted=true, makes it visible to all users
 - **deny**: Sets comment accepted=false, hides it from public view
-- **delete**: Permanently removes the comment from database
+- **delete**: Permanently removes the comment from database (comments with replies are tombstoned instead)
 
 Requires admin authentication. Only comments that need moderation will be visible to admins.
         `,
       },
     }
+  )
+  .put(
+    "/admin/posts/:id",
+    async ({ params: { id }, body, currentUser }) => {
+      if (!currentUser?.role?.includes("admin")) {
+        throw new ApiError(403, "Forbidden: Admin access required");
+      }
+
+      const filePath = postPath(id);
+      if (!POST_ID_PATTERN.test(id) || !existsSync(filePath)) {
+        throw new ApiError(404, "Blog post not found");
+      }
+
+      const errors = new FieldErrors();
+      if (body.title !== undefined && !body.title.trim()) {
+        errors.add("title", "Title cannot be empty");
+      }
+      if (body.title && body.title.length > MAX_TITLE_LENGTH) {
+        errors.add(
+          "title",
+          `Title must be at most ${MAX_TITLE_LENGTH} characters`
+        );
+      }
+      if (body.snippet !== undefined && !body.snippet.trim()) {
+        errors.add("snippet", "Snippet cannot be empty");
+      }
+      if (body.snippet && body.snippet.length > MAX_SNIPPET_LENGTH) {
+        errors.add(
+          "snippet",
+          `Snippet must be at most ${MAX_SNIPPET_LENGTH} characters`
+        );
+      }
+      if (body.markdownContent !== undefined && !body.markdownContent.trim()) {
+        errors.add("markdownContent", "Content cannot be empty");
+      }
+      if (
+        body.markdownContent &&
+        body.markdownContent.length > MAX_POST_CONTENT_LENGTH
+      ) {
+        errors.add(
+          "markdownContent",
+          `Content must be at most ${MAX_POST_CONTENT_LENGTH} characters`
+        );
+      }
+      if (body.publishAt && isNaN(new Date(body.publishAt).getTime())) {
+        errors.add("publishAt", "Must be a valid date");
+      }
+      errors.throwIfAny();
+
+      return withPostLock(id, async () => {
+        const { data, content } = matter(await Bun.file(filePath).text());
+        const version = postVersion(data);
+        if (body.version !== version) {
+          throw new ApiError(
+            409,
+            `Post was changed by someone else (now at version ${version})`,
+            "version_conflict"
+          );
+        }
+
+        const updatedAt = new Date();
+        if (body.publishAt === null) delete data.publishAt;
+        else if (body.publishAt !== undefined) {
+          data.publishAt = new Date(body.publishAt).toISOString();
+        }
+        const frontmatter = {
+          ...data,
+          ...(body.title !== undefined && { title: body.title }),
+          ...(body.snippet !== undefined && { snippet: body.snippet }),
+          ...(body.draft !== undefined && { draft: body.draft }),
+          updatedAt: updatedAt.toISOString(),
+          version: version + 1,
+        };
+        await Bun.write(
+          filePath,
+          matter.stringify(body.markdownContent ?? content, frontmatter)
+        );
+        await recordAdminAction(
+          currentUser.id,
+          "post.edit",
+          { type: "post", id },
+          { version: version + 1 }
+        );
+
+        return { id, version: version + 1, updatedAt };
+      });
+    },
+    {
+      params: t.Object({
+        id: t.String({
+          description: "Blog post ID (filename without .md)",
+        }),
+      }),
+      body: t.Object({
+        version: t.Integer({
+          description: "The post's version when the editor loaded it",
+        }),
+        title: t.Optional(t.String()),
+        snippet: t.Optional(t.String()),
+        markdownContent: t.Optional(t.String()),
+        draft: t.Optional(t.Boolean()),
+        publishAt: t.Optional(
+          t.Nullable(
+            t.String({
+              format: "date-time",
+              description: "When to publish a draft, null to unschedule",
+            })
+          )
+        ),
+      }),
+      response: t.Object({
+        id: t.String(),
+        version: t.Integer(),
+        updatedAt: t.Date(),
+      }),
+      detail: {
+        summary: "Edit a blog post - Admin only",
+        tags: ["admin", "blogs"],
+        security: [{ session: [] }],
+        description:
+          "Updates the post's title, snippet, markdown, draft flag and/or `publishAt` and bumps its `version`. A draft with a `publishAt` is published automatically once that time passes, and readers never see a post before its `publishAt`. `version` must be the version the editor last saw; if the post has been edited since, nothing is written and a 409 `version_conflict` is returned so the client can reload and reconcile.",
+      },
+    }
+  )
+  .put(
+    "/admin/featured",
+    async ({ body: { id }, currentUser }) => {
+      if (!currentUser?.role?.includes("admin")) {
+        throw new ApiError(403, "Forbidden: Admin access required");
+      }
+      if (!(await setFeaturedPost(id))) {
+        throw new ApiError(404, "Blog post not found");
+      }
+      await recordAdminAction(
+        currentUser.id,
+        id ? "post.feature" : "post.unfeature",
+        id ? { type: "post", id } : null
+      );
+      return { id };
+    },
+    {
+      body: t.Object({
+        id: t.Nullable(
+          t.String({ description: "Post to feature, null to clear it" })
+        ),
+      }),
+      response: t.Object({ id: t.Nullable(t.String()) }),
+      detail: {
+        summary: "Set the featured blog post - Admin only",
+        tags: ["admin", "blogs"],
+        security: [{ session: [] }],
+        description:
+          "Features one post, unfeaturing whichever post was featured before, or clears the featured post when `id` is null. Drafts can be featured ahead of time and appear at `GET /blog/featured` once published. Each post whose flag changes has its `version` bumped.",
+      },
+    }
+  )
+  .get(
+    "/admin/posts/:id/reactions",
+    async ({ params: { id }, query, currentUser }) => {
+      if (!currentUser?.role?.includes("admin")) {
+        throw new ApiError(403, "Forbidden: Admin access required");
+      }
+      if (!POST_ID_PATTERN.test(id) || !existsSync(postPath(id))) {
+        throw new ApiError(404, "Blog post not found");
+      }
+
+      const limit = parseLimit(query.limit);
+      const [likes, dislikes, likedBy, dislikedBy] = await Promise.all([
+        reactionsCollection.countDocuments({ blogId: id, type: "like" }),
+        reactionsCollection.countDocuments({ blogId: id, type: "dislike" }),
+        findReactors(id, "like", limit, query.likesAfter),
+        findReactors(id, "dislike", limit, query.dislikesAfter),
+      ]);
+
+      return { likes, dislikes, likedBy, dislikedBy };
+    },
+    {
+      params: t.Object({
+        id: t.String({
+          description: "Blog post ID (filename without .md)",
+        }),
+      }),
+      query: t.Object({
+        limit: t.Optional(
+          t.String({ description: `Users per list, max ${MAX_PAGE_SIZE}` })
+        ),
+        likesAfter: t.Optional(
+          t.String({
+            description: "`likedBy.nextCursor` from a previous page",
+          })
+        ),
+        dislikesAfter: t.Optional(
+          t.String({
+            description: "`dislikedBy.nextCursor` from a previous page",
+          })
+        ),
+      }),
+      response: t.Object({
+        likes: t.Number(),
+        dislikes: t.Number(),
+        likedBy: ReactorPageSchema,
+        dislikedBy: ReactorPageSchema,
+      }),
+      detail: {
+        summary: "See who reacted to a blog post - Admin only",
+        tags: ["admin", "blogs", "reactions"],
+        security: [{ session: [] }],
+        description:
+          "Returns like and dislike counts plus the users in each bucket, newest reaction first. Each list is paged separately: pass its `nextCursor` back as `likesAfter` or `dislikesAfter`. Drafts are included; unknown posts return 404.",
+      },
+    }
+  )
+  .post(
+    "/admin/preview",
+    async ({ body: { markdownContent }, currentUser }) => {
+      if (!currentUser?.role?.includes("admin")) {
+        throw new ApiError(403, "Forbidden: Admin access required");
+      }
+
+      return {
+        content: await renderPostContent(markdownContent, false),
+        snippet: generateSnippet(markdownContent),
+      };
+    },
+    {
+      body: t.Object({
+        markdownContent: t.String({ maxLength: MAX_POST_CONTENT_LENGTH }),
+      }),
+      response: t.Object({
+        content: t.String({ description: "Rendered HTML" }),
+        snippet: t.String({ description: "Snippet generated on import" }),
+      }),
+      detail: {
+        summary: "Preview a post's markdown rendering - Admin only",
+        tags: ["admin", "blogs"],
+        security: [{ session: [] }],
+        description:
+          "Renders markdown exactly as a published post's `content` is rendered (including code highlighting), along with the snippet an import would generate from it. Nothing is saved.",
+      },
+    }
+  )
+  .post(
+    "/admin/posts/import",
+    async ({
+      body: { posts, strict },
+      currentUser,
+      request: { headers },
+      set,
+    }) => {
+      if (!currentUser?.role?.includes("admin")) {
+        throw new ApiError(403, "Forbidden: Admin access required");
+      }
+
+      return withIdempotency(
+        { headers, set, userId: currentUser.id, scope: "import-posts" },
+        async () => {
+          const result = await importPosts(posts, strict);
+          await recordAdminAction(currentUser.id, "post.import", null, {
+            created: result.created,
+          });
+          return result;
+        }
+      );
+    },
+    {
+      body: t.Object({
+        posts: t.Array(ImportedPostSchema, {
+          minItems: 1,
+          maxItems: MAX_IMPORT_POSTS,
+        }),
+        strict: t.Optional(
+          t.Boolean({ description: "Abort the whole import on any conflict" })
+        ),
+      }),
+      response: t.Object({
+        created: t.Array(t.String(), { description: "IDs of new posts" }),
+        skipped: t.Array(
+          t.Object({ title: t.String(), id: t.String(), reason: t.String() })
+        ),
+      }),
+      detail: {
+        summary: "Import blog posts in bulk - Admin only",
+        tags: ["admin", "blogs"],
+        security: [{ session: [] }],
+        description:
+          "Creates up to 100 published posts from JSON, using a slug of each title as its ID. A missing `snippet` is generated from the first 160 characters of the content's plain text, cut at a word boundary. Posts whose slug is already taken (or repeated in the batch) are listed in `skipped` and the rest are created; with `strict: true` any conflict aborts the import with a 409 and nothing is written. If writing fails part-way, the posts already written by the request are removed. Supports an `Idempotency-Key` header like comment creation.",
+      },
+    }
   );
@@ -0,0 +1,205 @@
+import { afterEach, beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import type { Namespace } from "socket.io";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { ConnectOptions, FakeNamespace } from "../test-utils/sockets";
+import { DEFAULT_CHESS_RATING } from "../utils/elo";
+
+describeWithDb("/chess", () => {
+  let db: Db;
+  let app: TestApp;
+  let ana: TestUser;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    ana = await createTestUser("ana");
+  });
+
+  const player = (user: TestUser | string) =>
+    typeof user === "string"
+      ? { userId: `id-${user}`, username: user }
+      : { userId: user.id, username: user.name };
+
+  let endedAt = Date.parse("2024-01-01T00:00:00Z");
+  const addGame = (
+    white: TestUser | string,
+    black: TestUser | string,
+    winner: "white" | "black" | null,
+    moves = ["e4", "e5"]
+  ) =>
+    db.collection("chess_games").insertOne({
+      white: player(white),
+      black: player(black),
+      winner,
+      reason: winner ? "resignation" : "agreement",
+      moves,
+      finalFen: "8/8/8/8/8/8/8/8 w - - 0 1",
+      timeControl: 300,
+      isPrivate: false,
+      startedAt: new Date(endedAt),
+      createdAt: new Date((endedAt += 60_000)),
+    });
+
+  const history = async (query: string) => {
+    const res = await request(app, `/chess/history/ana?${query}`);
+    const body = await res.json();
+    return {
+      ...body,
+      opponents: body.games.map((g: any) =>
+        g.white.username === "ana" ? g.black.username : g.white.username
+      ),
+    };
+  };
+
+  test("history filters by result and colour", async () => {
+    await addGame(ana, "bo", "white"); // Win as white
+    await addGame("cy", ana, "white"); // Loss as black
+    await addGame("di", ana, null); // Draw as black
+    await addGame(ana, "ed", "black"); // Loss as white
+
+    expect((await history("result=loss")).opponents).toEqual(["ed", "cy"]);
+    const asBlack = await history("color=black");
+    expect(asBlack.opponents).toEqual(["di", "cy"]);
+    expect(asBlack.totals).toEqual({ wins: 0, losses: 1, draws: 1 });
+
+    // The result filter doesn't change the totals
+    const both = await history("color=black&result=loss");
+    expect(both.opponents).toEqual(["cy"]);
+    expect(both.totals).toEqual(asBlack.totals);
+  });
+
+  test("stats tally a player's games and ratings", async () => {
+    const eve = await createTestUser("eve");
+    await db
+      .collection("user")
+      .updateOne(
+        { _id: new ObjectId(eve.id) },
+        { $set: { chessRating: 1300, chessPeakRating: 1400 } }
+      );
+    await addGame(eve, "fay", "white", ["e4", "e5", "Nf3"]);
+    await addGame("fay", eve, "white", ["d4", "Nf6"]);
+    await addGame(eve, "gus", null, ["e4", "c5", "Nf3", "d6"]);
+    await addGame(eve, "gus", "white", ["e4", "e6"]);
+
+    const res = await request(app, "/chess/stats/eve");
+    expect(await res.json()).toEqual({
+      totalGames: 4,
+      wins: 2,
+      losses: 1,
+      draws: 1,
+      winRate: 0.5,
+      averageMoves: 1.5, // 2 + 1 + 2 + 1 full moves
+      favoriteFirstMove: "e4",
+      favoriteOpening: null,
+      rating: 1300,
+      peakRating: 1400,
+    });
+    expect((await request(app, "/chess/stats/nobody")).status).toBe(404);
+  });
+});
+
+// Routes that report on the games held by the chess socket
+describeWithDb("/chess live games", () => {
+  let app: TestApp;
+  let chess: typeof import("../webhooks/chess");
+  let nsp: FakeNamespace;
+  let kim: TestUser;
+  let lee: TestUser;
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    app = await createTestApp();
+    chess = await import("../webhooks/chess");
+    nsp = new FakeNamespace("/sockets/chess");
+    chess.setupChess(nsp as unknown as Namespace);
+    kim = await createTestUser("kim");
+    lee = await createTestUser("lee");
+  });
+
+  afterEach(() => {
+    chess.shutdownChess();
+    nsp.disconnectAll();
+  });
+
+  const connect = (user: TestUser, query: ConnectOptions["query"] = {}) =>
+    nsp.connect({ auth: { token: user.token }, query });
+
+  const get = async (path: string, user?: TestUser) =>
+    (await request(app, `/chess${path}`, { user })).json();
+
+  test("/open-games lists a waiting player until they're paired", async () => {
+    await connect(kim, { color: "black" });
+    expect(await get("/open-games")).toEqual([
+      {
+        userId: kim.id,
+        username: "kim",
+        rating: DEFAULT_CHESS_RATING,
+        colorPreference: "black",
+        waitingSince: expect.any(String),
+      },
+    ]);
+
+    await connect(lee);
+    expect(await get("/open-games")).toEqual([]);
+  });
+
+  test("/active lists the caller's game in progress", async () => {
+    const white = await connect(kim, { color: "white" });
+    const black = await connect(lee, { color: "black" });
+    await white.send("bid", { time: 300 });
+    await black.send("bid", { time: 300 });
+    await white.send("move", { move: "e4" });
+
+    expect(await get("/active", kim)).toEqual([
+      {
+        gameId: white.data.gameId,
+        color: "white",
+        opponent: { userId: lee.id, username: "lee", isBot: false },
+        phase: "playing",
+        turn: "black",
+        fen: black.last("update").fen,
+        whiteTime: 300,
+        blackTime: 300,
+        isPrivate: false,
+      },
+    ]);
+    expect((await request(app, "/chess/active")).status).toBe(401);
+  });
+
+  test("cancelling a search drops the player from /open-games", async () => {
+    const socket = await connect(kim);
+    expect((await get("/open-games")).map((p: any) => p.userId)).toEqual([
+      kim.id,
+    ]);
+
+    await socket.send("cancel_search");
+    expect(socket.last("search_cancelled")).toEqual({ reason: "aborted" });
+    expect(socket.connected).toBe(false);
+    expect(await get("/open-games")).toEqual([]);
+
+    // Nobody is left for the next player to be paired with
+    const next = await connect(lee);
+    expect(next.received("paired")).toEqual([]);
+  });
+
+  test("/online shows connected players until they leave", async () => {
+    const waiting = await connect(kim);
+    await connect(lee, { vs: "bot" });
+    expect(await get("/online")).toEqual([
+      { userId: kim.id, username: "kim", status: "waiting" },
+      { userId: lee.id, username: "lee", status: "playing" },
+    ]);
+
+    waiting.drop();
+    expect(await get("/online")).toEqual([
+      { userId: lee.id, username: "lee", status: "playing" },
+    ]);
+  });
+});
@@ -0,0 +1,390 @@
+import { Elysia, t } from "elysia";
+import { auth } from "../auth";
+import {
+  createInvite,
+  listOpenGames,
+  listUserGames,
+  listOnlinePlayers,
+} from "../webhooks/chess";
+import { PublicUserSchema } from "./profile";
+import { ApiError, handleApiError } from "../utils/errors";
+import {
+  PaginationQuerySchema,
+  parseLimit,
+  decodeCursor,
+  encodeCursor,
+  setPaginationHeaders,
+} from "../utils/pagination";
+import {
+  findChessHistory,
+  countChessResults,
+  countChessHistory,
+  findChessGame,
+  getChessStats,
+  HistoryFilters,
+} from "../utils/chess-history";
+import { toPgn } from "../utils/chess-pgn";
+import { resolveStartPosition, STANDARD_START } from "../utils/chess";
+import { connectToDatabase } from "../db/connect";
+import {
+  getChessLeaderboard,
+  getChessRatings,
+  DEFAULT_CHESS_RATING,
+} from "../utils/profile";
+
+const { userCollection } = await connectToDatabase();
+
+const MAX_RATING_LOOKUPS = 100;
+
+const SideSchema = t.Union([t.Literal("white"), t.Literal("black")]);
+
+const VariantSchema = t.Union([t.Literal("standard"), t.Literal("custom")]);
+
+const OpeningSchema = t.Object({
+  eco: t.String({ description: "ECO code, e.g. B20" }),
+  name: t.String(),
+});
+
+const MoveTimeSchema = t.Object({
+  thinkMs: t.Number({ description: "How long the mover took" }),
+  clock: t.Number({ description: "Mover's seconds left after the move" }),
+});
+
+const ChessGameSchema = t.Object({
+  id: t.String(),
+  white: t.Object({ userId: t.String(), username: t.String() }),
+  black: t.Object({ userId: t.String(), username: t.String() }),
+  winner: t.Nullable(t.Union([t.Literal("white"), t.Literal("black")])),
+  reason: t.String(),
+  moves: t.Array(t.String()),
+  moveTimes: t.Nullable(
+    t.Array(MoveTimeSchema, { description: "One per move, null if unknown" })
+  ),
+  opening: t.Nullable(OpeningSchema),
+  variant: VariantSchema,
+  initialFen: t.String(),
+  finalFen: t.String(),
+  timeControl: t.Number(),
+  isPrivate: t.Boolean(),
+  startedAt: t.Date(),
+  endedAt: t.Date(),
+});
+
+function parseDate(value: string | undefined, name: string) {
+  if (!value) return undefined;
+  const date = new Date(value);
+  if (isNaN(date.getTime())) {
+    throw new ApiError(400, `Invalid ${name} date`);
+  }
+  return date;
+}
+
+export const chessRoutes = new Elysia({ prefix: "/chess" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers }).catch(() => null);
+    return { currentUser: session?.user };
+  })
+  .onError(handleApiError)
+  .get(
+    "/leaderboard",
+    async ({ query }) => {
+      const limit = Math.min(parseInt(query.limit || "50") || 50, 100);
+      return await getChessLeaderboard(limit);
+    },
+    {
+      query: t.Object({
+        limit: t.Optional(t.String()),
+      }),
+      response: t.Array(PublicUserSchema),
+      detail: {
+        summary: "Get the top chess players ranked by Elo rating",
+        tags: ["chess", "leaderboards"],
+        description:
+          "Returns public profiles of players who have a chess rating, highest first. Defaults to 50 players, max 100.",
+      },
+    }
+  )
+  .post(
+    "/ratings",
+    ({ body: { usernames } }) => getChessRatings([...new Set(usernames)]),
+    {
+      body: t.Object({
+        usernames: t.Array(t.String(), {
+          minItems: 1,
+          maxItems: MAX_RATING_LOOKUPS,
+          description: `Usernames to look up, at most ${MAX_RATING_LOOKUPS}`,
+        }),
+      }),
+      response: t.Array(
+        t.Object({
+          username: t.String(),
+          rating: t.Nullable(t.Number({ description: "Null if unknown" })),
+          rank: t.Nullable(
+            t.Number({ description: "Leaderboard position, ties share one" })
+          ),
+        })
+      ),
+      detail: {
+        summary: "Get several players' chess ratings and ranks",
+        tags: ["chess", "leaderboards"],
+        description:
+          "Returns the current rating and leaderboard rank for each username, in the order asked for (duplicates removed). Unknown usernames get a null rating and rank; players who have never been rated get the default rating and a null rank. At most 100 usernames per request.",
+      },
+    }
+  )
+  .get("/online", () => listOnlinePlayers(), {
+    response: t.Array(
+      t.Object({
+        userId: t.String(),
+        username: t.String(),
+        status: t.Union([
+          t.Literal("playing"),
+          t.Literal("waiting"),
+          t.Literal("spectating"),
+          t.Literal("idle"),
+        ]),
+      })
+    ),
+    detail: {
+      summary: "List players connected to the chess server",
+      tags: ["chess"],
+      description:
+        "Returns every signed-in user currently connected to the chess socket, sorted by username, with whether they're `playing`, `waiting` for an opponent, `spectating` or `idle` (for example after a game ended). Clients that stop answering heartbeats are dropped automatically.",
+    },
+  })
+  .get("/open-games", () => listOpenGames(), {
+    response: t.Array(
+      t.Object({
+        userId: t.String(),
+        username: t.String(),
+        rating: t.Number(),
+        colorPreference: t.Union([
+          t.Literal("white"),
+          t.Literal("black"),
+          t.Literal("random"),
+        ]),
+        waitingSince: t.Date(),
+      })
+    ),
+    detail: {
+      summary: "List players waiting for a chess opponent",
+      tags: ["chess"],
+      description:
+        "Returns everyone in the public matchmaking queue with their rating and color preference, longest-waiting first. Connect to the chess socket to be paired; players in private invite games are not listed.",
+    },
+  })
+  .get(
+    "/active",
+    ({ currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+      return listUserGames(currentUser.id);
+    },
+    {
+      response: t.Array(
+        t.Object({
+          gameId: t.String(),
+          color: SideSchema,
+          opponent: t.Object({
+            userId: t.String(),
+            username: t.String(),
+            isBot: t.Boolean(),
+          }),
+          phase: t.Union([t.Literal("bidding"), t.Literal("playing")]),
+          turn: SideSchema,
+          fen: t.String(),
+          whiteTime: t.Number({ description: "Seconds left" }),
+          blackTime: t.Number({ description: "Seconds left" }),
+          isPrivate: t.Boolean(),
+        })
+      ),
+      detail: {
+        summary: "List the current user's chess games in progress",
+        tags: ["chess"],
+        security: [{ session: [] }],
+        description:
+          "Returns every unfinished game the caller is playing, with their color, opponent, whose turn it is and the current position. Empty when there are none. Connecting to the chess socket resumes the game.",
+      },
+    }
+  )
+  .post(
+    "/invite",
+    ({ body, currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      let start;
+      try {
+        start = resolveStartPosition(body?.variant, body?.fen);
+      } catch (error) {
+        throw new ApiError(400, (error as Error).message);
+      }
+      return createInvite(currentUser.id, start);
+    },
+    {
+      body: t.Optional(
+        t.Object({
+          variant: t.Optional(VariantSchema),
+          fen: t.Optional(
+            t.String({ description: "Starting FEN for the custom variant" })
+          ),
+        })
+      ),
+      response: t.Object({
+        code: t.String(),
+        expiresAt: t.Date(),
+        variant: VariantSchema,
+        fen: t.String({ description: "Position the game will start from" }),
+      }),
+      detail: {
+        summary: "Create a private chess game invite",
+        tags: ["chess"],
+        description:
+          "Returns a join code for a private game. Share it with a friend; both players connect to the chess socket with `?invite=<code>` and are paired with each other only. Codes expire after 10 minutes if unused. An optional `variant` picks the starting position: `standard` (default) or `custom` with a `fen`. Invalid or already finished positions are rejected with a 400.",
+      },
+    }
+  )
+  .get(
+    "/stats/:username",
+    async ({ params: { username } }) => {
+      const user = await userCollection.findOne(
+        { name: username },
+        { projection: { chessRating: 1, chessPeakRating: 1 } }
+      );
+      if (!user) throw new ApiError(404, "User not found");
+
+      const rating = user.chessRating ?? DEFAULT_CHESS_RATING;
+      return {
+        ...(await getChessStats(user._id.toString())),
+        rating,
+        // Peak ratings are only tracked from the first rated game onwards
+        peakRating: Math.max(rating, user.chessPeakRating ?? rating),
+      };
+    },
+    {
+      params: t.Object({
+        username: t.String({ description: "Player's username" }),
+      }),
+      response: t.Object({
+        totalGames: t.Number(),
+        wins: t.Number(),
+        losses: t.Number(),
+        draws: t.Number(),
+        winRate: t.Number({ description: "Wins as a fraction of all games" }),
+        averageMoves: t.Number({ description: "Average full moves per game" }),
+        favoriteFirstMove: t.Nullable(
+          t.String({ description: "The player's most common first move (SAN)" })
+        ),
+        favoriteOpening: t.Nullable(
+          t.Composite([OpeningSchema, t.Object({ games: t.Number() })], {
+            description: "The opening the player's games most often reach",
+          })
+        ),
+        rating: t.Number(),
+        peakRating: t.Number(),
+      }),
+      detail: {
+        summary: "Get a player's chess statistics",
+        tags: ["chess"],
+        description:
+          "Summarises the player's recorded games (games against the engine are not recorded) along with their current and peak rating.",
+      },
+    }
+  )
+  .get(
+    "/history/:username",
+    async ({ params: { username }, query, request, set }) => {
+      const limit = parseLimit(query.limit);
+      const filters: HistoryFilters = {
+        result: query.result,
+        color: query.color,
+        from: parseDate(query.from, "from"),
+        to: parseDate(query.to, "to"),
+      };
+
+      const [page, totals, total] = await Promise.all([
+        findChessHistory(username, filters, {
+          limit,
+          cursor: query.after ? decodeCursor(query.after) : undefined,
+          offset: Math.max(0, parseInt(query.offset || "0") || 0),
+        }),
+        countChessResults(username, filters),
+        countChessHistory(username, filters),
+      ]);
+
+      const games = page.slice(0, limit);
+      const last = games[games.length - 1];
+      const nextCursor =
+        page.length > limit && last
+          ? encodeCursor(last.createdAt, last._id.toString())
+          : null;
+      setPaginationHeaders({ request, set }, { total, limit, nextCursor });
+
+      return {
+        games: games.map(({ _id, createdAt, ...game }) => ({
+          ...game,
+          opening: game.opening ?? null,
+          moveTimes: game.moveTimes ?? null,
+          variant: game.variant ?? "standard",
+          initialFen: game.initialFen ?? STANDARD_START.fen,
+          id: _id.toString(),
+          endedAt: createdAt,
+        })),
+        totals,
+        nextCursor,
+      };
+    },
+    {
+      params: t.Object({
+        username: t.String({ description: "Player's username" }),
+      }),
+      query: t.Composite([
+        PaginationQuerySchema,
+        t.Object({
+          result: t.Optional(
+            t.Union([t.Literal("win"), t.Literal("loss"), t.Literal("draw")])
+          ),
+          color: t.Optional(t.Union([t.Literal("white"), t.Literal("black")])),
+          from: t.Optional(
+            t.String({ description: "Only games ended at or after (ISO)" })
+          ),
+          to: t.Optional(
+            t.String({ description: "Only games ended at or before (ISO)" })
+          ),
+        }),
+      ]),
+      response: t.Object({
+        games: t.Array(ChessGameSchema),
+        totals: t.Object({
+          wins: t.Number(),
+          losses: t.Number(),
+          draws: t.Number(),
+        }),
+        nextCursor: t.Nullable(t.String()),
+      }),
+      detail: {
+        summary: "Get a player's finished chess games, newest first",
+        tags: ["chess"],
+        description:
+          "Filters by the player's `result`, their `color` and an end date range. `totals` counts wins, losses and draws matching the colour and date filters (the result filter is ignored so all three stay meaningful). Pass `nextCursor` back as `after` for the next page. Games against the engine are not recorded.",
+      },
+    }
+  )
+  .get(
+    "/games/:id/pgn",
+    async ({ params: { id }, set }) => {
+      const game = await findChessGame(id);
+      if (!game) throw new ApiError(404, "Game not found");
+
+      set.headers["content-type"] = "application/x-chess-pgn; charset=utf-8";
+      set.headers["content-disposition"] = `attachment; filename="${id}.pgn"`;
+      return toPgn(game);
+    },
+    {
+      params: t.Object({ id: t.String({ description: "Recorded game ID" }) }),
+      detail: {
+        summary: "Download a recorded chess game as PGN",
+        tags: ["chess"],
+        description:
+          "Returns the game's PGN with players, result, time control and opening headers. Games that recorded move times give every move a `{[%clk h:mm:ss] [%emt h:mm:ss.s]}` comment with the mover's clock after the move and how long they thought.",
+      },
+    }
+  );
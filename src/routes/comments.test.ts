@@ -0,0 +1,112 @@
+import { beforeAll, expect, test } from "bun:test";
+import type { Db, ObjectId } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+
+describeWithDb("/comments", () => {
+  let db: Db;
+  let app: TestApp;
+  let author: TestUser;
+  let stranger: TestUser;
+  let admin: TestUser;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    author = await createTestUser("author");
+    stranger = await createTestUser("stranger");
+    admin = await createTestUser("moderator", { role: "admin" });
+  });
+
+  const addComment = async () => {
+    const { insertedId } = await db.collection("comments").insertOne({
+      blogId: "example",
+      authorId: author.id,
+      content: "<p>Original</p>",
+      accepted: true,
+      createdAt: new Date(),
+      parentId: null,
+    });
+    return insertedId;
+  };
+
+  const edit = (id: ObjectId, user: TestUser, content: string) =>
+    request(app, `/comments/${id}`, {
+      method: "PUT",
+      user,
+      body: { content },
+    });
+
+  const stored = (id: ObjectId) =>
+    db.collection("comments").findOne({ _id: id });
+
+  test("the author can edit, which sends it back to moderation", async () => {
+    const id = await addComment();
+    const res = await edit(id, author, "**Updated**");
+    expect(res.status).toBe(200);
+
+    const comment = await stored(id);
+    expect(comment?.content).toContain("<strong>Updated</strong>");
+    expect(comment?.editedAt).toBeInstanceOf(Date);
+    expect(comment?.accepted).toBe(false);
+  });
+
+  test("someone else can't edit it", async () => {
+    const id = await addComment();
+    expect((await edit(id, stranger, "Mine now")).status).toBe(403);
+    expect((await stored(id))?.content).toBe("<p>Original</p>");
+  });
+
+  test("an admin can edit it and it stays published", async () => {
+    const id = await addComment();
+    expect((await edit(id, admin, "Tidied up")).status).toBe(200);
+
+    const comment = await stored(id);
+    expect(comment?.content).toContain("Tidied up");
+    expect(comment?.accepted).toBe(true);
+  });
+
+  test("like and dislike toggle the user's reaction", async () => {
+    const id = await addComment();
+    const react = (type: "like" | "dislike", user = stranger) =>
+      request(app, `/comments/${id}/${type}`, { method: "POST", user });
+    const counts = async (type: "like" | "dislike", user?: TestUser) =>
+      (await react(type, user)).json();
+
+    expect(await counts("like")).toEqual({
+      likes: 1,
+      dislikes: 0,
+      userReaction: "like",
+    });
+    expect(await counts("dislike", admin)).toMatchObject({
+      likes: 1,
+      dislikes: 1,
+      userReaction: "dislike",
+    });
+    // Switching, then clicking the same reaction again to remove it
+    expect(await counts("dislike")).toEqual({
+      likes: 0,
+      dislikes: 2,
+      userReaction: "dislike",
+    });
+    expect(await counts("dislike")).toEqual({
+      likes: 0,
+      dislikes: 1,
+      userReaction: null,
+    });
+
+    const anonymous = await request(app, `/comments/${id}/like`, {
+      method: "POST",
+    });
+    expect(anonymous.status).toBe(401);
+    await db
+      .collection("comments")
+      .updateOne({ _id: id }, { $set: { deleted: true } });
+    expect((await react("like")).status).toBe(404);
+  });
+});
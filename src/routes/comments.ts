@@ -0,0 +1,194 @@
+import { Elysia, t } from "elysia";
+import { ObjectId } from "mongodb";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { ApiError, FieldErrors, handleApiError } from "../utils/errors";
+import { broadcastComment } from "../webhooks/comments";
+import {
+  MAX_COMMENT_LENGTH,
+  removeComment,
+  renderCommentContent,
+  withCommentReactions,
+} from "../utils/comments";
+import { toggleReaction } from "../utils/reactions";
+import { ReactionType } from "../db/models";
+import { CommentSchema } from "./blog";
+
+const { commentsCollection, commentReactionsCollection } =
+  await connectToDatabase();
+
+const CommentParamsSchema = t.Object({
+  id: t.String({ description: "Comment ID (ObjectId string)" }),
+});
+
+/**
+ * Loads a comment the current user may change: its author or an admin.
+ */
+async function findOwnComment(
+  id: string,
+  currentUser: { id: string; role?: string | null } | undefined
+) {
+  if (!currentUser) throw new ApiError(401, "Unauthorized");
+  if (!ObjectId.isValid(id)) {
+    throw new ApiError(400, "Invalid comment ID format");
+  }
+
+  const comment = await commentsCollection.findOne({ _id: new ObjectId(id) });
+  if (!comment || comment.deleted) {
+    throw new ApiError(404, "Comment not found");
+  }
+
+  const isAdmin = !!currentUser.role?.includes("admin");
+  if (comment.authorId !== currentUser.id && !isAdmin) {
+    throw new ApiError(403, "Forbidden: You can only change your own comments");
+  }
+
+  return { comment, isAdmin };
+}
+
+const ReactionSummarySchema = t.Object({
+  likes: t.Number(),
+  dislikes: t.Number(),
+  userReaction: t.Nullable(
+    t.Union([t.Literal("like"), t.Literal("dislike")])
+  ),
+});
+
+/**
+ * Toggles the current user's reaction on a comment and returns the new
+ * counts.
+ */
+async function reactToComment(
+  id: string,
+  userId: string | undefined,
+  type: ReactionType
+) {
+  if (!userId) throw new ApiError(401, "Unauthorized");
+  if (!ObjectId.isValid(id)) {
+    throw new ApiError(400, "Invalid comment ID format");
+  }
+
+  const comment = await commentsCollection.findOne({ _id: new ObjectId(id) });
+  if (!comment || comment.deleted) {
+    throw new ApiError(404, "Comment not found");
+  }
+
+  await toggleReaction(
+    commentReactionsCollection,
+    { commentId: id },
+    userId,
+    type
+  );
+
+  const [{ likes, dislikes, userReaction }] = await withCommentReactions(
+    [comment],
+    userId
+  );
+  return { likes, dislikes, userReaction };
+}
+
+const reactionDetail = (type: ReactionType) => ({
+  summary: `Toggle a ${type} on a comment`,
+  tags: ["comments", "reactions"],
+  security: [{ session: [] }],
+  description: `Adds a ${type}, removes it if the user already ${type}d the comment, or switches an existing reaction to a ${type}. Returns the comment's updated counts.`,
+});
+
+export const commentsRoutes = new Elysia({ prefix: "/comments" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers }).catch(() => null);
+    return { currentUser: session?.user };
+  })
+  .onError(handleApiError)
+  .put(
+    "/:id",
+    async ({ params: { id }, body: { content }, currentUser }) => {
+      const { comment, isAdmin } = await findOwnComment(id, currentUser);
+
+      const errors = new FieldErrors();
+      if (!content.trim()) errors.add("content", "Content is required");
+      if (content.length > MAX_COMMENT_LENGTH) {
+        errors.add(
+          "content",
+          `Content must be at most ${MAX_COMMENT_LENGTH} characters`
+        );
+      }
+      errors.throwIfAny();
+
+      const editedAt = new Date();
+      const update = {
+        content: await renderCommentContent(content),
+        editedAt,
+        // Edits by the author go back through moderation
+        accepted: isAdmin ? comment.accepted : false,
+      };
+
+      await commentsCollection.updateOne(
+        { _id: comment._id },
+        { $set: update }
+      );
+
+      const [updated] = await withCommentReactions(
+        [{ ...comment, ...update }],
+        currentUser!.id
+      );
+      const { userReaction, ...forReaders } = updated;
+      broadcastComment("comment_updated", {
+        ...forReaders,
+        _id: updated._id.toString(),
+      });
+      return { ...updated, _id: updated._id.toString() };
+    },
+    {
+      params: CommentParamsSchema,
+      body: t.Object({
+        content: t.String({ description: "New comment content (markdown)" }),
+      }),
+      response: CommentSchema,
+      detail: {
+        summary: "Edit a comment",
+        tags: ["comments"],
+        security: [{ session: [] }],
+        description:
+          "Replaces the content of a comment and sets `editedAt`. Only the comment's author or an admin may edit it. Edits by the author unpublish the comment until it is accepted again.",
+      },
+    }
+  )
+  .delete(
+    "/:id",
+    async ({ params: { id }, currentUser }) => {
+      const { comment } = await findOwnComment(id, currentUser);
+      return { success: await removeComment(comment._id) };
+    },
+    {
+      params: CommentParamsSchema,
+      response: t.Object({ success: t.Boolean() }),
+      detail: {
+        summary: "Delete a comment",
+        tags: ["comments"],
+        security: [{ session: [] }],
+        description:
+          "Deletes a comment. Only the comment's author or an admin may delete it. Comments with replies are tombstoned (content removed, `deleted: true`) so the thread stays intact.",
+      },
+    }
+  )
+  .post(
+    "/:id/like",
+    ({ params: { id }, currentUser }) =>
+      reactToComment(id, currentUser?.id, "like"),
+    {
+      params: CommentParamsSchema,
+      response: ReactionSummarySchema,
+      detail: reactionDetail("like"),
+    }
+  )
+  .post(
+    "/:id/dislike",
+    ({ params: { id }, currentUser }) =>
+      reactToComment(id, currentUser?.id, "dislike"),
+    {
+      params: CommentParamsSchema,
+      response: ReactionSummarySchema,
+      detail: reactionDetail("dislike"),
+    }
+  );
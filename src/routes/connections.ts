@@ -5,6 +5,7 @@ import {
   connectionsNamespace,
 } from "../webhooks/connections";
 import { auth } from "../auth";
+import { ApiError, handleApiError } from "../utils/errors";
 
 export interface NotificationEventData {
   type?: "info" | "success" | "warning" | "error";
@@ -48,6 +49,8 @@ export const UserDataSchema = t.Union([
     age: t.Optional(t.Union([t.Number(), t.Null()])),
     chessWins: t.Optional(t.Union([t.Number(), t.Null()])),
     chessLosses: t.Optional(t.Union([t.Number(), t.Null()])),
+    chessRating: t.Optional(t.Union([t.Number(), t.Null()])),
+    chessPeakRating: t.Optional(t.Union([t.Number(), t.Null()])),
     draughtsWins: t.Optional(t.Union([t.Number(), t.Null()])),
     draughtsLosses: t.Optional(t.Union([t.Number(), t.Null()])),
     arithmeticScore: t.Optional(t.Union([t.Number(), t.Null()])),
@@ -55,6 +58,9 @@ export const UserDataSchema = t.Union([
     // Verification fields
     verifiedName: t.Optional(t.Union([t.String(), t.Null()])),
     verifiedImage: t.Optional(t.Union([t.String(), t.Null()])),
+    // Self-managed profile fields
+    displayName: t.Optional(t.Union([t.String(), t.Null()])),
+    bio: t.Optional(t.Union([t.String(), t.Null()])),
     // Ban fields from admin plugin
     banned: t.Optional(t.Union([t.Boolean(), t.Null()])),
     role: t.Optional(t.Union([t.String(), t.Null()])),
@@ -113,33 +119,15 @@ export const connectionsRoutes = new Elysia({ prefix: "/connections" })
     beforeHandle: async ({ request: { headers } }) => {
       const session = await auth.api.getSession({ headers });
       if (!session) {
-        throw new Error("Unauthorized");
+        throw new ApiError(401, "Unauthorized: Authentication required");
       }
 
       if (!session.user.role?.includes("admin")) {
-        throw new Error("Forbidden: Admin access required");
+        throw new ApiError(403, "Forbidden: Admin access required");
       }
     },
   })
-  .onError(({ error: err, set }) => {
-    if (err instanceof Error) {
-      if (err.message === "Unauthorized") {
-        set.status = 401;
-        return { message: "Unauthorized: Authentication required" };
-      }
-      if (err.message === "Forbidden: Admin access required") {
-        set.status = 403;
-        return { message: "Forbidden: Admin access required" };
-      }
-      if (err.message === "Connection not found") {
-        set.status = 404;
-        return { message: "Connection not found" };
-      }
-      console.error("Unhandled error:", err.message);
-    }
-    set.status = 500;
-    return { message: "Internal server error" };
-  })
+  .onError(handleApiError)
   .get(
     "/",
     ({}) => ({
@@ -172,7 +160,7 @@ export const connectionsRoutes = new Elysia({ prefix: "/connections" })
 
       const socket = connectionsNamespace.sockets.get(socketId);
       if (!socket) {
-        throw new Error("Connection not found");
+        throw new ApiError(404, "Connection not found");
       }
 
       socket.emit(event, data);
@@ -203,7 +191,7 @@ export const connectionsRoutes = new Elysia({ prefix: "/connections" })
     ({ body: { socketId } }) => {
       const socket = connectionsNamespace.sockets.get(socketId);
       if (!socket) {
-        throw new Error("Connection not found");
+        throw new ApiError(404, "Connection not found");
       }
 
       socket.disconnect(true);
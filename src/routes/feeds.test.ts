@@ -0,0 +1,52 @@
+import { beforeAll, expect, test } from "bun:test";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import { describeWithDb, resetTestDatabase } from "../test-utils/db";
+import { writeTestPost } from "../test-utils/posts";
+
+describeWithDb("/feed.json", () => {
+  let app: TestApp;
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    app = await createTestApp();
+  });
+
+  test("lists the 20 newest published posts as JSON Feed 1.1", async () => {
+    // post-1 is the oldest, post-21 the newest
+    for (let i = 1; i <= 21; i++) {
+      const createdAt = new Date(Date.UTC(2024, 0, i)).toISOString();
+      await writeTestPost(`post-${i}`, {
+        createdAt,
+        tags: i === 21 ? ["news"] : [],
+      });
+    }
+    await writeTestPost("unreleased", {
+      draft: true,
+      createdAt: "2025-01-01T00:00:00Z",
+    });
+
+    const res = await request(app, "/feed.json");
+    expect(res.headers.get("content-type")).toStartWith(
+      "application/feed+json"
+    );
+    const feed = await res.json();
+    expect(feed).toMatchObject({
+      version: "https://jsonfeed.org/version/1.1",
+      home_page_url: "http://localhost",
+      feed_url: "http://localhost/feed.json",
+    });
+
+    expect(feed.items).toHaveLength(20);
+    expect(feed.items[0]).toMatchObject({
+      id: "post-21",
+      url: "http://localhost/blog/post-21",
+      title: "Post post-21",
+      content_html: "<p>Content of post-21</p>\n",
+      summary: "Snippet of post-21",
+      date_published: "2024-01-21T00:00:00.000Z",
+      tags: ["news"],
+    });
+    expect(feed.items[1]).not.toHaveProperty("tags");
+    expect(feed.items[19].id).toBe("post-2");
+  });
+});
@@ -0,0 +1,56 @@
+import { Elysia } from "elysia";
+import { getBlogsFromFiles, renderPostContent } from "./blog";
+
+const FEED_SIZE = 20;
+const FEED_TITLE = process.env.FEED_TITLE || "Blog";
+
+// Public site that links in feeds point at, defaults to the API's origin
+const siteUrl = (request: Request) =>
+  (process.env.SITE_URL || new URL(request.url).origin).replace(/\/+$/, "");
+
+/**
+ * Newest published posts, shared by every feed format.
+ */
+export async function getRecentPosts(limit = FEED_SIZE) {
+  const posts = await getBlogsFromFiles();
+  return posts
+    .sort((a, b) => b.createdAt.getTime() - a.createdAt.getTime())
+    .slice(0, limit);
+}
+
+export const feedRoutes = new Elysia().get(
+  "/feed.json",
+  async ({ request, set }) => {
+    const site = siteUrl(request);
+    const posts = await getRecentPosts();
+
+    set.headers["content-type"] = "application/feed+json; charset=utf-8";
+    return {
+      version: "https://jsonfeed.org/version/1.1",
+      title: FEED_TITLE,
+      home_page_url: site,
+      feed_url: new URL(request.url).href,
+      items: await Promise.all(
+        posts.map(async (post) => ({
+          id: post.id,
+          url: `${site}/blog/${post.id}`,
+          title: post.title,
+          content_html: await renderPostContent(post.markdownContent),
+          content_text: post.snippet,
+          summary: post.snippet,
+          date_published: post.createdAt.toISOString(),
+          date_modified: post.updatedAt.toISOString(),
+          ...(post.tags.length > 0 && { tags: post.tags }),
+        }))
+      ),
+    };
+  },
+  {
+    detail: {
+      summary: "JSON Feed of recent blog posts",
+      tags: ["blogs"],
+      description:
+        "A JSON Feed 1.1 document with the 20 newest published posts. `content_html` is the rendered markdown and `content_text` the post's snippet. Item URLs use `SITE_URL` when set, otherwise this server's origin.",
+    },
+  }
+);
@@ -0,0 +1,75 @@
+import { Elysia, t } from "elysia";
+import { connectToDatabase } from "../db/connect";
+
+const { db } = await connectToDatabase();
+
+const DB_PING_TIMEOUT_MS = 2000; // Report the database as down rather than hang
+
+/**
+ * Runs a trivial `ping` command to check the database is reachable.
+ */
+async function pingDatabase(): Promise<boolean> {
+  try {
+    await Promise.race([
+      db.command({ ping: 1 }),
+      new Promise((_, reject) =>
+        setTimeout(
+          () => reject(new Error("Database ping timed out")),
+          DB_PING_TIMEOUT_MS
+        )
+      ),
+    ]);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+const HealthSchema = t.Object({
+  status: t.String(),
+  database: t.String(),
+  uptime: t.Number(),
+});
+
+export const healthRoutes = new Elysia()
+  .get(
+    "/health",
+    async () => ({
+      status: "ok",
+      database: (await pingDatabase()) ? "up" : "down",
+      uptime: Math.floor(process.uptime()),
+    }),
+    {
+      response: HealthSchema,
+      detail: {
+        summary: "Liveness check",
+        tags: ["health"],
+        description:
+          "Returns 200 while the process is running, along with whether the database answered a ping. The database being down does not fail this check.",
+      },
+    }
+  )
+  .get(
+    "/ready",
+    async ({ set }) => {
+      const databaseUp = await pingDatabase();
+      if (!databaseUp) set.status = 503;
+      return {
+        status: databaseUp ? "ok" : "unavailable",
+        database: databaseUp ? "up" : "down",
+        uptime: Math.floor(process.uptime()),
+      };
+    },
+    {
+      response: {
+        200: HealthSchema,
+        503: HealthSchema,
+      },
+      detail: {
+        summary: "Readiness check",
+        tags: ["health"],
+        description:
+          "Returns 200 when the database is reachable, 503 otherwise. Use this to decide whether the instance should receive traffic.",
+      },
+    }
+  );
@@ -0,0 +1,86 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { mkdir, writeFile } from "node:fs/promises";
+import path from "node:path";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TEST_DATA_DIR,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { fileForm, pngFile } from "../test-utils/images";
+
+describeWithDb("images", () => {
+  let db: Db;
+  let app: TestApp;
+  let images: typeof import("./images");
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    images = await import("./images");
+  });
+
+  const storedSize = async (userId: string) =>
+    (await db.collection("user").findOne({ _id: new ObjectId(userId) }))
+      ?.imagesStoredSize;
+
+  test("recomputing repairs a drifted imagesStoredSize", async () => {
+    const drifted = await createTestUser();
+    const correct = await createTestUser();
+    const users = db.collection("user");
+    await users.updateOne(
+      { _id: new ObjectId(drifted.id) },
+      { $set: { imagesStoredSize: 999_999 } }
+    );
+    await users.updateOne(
+      { _id: new ObjectId(correct.id) },
+      { $set: { imagesStoredSize: 0 } }
+    );
+
+    const dir = path.join(TEST_DATA_DIR, "images");
+    await mkdir(dir, { recursive: true });
+    await writeFile(path.join(dir, `${drifted.id}_1.jpg`), Buffer.alloc(100));
+    await writeFile(path.join(dir, `${drifted.id}_2.jpg`), Buffer.alloc(50));
+
+    expect(await images.recomputeStoredImageSizes()).toEqual({
+      checked: 2,
+      corrected: 1,
+    });
+    expect(await storedSize(drifted.id)).toBe(150);
+    expect(await storedSize(correct.id)).toBe(0);
+  });
+
+  test("an uploaded image can be fetched back", async () => {
+    const user = await createTestUser();
+    const upload = (file: File) =>
+      request(app, "/images/upload", {
+        method: "POST",
+        user,
+        body: fileForm("image", file),
+      });
+
+    const res = await upload(pngFile("holiday.png"));
+    expect(res.status).toBe(200);
+    const { imageId, url, size } = await res.json();
+    expect(imageId).toMatch(new RegExp(`^${user.id}_\\d+_holiday\\.jpg$`));
+
+    const image = await request(app, url);
+    expect(image.status).toBe(200);
+    expect((await image.arrayBuffer()).byteLength).toBe(size);
+    expect(await storedSize(user.id)).toBe(size);
+
+    const { images: listed } = await (
+      await request(app, "/images", { user })
+    ).json();
+    expect(listed.map((i: { id: string }) => i.id)).toEqual([imageId]);
+
+    const text = new File(["hello"], "notes.txt", { type: "text/plain" });
+    const rejected = await upload(text);
+    expect(rejected.status).toBe(400);
+    expect(await rejected.json()).toEqual({
+      error: "Invalid file type. Only images are allowed",
+    });
+  });
+});
@@ -22,10 +22,56 @@ const ALLOWED_MIME_TYPES = new Set([
   "image/bmp",
 ]);
 
+/**
+ * Detects PNG, JPEG, GIF or WebP data from its leading magic bytes, ignoring
+ * whatever content type the client claimed. Returns null for anything else.
+ */
+export function detectImageMimeType(
+  buffer: Buffer
+): "image/png" | "image/jpeg" | "image/gif" | "image/webp" | null {
+  if (
+    buffer.length >= 8 &&
+    buffer
+      .subarray(0, 8)
+      .equals(Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]))
+  ) {
+    return "image/png";
+  }
+  if (
+    buffer.length >= 3 &&
+    buffer[0] === 0xff &&
+    buffer[1] === 0xd8 &&
+    buffer[2] === 0xff
+  ) {
+    return "image/jpeg";
+  }
+  if (buffer.length >= 6 && buffer.subarray(0, 4).toString() === "GIF8") {
+    return "image/gif";
+  }
+  if (
+    buffer.length >= 12 &&
+    buffer.subarray(0, 4).toString() === "RIFF" &&
+    buffer.subarray(8, 12).toString() === "WEBP"
+  ) {
+    return "image/webp";
+  }
+  return null;
+}
+
+/**
+ * Returns a stored image file, or null if it doesn't exist.
+ */
+export async function getImageFile(imageId: string) {
+  // Image IDs are plain filenames, never paths
+  if (path.basename(imageId) !== imageId) return null;
+  const file = Bun.file(path.join(dataDir, "images", imageId));
+  return (await file.exists()) ? file : null;
+}
+
 /**
  * Reusable function to upload an image buffer to the user's directory
  */
-async function uploadImage(
+export async function uploadImage(
   userId: string,
   imageBuffer: Buffer,
   mimeType: string,
@@ -204,6 +250,40 @@ export async function downloadAndUploadImage(
   }
 }
 
+/**
+ * Recomputes every user's `imagesStoredSize` from the image files on disk
+ * (files are named after their owner's ID), fixing counters that drifted
+ * when uploads or deletes raced. Returns how many users were corrected.
+ */
+export async function recomputeStoredImageSizes() {
+  const imagesDir = path.join(dataDir, "images");
+  const files = await fs.readdir(imagesDir).catch(() => [] as string[]);
+
+  const sizes = new Map<string, number>();
+  for (const file of files) {
+    const userId = file.split("_")[0];
+    if (!ObjectId.isValid(userId)) continue;
+    const { size } = await fs.stat(path.join(imagesDir, file));
+    sizes.set(userId, (sizes.get(userId) ?? 0) + size);
+  }
+
+  const users = await usersCollection
+    .find({}, { projection: { imagesStoredSize: 1 } })
+    .toArray();
+  const actual = (id: ObjectId) => sizes.get(id.toString()) ?? 0;
+  const updates = users
+    .filter((u) => (u.imagesStoredSize ?? 0) !== actual(u._id))
+    .map((u) => ({
+      updateOne: {
+        filter: { _id: u._id },
+        update: { $set: { imagesStoredSize: actual(u._id) } },
+      },
+    }));
+  if (updates.length > 0) await usersCollection.bulkWrite(updates);
+
+  return { checked: users.length, corrected: updates.length };
+}
+
 export const imageRoutes = new Elysia({ prefix: "/images" })
   .derive(async ({ request: { headers } }) => {
     const session = await auth.api.getSession({ headers }).catch(() => null);
@@ -34,14 +34,14 @@ export const leaderboardsRoutes = new Elysia({ prefix: "/leaderboards" })
     {
       query: t.Object({
         attribute: t.String({
-          description: `The attribute name to rank users by (e.g., chessWins). Allowed attributes: age, chessWins, chessLosses, draughtsWins, draughtsLosses, name.`,
+          description: `The attribute name to rank users by (e.g., chessWins). Allowed attributes: age, chessWins, chessLosses, chessRating, draughtsWins, draughtsLosses, name.`,
         }),
       }),
       response: t.Array(PublicUserSchema),
       detail: {
         summary: "Get leaderboard of users ranked by a specific attribute",
         tags: ["leaderboards"],
-        description: `Retrieves a list of public user profiles ranked by the specified attribute value in descending order. Allowed attributes: age, chessWins, chessLosses, draughtsWins, draughtsLosses, name.`,
+        description: `Retrieves a list of public user profiles ranked by the specified attribute value in descending order. Allowed attributes: age, chessWins, chessLosses, chessRating, draughtsWins, draughtsLosses, name.`,
       },
     }
   );
@@ -0,0 +1,231 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TEST_PASSWORD,
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { fileForm, pngFile } from "../test-utils/images";
+import { writeTestPost } from "../test-utils/posts";
+
+describeWithDb("/me", () => {
+  let db: Db;
+  let app: TestApp;
+  let DELETED_USER_ID: string;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    ({ DELETED_USER_ID } = await import("../utils/account"));
+  });
+
+  const addComment = (user: TestUser) =>
+    db.collection("comments").insertOne({
+      blogId: "example",
+      authorId: user.id,
+      content: "Nice post",
+      accepted: true,
+      createdAt: new Date(),
+      parentId: null,
+    });
+
+  const getMe = async (user: TestUser) =>
+    (await request(app, "/me", { user })).json();
+
+  const addReaction = (user: TestUser) =>
+    db.collection("reactions").insertOne({
+      blogId: "example",
+      userId: user.id,
+      type: "like",
+      createdAt: new Date(),
+    });
+
+  test("GET returns the stored profile and counts", async () => {
+    const user = await createTestUser("seeded");
+    await db
+      .collection("user")
+      .updateOne(
+        { _id: new ObjectId(user.id) },
+        { $set: { chessRating: 1350, bio: "Hello" } }
+      );
+    await addComment(user);
+
+    const res = await request(app, "/me", { user });
+    expect(await res.json()).toMatchObject({
+      id: user.id,
+      name: "seeded",
+      email: user.email,
+      emailVerified: false,
+      bio: "Hello",
+      displayName: null,
+      isAdmin: false,
+      chessRating: 1350,
+      commentCount: 1,
+      flashcardDeckCount: 0,
+    });
+    expect((await request(app, "/me")).status).toBe(401);
+  });
+
+  test("PUT /profile updates the display name and bio", async () => {
+    const user = await createTestUser("profiled");
+    const update = (body: object) =>
+      request(app, "/me/profile", { method: "PUT", user, body });
+
+    const res = await update({ displayName: "  Prof  ", bio: "About me" });
+    expect(res.status).toBe(200);
+
+    // Rejected updates leave the saved values alone
+    const tooLong = await update({ displayName: "x".repeat(51) });
+    expect(tooLong.status).toBe(422);
+    expect((await tooLong.json()).error.fields).toHaveProperty("displayName");
+    expect((await update({ bio: "<b>bold</b>" })).status).toBe(422);
+    expect((await update({})).status).toBe(400);
+
+    const me = await getMe(user);
+    expect(me.displayName).toBe("Prof");
+    expect(me.bio).toBe("About me");
+  });
+
+  test("POST /avatar stores the image and sets it as the avatar", async () => {
+    const user = await createTestUser("pictured");
+    const upload = (file: File) =>
+      request(app, "/me/avatar", {
+        method: "POST",
+        user,
+        body: fileForm("avatar", file),
+      });
+
+    const res = await upload(pngFile("me.png"));
+    expect(res.status).toBe(200);
+    const { url } = await res.json();
+    expect(url).toStartWith(`/images/${user.id}_`);
+    expect((await getMe(user)).image).toBe(url);
+
+    // Stored re-encoded as JPEG
+    const image = await request(app, url);
+    expect(image.status).toBe(200);
+    const bytes = new Uint8Array(await image.arrayBuffer());
+    expect([...bytes.subarray(0, 3)]).toEqual([0xff, 0xd8, 0xff]);
+
+    const fake = await upload(pngFile("fake.png", "not really a PNG"));
+    expect(fake.status).toBe(400);
+    expect((await fake.json()).error.message).toBe(
+      "Avatar must be a PNG or JPEG image"
+    );
+    expect((await getMe(user)).image).toBe(url);
+  });
+
+  test("GET /export includes the user's comments and reactions", async () => {
+    const user = await createTestUser("exporter");
+    const other = await createTestUser("bystander");
+    const { insertedId } = await addComment(user);
+    await addComment(other);
+    await addReaction(user);
+
+    const res = await request(app, "/me/export", { user });
+    expect(res.headers.get("content-disposition")).toBe(
+      `attachment; filename="export-${user.id}.json"`
+    );
+    const data = await res.json();
+    expect(data.profile).toMatchObject({ id: user.id, email: user.email });
+    expect(data.comments).toHaveLength(1);
+    expect(data.comments[0]).toMatchObject({
+      id: insertedId.toString(),
+      content: "Nice post",
+    });
+    expect(data.reactions.posts).toHaveLength(1);
+    expect(data.reactions.posts[0]).toMatchObject({
+      blogId: "example",
+      type: "like",
+    });
+  });
+
+  test("DELETE anonymizes comments and stops the user signing in", async () => {
+    const user = await createTestUser("leaving");
+    const { insertedId } = await addComment(user);
+    await addReaction(user);
+
+    const res = await request(app, "/me", {
+      method: "DELETE",
+      user,
+      body: { password: TEST_PASSWORD },
+    });
+    expect(res.status).toBe(200);
+
+    const comment = await db.collection("comments").findOne({
+      _id: insertedId,
+    });
+    expect(comment?.authorId).toBe(DELETED_USER_ID);
+    expect(comment?.content).toBe("Nice post");
+    expect(
+      await db.collection("reactions").countDocuments({ userId: user.id })
+    ).toBe(0);
+
+    const signIn = await request(app, "/auth/api/sign-in/email", {
+      method: "POST",
+      body: { email: user.email, password: TEST_PASSWORD },
+    });
+    expect(signIn.status).toBe(401);
+    expect((await request(app, "/me", { user })).status).toBe(401);
+  });
+
+  test("DELETE with the wrong password keeps everything", async () => {
+    const user = await createTestUser("staying");
+    const { insertedId } = await addComment(user);
+
+    const res = await request(app, "/me", {
+      method: "DELETE",
+      user,
+      body: { password: "not my password" },
+    });
+    expect(res.status).toBe(400);
+
+    const comment = await db.collection("comments").findOne({
+      _id: insertedId,
+    });
+    expect(comment?.authorId).toBe(user.id);
+    expect((await request(app, "/me", { user })).status).toBe(200);
+  });
+
+  test("GET /reactions lists liked and disliked posts", async () => {
+    const user = await createTestUser("opinionated");
+    await writeTestPost("liked", { title: "Liked Post" });
+    await writeTestPost("disliked", { title: "Disliked Post" });
+    await writeTestPost("unpublished", { draft: true });
+    const react = (blogId: string, type: string, createdAt: string) =>
+      db.collection("reactions").insertOne({
+        blogId,
+        userId: user.id,
+        type,
+        createdAt: new Date(createdAt),
+      });
+    await react("liked", "like", "2024-01-01T00:00:00Z");
+    await react("disliked", "dislike", "2024-02-01T00:00:00Z");
+    await react("unpublished", "like", "2024-03-01T00:00:00Z");
+
+    const res = await request(app, "/me/reactions?limit=1", { user });
+    expect(res.headers.get("x-total-count")).toBe("2");
+    const first = await res.json();
+    expect(first.reactions).toEqual([
+      {
+        type: "dislike",
+        createdAt: "2024-02-01T00:00:00.000Z",
+        post: { id: "disliked", title: "Disliked Post", url: "/blog/disliked" },
+      },
+    ]);
+
+    const next = await request(
+      app,
+      `/me/reactions?limit=1&after=${first.nextCursor}`,
+      { user }
+    );
+    const second = await next.json();
+    expect(second.reactions.map((r: any) => [r.type, r.post.id])).toEqual([
+      ["like", "liked"],
+    ]);
+    expect(second.nextCursor).toBeNull();
+  });
+});
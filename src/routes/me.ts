@@ -0,0 +1,403 @@
+import { Elysia, t } from "elysia";
+import { ObjectId } from "mongodb";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { DEFAULT_CHESS_RATING } from "../utils/profile";
+import { ApiError, FieldErrors, handleApiError } from "../utils/errors";
+import {
+  PaginationQuerySchema,
+  parseLimit,
+  decodeCursor,
+  encodeCursor,
+  mongoCursorFilter,
+  setPaginationHeaders,
+} from "../utils/pagination";
+import { detectImageMimeType, uploadImage } from "./images";
+import { getBlogsFromFiles } from "./blog";
+
+const {
+  userCollection,
+  commentsCollection,
+  flashcardsCollection,
+  reactionsCollection,
+  commentReactionsCollection,
+  reportsCollection,
+  mediaCollection,
+  chessGamesCollection,
+} = await connectToDatabase();
+
+const MAX_BIO_LENGTH = 500;
+const MAX_DISPLAY_NAME_LENGTH = 50;
+const HTML_TAG_PATTERN = /<[^>]*>/;
+const MAX_AVATAR_SIZE = 2 * 1024 * 1024; // 2MB
+
+// Replaces Mongo `_id`s with string `id`s for the export document
+const withStringId = <T extends { _id: unknown }>({ _id, ...rest }: T) => ({
+  id: String(_id),
+  ...rest,
+});
+
+export const MeSchema = t.Object({
+  id: t.String(),
+  name: t.String(),
+  email: t.Nullable(t.String()),
+  emailVerified: t.Boolean(),
+  image: t.Nullable(t.String()),
+  displayName: t.Nullable(t.String()),
+  bio: t.Nullable(t.String()),
+  role: t.Nullable(t.String()),
+  isAdmin: t.Boolean(),
+  createdAt: t.Date(),
+  updatedAt: t.Date(),
+  chessRating: t.Number(),
+  commentCount: t.Number(),
+  flashcardDeckCount: t.Number(),
+});
+
+export const meRoutes = new Elysia({ prefix: "/me" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers }).catch(() => null);
+    return { currentUser: session?.user };
+  })
+  .onError(handleApiError)
+  .get(
+    "/",
+    async ({ currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      // Read from the database so role changes apply immediately
+      const user = await userCollection.findOne({
+        _id: new ObjectId(currentUser.id),
+      });
+      if (!user) throw new ApiError(404, "User not found");
+
+      const [commentCount, flashcardDeckCount] = await Promise.all([
+        commentsCollection.countDocuments({ authorId: currentUser.id }),
+        flashcardsCollection.countDocuments({ userId: currentUser.id }),
+      ]);
+
+      return {
+        id: user._id.toString(),
+        name: user.name || "",
+        email: user.email ?? null,
+        emailVerified: !!user.emailVerified,
+        image: user.image ?? null,
+        displayName: user.displayName ?? null,
+        bio: user.bio ?? null,
+        role: user.role ?? null,
+        isAdmin: !!user.role?.includes("admin"),
+        createdAt: user.createdAt,
+        updatedAt: user.updatedAt,
+        chessRating: user.chessRating ?? DEFAULT_CHESS_RATING,
+        commentCount,
+        flashcardDeckCount,
+      };
+    },
+    {
+      response: MeSchema,
+      detail: {
+        summary: "Get the current user's full profile",
+        tags: ["me"],
+        description:
+          "Returns the logged-in user's account details read fresh from the database, including role, chess rating and how many comments and flashcard decks they have created.",
+      },
+    }
+  )
+  .put(
+    "/profile",
+    async ({ body, currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      const update: { displayName?: string; bio?: string } = {};
+      const errors = new FieldErrors();
+      if (body.displayName !== undefined) {
+        const displayName = body.displayName.trim();
+        if (!displayName) {
+          errors.add("displayName", "Display name cannot be empty");
+        }
+        if (displayName.length > MAX_DISPLAY_NAME_LENGTH) {
+          errors.add(
+            "displayName",
+            `Display name must be at most ${MAX_DISPLAY_NAME_LENGTH} characters`
+          );
+        }
+        if (HTML_TAG_PATTERN.test(displayName)) {
+          errors.add("displayName", "HTML is not allowed in the display name");
+        }
+        update.displayName = displayName;
+      }
+      if (body.bio !== undefined) {
+        const bio = body.bio.trim();
+        if (bio.length > MAX_BIO_LENGTH) {
+          errors.add("bio", `Bio must be at most ${MAX_BIO_LENGTH} characters`);
+        }
+        if (HTML_TAG_PATTERN.test(bio)) {
+          errors.add("bio", "HTML is not allowed in the bio");
+        }
+        update.bio = bio;
+      }
+      errors.throwIfAny();
+
+      if (Object.keys(update).length === 0) {
+        throw new ApiError(400, "Nothing to update");
+      }
+
+      await userCollection.updateOne(
+        { _id: new ObjectId(currentUser.id) },
+        { $set: { ...update, updatedAt: new Date() } }
+      );
+
+      return { message: "Profile updated" };
+    },
+    {
+      body: t.Object({
+        displayName: t.Optional(t.String()),
+        bio: t.Optional(t.String()),
+      }),
+      response: t.Object({ message: t.String() }),
+      detail: {
+        summary: "Update the current user's display name and bio",
+        tags: ["me"],
+        description:
+          "Sets `displayName` (max 50 characters) and/or `bio` (max 500 characters, may be empty to clear it). HTML is rejected. At least one field is required. The verified `name` shown on public profiles is unaffected.",
+      },
+    }
+  )
+  .post(
+    "/avatar",
+    async ({ body: { avatar }, currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      const buffer = Buffer.from(await avatar.arrayBuffer());
+      if (buffer.length > MAX_AVATAR_SIZE) {
+        throw new ApiError(400, "Avatar too large. Maximum size is 2MB");
+      }
+
+      // Trust the file contents, not the client's content type
+      const mimeType = detectImageMimeType(buffer);
+      if (mimeType !== "image/png" && mimeType !== "image/jpeg") {
+        throw new ApiError(400, "Avatar must be a PNG or JPEG image");
+      }
+
+      let result;
+      try {
+        result = await uploadImage(
+          currentUser.id,
+          buffer,
+          mimeType,
+          avatar.name
+        );
+      } catch (error) {
+        throw new ApiError(400, (error as Error).message);
+      }
+
+      // Like other profile images, this is shown publicly once verified
+      await userCollection.updateOne(
+        { _id: new ObjectId(currentUser.id) },
+        { $set: { image: result.url, updatedAt: new Date() } }
+      );
+
+      return { url: result.url };
+    },
+    {
+      body: t.Object({
+        avatar: t.File({
+          maxSize: MAX_AVATAR_SIZE,
+          description: "PNG or JPEG image (max 2MB)",
+        }),
+      }),
+      response: t.Object({ url: t.String() }),
+      detail: {
+        summary: "Upload a new avatar",
+        tags: ["me"],
+        description:
+          "Stores a PNG or JPEG avatar (validated by its contents) and sets it as the user's profile image. Counts towards the image storage quota and daily upload limit. As with other profile images it appears publicly once verified.",
+        security: [{ session: [] }],
+      },
+    }
+  )
+  .get(
+    "/reactions",
+    async ({ currentUser, query, request, set }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      // Reactions on drafts and deleted posts are left out
+      const posts = await getBlogsFromFiles();
+      const titles = new Map(posts.map((p) => [p.id, p.title]));
+      const countFilter = {
+        userId: currentUser.id,
+        blogId: { $in: [...titles.keys()] },
+      };
+      let filter: any = countFilter;
+      if (query.after) {
+        const cursor = decodeCursor(query.after);
+        if (!ObjectId.isValid(cursor.id)) {
+          throw new ApiError(400, "Invalid cursor");
+        }
+        filter = {
+          ...filter,
+          ...mongoCursorFilter(cursor, (id) => new ObjectId(id)),
+        };
+      }
+
+      const limit = parseLimit(query.limit);
+      let find = reactionsCollection
+        .find(filter)
+        .sort({ createdAt: -1, _id: -1 });
+      if (!query.after && query.offset) {
+        find = find.skip(Math.max(0, parseInt(query.offset) || 0));
+      }
+      // Fetch one extra to know whether there is another page
+      const [page, total] = await Promise.all([
+        find.limit(limit + 1).toArray(),
+        reactionsCollection.countDocuments(countFilter),
+      ]);
+
+      const reactions = page.slice(0, limit);
+      const last = reactions[reactions.length - 1];
+      const nextCursor =
+        page.length > limit && last
+          ? encodeCursor(last.createdAt, last._id.toString())
+          : null;
+      setPaginationHeaders({ request, set }, { total, limit, nextCursor });
+
+      return {
+        reactions: reactions.map((r) => ({
+          type: r.type,
+          createdAt: r.createdAt,
+          post: {
+            id: r.blogId,
+            title: titles.get(r.blogId)!,
+            url: `/blog/${r.blogId}`,
+          },
+        })),
+        nextCursor,
+      };
+    },
+    {
+      query: PaginationQuerySchema,
+      response: t.Object({
+        reactions: t.Array(
+          t.Object({
+            type: t.Union([t.Literal("like"), t.Literal("dislike")]),
+            createdAt: t.Date(),
+            post: t.Object({
+              id: t.String(),
+              title: t.String(),
+              url: t.String({ description: "API path of the post" }),
+            }),
+          })
+        ),
+        nextCursor: t.Nullable(t.String()),
+      }),
+      detail: {
+        summary: "Get a page of the current user's post reactions",
+        tags: ["me", "reactions"],
+        security: [{ session: [] }],
+        description:
+          "Lists the posts the user has liked or disliked, most recent reaction first, with each post's title and path. Reactions on posts that are unpublished or deleted are left out. Pass `nextCursor` back as `after` for the next page.",
+      },
+    }
+  )
+  .delete(
+    "/",
+    async ({ request: { headers }, body: { password }, currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      try {
+        await auth.api.deleteUser({ headers, body: { password } });
+      } catch (error: any) {
+        // better-auth rejects a wrong password with a 400
+        if (error?.statusCode !== 400) throw error;
+        throw new ApiError(400, error.body?.message ?? "Invalid password");
+      }
+
+      return { message: "Account deleted" };
+    },
+    {
+      body: t.Object({
+        password: t.String({ description: "Current password, to confirm" }),
+      }),
+      response: t.Object({ message: t.String() }),
+      detail: {
+        summary: "Delete the current user's account",
+        tags: ["me"],
+        security: [{ session: [] }],
+        description:
+          "Permanently deletes the account after checking the password and logs out every session. Comments, reports and chess games are kept but attributed to `deleted`, reactions and flashcard decks are removed and active chess games are forfeited.",
+      },
+    }
+  )
+  .get(
+    "/export",
+    async ({ currentUser, set }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      const user = await userCollection.findOne({
+        _id: new ObjectId(currentUser.id),
+      });
+      if (!user) throw new ApiError(404, "User not found");
+
+      const userId = currentUser.id;
+      const [
+        comments,
+        postReactions,
+        commentReactions,
+        reports,
+        flashcardDecks,
+        media,
+        chessGames,
+      ] = await Promise.all([
+        commentsCollection.find({ authorId: userId }).toArray(),
+        reactionsCollection.find({ userId }).toArray(),
+        commentReactionsCollection.find({ userId }).toArray(),
+        reportsCollection.find({ reporterId: userId }).toArray(),
+        flashcardsCollection.find({ userId }).toArray(),
+        mediaCollection.find({ uploadedBy: userId }).toArray(),
+        chessGamesCollection
+          .find({
+            $or: [{ "white.userId": userId }, { "black.userId": userId }],
+          })
+          .toArray(),
+      ]);
+
+      // Push subscription keys are secrets, only the endpoints are exported
+      const { pushSubscriptions, ...profile } = user;
+
+      set.headers["content-type"] = "application/json; charset=utf-8";
+      set.headers["content-disposition"] =
+        `attachment; filename="export-${userId}.json"`;
+
+      return JSON.stringify(
+        {
+          exportedAt: new Date(),
+          profile: {
+            ...withStringId(profile),
+            pushSubscriptions: (pushSubscriptions ?? []).map((s) => ({
+              endpoint: s.endpoint,
+            })),
+          },
+          comments: comments.map(withStringId),
+          reactions: {
+            posts: postReactions.map(withStringId),
+            comments: commentReactions.map(withStringId),
+          },
+          reports: reports.map(withStringId),
+          flashcardDecks: flashcardDecks.map(withStringId),
+          media: media.map(withStringId),
+          chessGames: chessGames.map(withStringId),
+        },
+        null,
+        2
+      );
+    },
+    {
+      detail: {
+        summary: "Download all of the current user's data",
+        tags: ["me"],
+        security: [{ session: [] }],
+        description:
+          "Returns a JSON file (sent as an attachment) with the user's profile, comments, reactions, reports, flashcard decks, uploaded media and chess games. Passwords and session tokens are stored outside the user document and are never included, nor are push subscription keys.",
+      },
+    }
+  );
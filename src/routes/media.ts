@@ -0,0 +1,138 @@
+import { Elysia, t } from "elysia";
+import { ObjectId } from "mongodb";
+import { promises as fs } from "node:fs";
+import path from "node:path";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { ApiError, handleApiError } from "../utils/errors";
+import { detectImageMimeType } from "./images";
+import { recordAdminAction } from "../utils/audit";
+
+const { mediaCollection } = await connectToDatabase();
+const dataDir = process.env.DATA_DIR || "data";
+const MAX_MEDIA_SIZE = 10 * 1024 * 1024; // 10MB
+const EXTENSIONS: Record<string, string> = {
+  "image/png": "png",
+  "image/jpeg": "jpg",
+  "image/gif": "gif",
+  "image/webp": "webp",
+};
+
+const MediaSchema = t.Object({
+  id: t.String(),
+  url: t.String(),
+  mimeType: t.String(),
+  size: t.Number(),
+  createdAt: t.Date(),
+});
+
+export const mediaRoutes = new Elysia({ prefix: "/media" })
+  .onError(handleApiError)
+  .get(
+    "/:id",
+    async ({ params: { id }, set }) => {
+      if (!ObjectId.isValid(id)) throw new ApiError(404, "Media not found");
+
+      const media = await mediaCollection.findOne({ _id: new ObjectId(id) });
+      const file = media
+        ? Bun.file(path.join(dataDir, "media", media.filename))
+        : null;
+      if (!media || !file || !(await file.exists())) {
+        throw new ApiError(404, "Media not found");
+      }
+
+      // Media is never modified after upload, so it can be cached forever
+      set.headers["content-type"] = media.mimeType;
+      set.headers["cache-control"] = "public, max-age=31536000, immutable";
+      return file;
+    },
+    {
+      params: t.Object({
+        id: t.String({ description: "Media ID returned by the upload" }),
+      }),
+      detail: {
+        summary: "Get an uploaded media file",
+        tags: ["media"],
+        description:
+          "Serves media uploaded for blog posts with its content type and long-lived cache headers.",
+      },
+    }
+  );
+
+export const adminMediaRoutes = new Elysia({ prefix: "/admin" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers });
+    if (!session) {
+      throw new ApiError(401, "Unauthorized: Admin access required");
+    }
+    if (!session.user.role?.includes("admin")) {
+      throw new ApiError(403, "Forbidden: Admin access required");
+    }
+    return { currentUser: session.user };
+  })
+  .onError(handleApiError)
+  .post(
+    "/upload-image",
+    async ({ body: { image }, currentUser }) => {
+      const buffer = Buffer.from(await image.arrayBuffer());
+      if (buffer.length > MAX_MEDIA_SIZE) {
+        throw new ApiError(400, "Image too large. Maximum size is 10MB");
+      }
+
+      const mimeType = detectImageMimeType(buffer);
+      if (!mimeType) {
+        throw new ApiError(
+          400,
+          "Only PNG, JPEG, GIF and WebP images are allowed"
+        );
+      }
+
+      // Stored as-is (no recompression) so animated GIFs keep working
+      const _id = new ObjectId();
+      const filename = `${_id.toString()}.${EXTENSIONS[mimeType]}`;
+      const filepath = path.join(dataDir, "media", filename);
+      await fs.mkdir(path.dirname(filepath), { recursive: true });
+      await fs.writeFile(filepath, buffer);
+
+      const createdAt = new Date();
+      await mediaCollection.insertOne({
+        _id,
+        filename,
+        mimeType,
+        size: buffer.length,
+        originalName: image.name,
+        uploadedBy: currentUser.id,
+        createdAt,
+      });
+
+      console.log(`Media uploaded by ${currentUser.id}: ${filename}`);
+      await recordAdminAction(currentUser.id, "media.upload", {
+        type: "media",
+        id: _id.toString(),
+      });
+
+      return {
+        id: _id.toString(),
+        url: `/media/${_id.toString()}`,
+        mimeType,
+        size: buffer.length,
+        createdAt,
+      };
+    },
+    {
+      body: t.Object({
+        image: t.File({
+          maxSize: MAX_MEDIA_SIZE,
+          description: "PNG, JPEG, GIF or WebP image (max 10MB)",
+        }),
+      }),
+      response: MediaSchema,
+      detail: {
+        summary: "Upload an image for use in blog posts",
+        tags: ["media", "admin"],
+        description:
+          "Admin only. Stores the image and returns a stable URL that can be embedded in markdown, e.g. `![alt](/media/<id>)`.",
+        security: [{ session: [] }],
+      },
+    }
+  );
@@ -0,0 +1,77 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { writeTestPost } from "../test-utils/posts";
+
+describeWithDb("/profile", () => {
+  let db: Db;
+  let app: TestApp;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+  });
+
+  let postedAt = Date.parse("2024-01-01T00:00:00Z");
+  const addComment = (
+    author: TestUser,
+    content: string,
+    { blogId = "commented", accepted = true, deleted = false } = {}
+  ) =>
+    db.collection("comments").insertOne({
+      blogId,
+      authorId: author.id,
+      content,
+      accepted,
+      deleted,
+      createdAt: new Date((postedAt += 1000)),
+      parentId: null,
+    });
+
+  test("a user's comments list only theirs, with their posts", async () => {
+    await writeTestPost("commented", { title: "Commented Post" });
+    const author = await createTestUser("commenter");
+    const other = await createTestUser("passer-by");
+    await addComment(author, "First");
+    await addComment(other, "Not mine");
+    await addComment(author, "Pending", { accepted: false });
+    await addComment(author, "Removed", { deleted: true });
+    await addComment(author, "On a lost post", { blogId: "gone" });
+
+    const comments = async (user?: TestUser) => {
+      const res = await request(app, `/profile/${author.id}/comments`, {
+        user,
+      });
+      return (await res.json()).comments;
+    };
+
+    const shown = await comments(other);
+    expect(shown.map((c: { content: string }) => c.content)).toEqual([
+      "On a lost post",
+      "First",
+    ]);
+    expect(shown[0].post).toBeNull();
+    expect(shown[1].post).toEqual({
+      id: "commented",
+      title: "Commented Post",
+      url: "/blog/commented",
+    });
+
+    // Authors also see what's awaiting moderation, never other users'
+    const own = await comments(author);
+    expect(own.map((c: { content: string }) => c.content)).toEqual([
+      "On a lost post",
+      "Pending",
+      "First",
+    ]);
+
+    const unknown = await request(app, `/profile/${new ObjectId()}/comments`);
+    expect(unknown.status).toBe(404);
+  });
+});
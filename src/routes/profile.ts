@@ -3,17 +3,47 @@ import { getPublicUser, updateHighScore } from "../utils/profile";
 import { auth } from "../auth";
 import { connectToDatabase } from "../db/connect";
 import { ObjectId } from "mongodb";
+import { ApiError, handleApiError } from "../utils/errors";
+import { recordAdminAction } from "../utils/audit";
+import { getImageFile } from "./images";
+import { getBlogsFromFiles, CommentSchema } from "./blog";
+import {
+  PaginationQuerySchema,
+  parseLimit,
+  decodeCursor,
+  encodeCursor,
+  mongoCursorFilter,
+  setPaginationHeaders,
+} from "../utils/pagination";
+
+const { userCollection, commentsCollection } = await connectToDatabase();
+
+const UserCommentSchema = t.Composite([
+  CommentSchema,
+  t.Object({
+    post: t.Nullable(
+      t.Object({
+        id: t.String(),
+        title: t.String(),
+        url: t.String({ description: "API path of the post" }),
+      })
+    ),
+  }),
+]);
 
 export const PublicUserSchema = t.Object({
   id: t.String(),
   name: t.String(), // This will now be the verifiedName with fallback
   image: t.Nullable(t.String()), // This will now be the verifiedImage with fallback
   isVerified: t.Boolean(),
+  displayName: t.Nullable(t.String()), // Chosen by the user, not verified
+  bio: t.Nullable(t.String()),
   createdAt: t.Date(),
   updatedAt: t.Date(),
   age: t.Nullable(t.Number()),
   chessWins: t.Nullable(t.Number()),
   chessLosses: t.Nullable(t.Number()),
+  chessRating: t.Nullable(t.Number()),
   draughtsWins: t.Nullable(t.Number()),
   draughtsLosses: t.Nullable(t.Number()),
   arithmeticScore: t.Nullable(t.Number()),
@@ -64,25 +94,7 @@ export const profileRoutes = new Elysia({ prefix: "/profile" })
     const session = await auth.api.getSession({ headers }).catch(() => null);
     return { currentUser: session?.user };
   })
-  .onError(({ error: err, set }) => {
-    if (err instanceof Error) {
-      if (err.message === "Invalid user ID format") {
-        set.status = 400;
-        return { message: "Invalid user ID format" };
-      }
-      if (err.message === "User not found") {
-        set.status = 404;
-        return { message: "User not found" };
-      }
-      if (err.message === "User not authenticated") {
-        set.status = 401;
-        return { message: "User not authenticated" };
-      }
-      console.error("Unhandled error:", err.message);
-    }
-    set.status = 500;
-    return { message: "Internal server error" };
-  })
+  .onError(handleApiError)
   .get(
     "/:userId",
     async ({ params: { userId } }) => {
@@ -103,11 +115,132 @@ export const profileRoutes = new Elysia({ prefix: "/profile" })
       },
     }
   )
+  .get(
+    "/:userId/comments",
+    async ({ params: { userId }, query, currentUser, request, set }) => {
+      if (!ObjectId.isValid(userId)) {
+        throw new ApiError(400, "Invalid user ID format");
+      }
+      const exists = await userCollection.countDocuments(
+        { _id: new ObjectId(userId) },
+        { limit: 1 }
+      );
+      if (!exists) throw new ApiError(404, "User not found");
+
+      // Admins see everything, authors also see their unmoderated comments
+      const isAdmin = !!currentUser?.role?.includes("admin");
+      let filter: any = { authorId: userId };
+      if (!isAdmin) {
+        filter.deleted = { $ne: true };
+        if (currentUser?.id !== userId) filter.accepted = true;
+      }
+      const countFilter = filter;
+      if (query.after) {
+        const cursor = decodeCursor(query.after);
+        if (!ObjectId.isValid(cursor.id)) {
+          throw new ApiError(400, "Invalid cursor");
+        }
+        filter = {
+          ...filter,
+          ...mongoCursorFilter(cursor, (id) => new ObjectId(id)),
+        };
+      }
+
+      const limit = parseLimit(query.limit);
+      let find = commentsCollection
+        .find(filter)
+        .sort({ createdAt: -1, _id: -1 });
+      if (!query.after && query.offset) {
+        find = find.skip(Math.max(0, parseInt(query.offset) || 0));
+      }
+      // Fetch one extra to know whether there is another page
+      const [page, posts, total] = await Promise.all([
+        find.limit(limit + 1).toArray(),
+        getBlogsFromFiles(isAdmin),
+        commentsCollection.countDocuments(countFilter),
+      ]);
+
+      const comments = page.slice(0, limit);
+      const titles = new Map(posts.map((p) => [p.id, p.title]));
+      const last = comments[comments.length - 1];
+      const nextCursor =
+        page.length > limit && last
+          ? encodeCursor(last.createdAt, last._id.toString())
+          : null;
+      setPaginationHeaders(
+        { request, set },
+        { total, limit, nextCursor }
+      );
+
+      return {
+        comments: comments.map((c) => ({
+          ...c,
+          _id: c._id.toString(),
+          post: titles.has(c.blogId)
+            ? {
+                id: c.blogId,
+                title: titles.get(c.blogId)!,
+                url: `/blog/${c.blogId}`,
+              }
+            : null,
+        })),
+        nextCursor,
+      };
+    },
+    {
+      params: t.Object({
+        userId: t.String({
+          description: "User ID whose comments to list",
+        }),
+      }),
+      query: PaginationQuerySchema,
+      response: t.Object({
+        comments: t.Array(UserCommentSchema),
+        nextCursor: t.Nullable(t.String()),
+      }),
+      detail: {
+        summary: "Get a page of a user's comments, newest first",
+        tags: ["profile", "comments"],
+        description:
+          "Lists the user's comments with the title and path of the post each is on (`post` is null if the post is gone or unpublished). Pass `nextCursor` back as `after` for the next page. Deleted comments are only shown to admins, and comments awaiting moderation only to admins and their author.",
+      },
+    }
+  )
+  .get(
+    "/:userId/avatar",
+    async ({ params: { userId }, set }) => {
+      const user = await getPublicUser(userId);
+
+      // Only locally stored images ("/images/<id>") can be served
+      const imageId = user.image?.startsWith("/images/")
+        ? user.image.slice("/images/".length)
+        : null;
+      const file = imageId ? await getImageFile(imageId) : null;
+      if (!file) throw new ApiError(404, "Avatar not found");
+
+      set.headers["content-type"] = file.type;
+      set.headers["cache-control"] = "public, max-age=3600";
+      return file;
+    },
+    {
+      params: t.Object({
+        userId: t.String({
+          description: "User ID to fetch the avatar for",
+        }),
+      }),
+      detail: {
+        summary: "Get a user's avatar image",
+        tags: ["profile"],
+        description:
+          "Serves the user's verified profile image with caching headers. Returns 404 if they have none.",
+      },
+    }
+  )
   .post(
     "/log-arithmetic",
     async ({ body, currentUser }) => {
       if (!currentUser) {
-        throw new Error("User not authenticated");
+        throw new ApiError(401, "User not authenticated");
       }
 
       const { finalScore, ...receivedSettings } = body;
@@ -177,7 +310,7 @@ export const profileRoutes = new Elysia({ prefix: "/profile" })
     "/log-tetris",
     async ({ body, currentUser }) => {
       if (!currentUser) {
-        throw new Error("User not authenticated");
+        throw new ApiError(401, "User not authenticated");
       }
 
       let finalScore = body.finalScore;
@@ -204,9 +337,6 @@ export const profileRoutes = new Elysia({ prefix: "/profile" })
   )
   .model({ PublicUser: PublicUserSchema });
 
-// Database connection for admin routes
-const { userCollection } = await connectToDatabase();
-
 const UnverifiedProfileSchema = t.Object({
   id: t.String(),
   name: t.String(),
@@ -220,32 +350,14 @@ export const adminProfileRoutes = new Elysia({ prefix: "/admin/profile" })
   .derive(async ({ request: { headers } }) => {
     const session = await auth.api.getSession({ headers });
     if (!session) {
-      throw new Error("Unauthorized");
+      throw new ApiError(401, "Unauthorized: Admin access required");
     }
     if (!session.user.role?.includes("admin")) {
-      throw new Error("Forbidden: Admin access required");
+      throw new ApiError(403, "Forbidden: Admin access required");
     }
     return { currentUser: session.user };
   })
-  .onError(({ error: err, set }) => {
-    if (err instanceof Error) {
-      if (err.message === "Unauthorized") {
-        set.status = 401;
-        return { message: "Unauthorized: Admin access required" };
-      }
-      if (err.message === "Forbidden: Admin access required") {
-        set.status = 403;
-        return { message: "Forbidden: Admin access required" };
-      }
-      if (err.message === "User not found") {
-        set.status = 404;
-        return { message: "User not found" };
-      }
-      console.error("Unhandled error:", err.message);
-    }
-    set.status = 500;
-    return { message: "Internal server error" };
-  })
+  .onError(handleApiError)
   .get(
     "/unverified",
     async () => {
@@ -288,12 +400,12 @@ export const adminProfileRoutes = new Elysia({ prefix: "/admin/profile" })
   )
   .post(
     "/:userId/verify",
-    async ({ params: { userId } }) => {
+    async ({ params: { userId }, currentUser }) => {
       const userIdObj = new ObjectId(userId);
       const user = await userCollection.findOne({ _id: userIdObj });
 
       if (!user) {
-        throw new Error("User not found");
+        throw new ApiError(404, "User not found");
       }
 
       // Set verified fields to current values
@@ -306,6 +418,10 @@ export const adminProfileRoutes = new Elysia({ prefix: "/admin/profile" })
           },
         }
       );
+      await recordAdminAction(currentUser.id, "profile.verify", {
+        type: "user",
+        id: userId,
+      });
 
       return {
         success: true,
@@ -0,0 +1,91 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+
+describeWithDb("reports", () => {
+  let db: Db;
+  let app: TestApp;
+  let reporter: TestUser;
+  let admin: TestUser;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    reporter = await createTestUser("reporter");
+    admin = await createTestUser("moderator", { role: "admin" });
+  });
+
+  const report = async (user: TestUser, targetId: string) => {
+    const res = await request(app, "/report", {
+      method: "POST",
+      user,
+      body: { targetType: "comment", targetId, reason: "Spam" },
+    });
+    return { status: res.status, ...(await res.json()) };
+  };
+
+  const addComment = async () => {
+    const { insertedId } = await db.collection("comments").insertOne({
+      blogId: "example",
+      authorId: "someone",
+      content: "<p>Buy now</p>",
+      accepted: true,
+      createdAt: new Date(),
+      parentId: null,
+    });
+    return insertedId.toString();
+  };
+
+  const openReports = async (targetId: string) => {
+    const res = await request(app, "/admin/reports", { user: admin });
+    const reports: any[] = await res.json();
+    return reports.filter((r) => r.targetId === targetId);
+  };
+
+  test("a duplicate report returns the open one", async () => {
+    const commentId = await addComment();
+    const first = await report(reporter, commentId);
+    expect(first.status).toBe(200);
+    expect((await report(reporter, commentId)).reportId).toBe(first.reportId);
+    const second = await report(admin, commentId);
+    expect(second.reportId).not.toBe(first.reportId);
+
+    const open = await openReports(commentId);
+    expect(open).toHaveLength(2);
+    expect(open[0].content.text).toBe("<p>Buy now</p>");
+  });
+
+  test("deleting the content resolves every report on it", async () => {
+    const targetId = await addComment();
+    const { reportId } = await report(reporter, targetId);
+    await report(admin, targetId);
+
+    const res = await request(app, `/admin/reports/${reportId}/resolve`, {
+      method: "POST",
+      user: admin,
+      body: { action: "delete_content" },
+    });
+    expect(res.status).toBe(200);
+
+    const comment = await db
+      .collection("comments")
+      .findOne({ _id: new ObjectId(targetId) });
+    expect(comment).toBeNull();
+    expect(await openReports(targetId)).toEqual([]);
+    expect(
+      await db
+        .collection("reports")
+        .countDocuments({ targetId, status: "resolved" })
+    ).toBe(2);
+  });
+
+  test("reporting something that doesn't exist is a 404", async () => {
+    expect((await report(reporter, "0".repeat(24))).status).toBe(404);
+  });
+});
@@ -0,0 +1,270 @@
+import { Elysia, t } from "elysia";
+import { ObjectId } from "mongodb";
+import { existsSync, promises as fs } from "node:fs";
+import matter from "gray-matter";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { ReportTargetType } from "../db/models";
+import { ApiError, handleApiError } from "../utils/errors";
+import { removeComment } from "../utils/comments";
+import { recordAdminAction } from "../utils/audit";
+import { postPath } from "../utils/post-files";
+
+const { reportsCollection, commentsCollection, reactionsCollection } =
+  await connectToDatabase();
+
+const MAX_REASON_LENGTH = 1000;
+const POST_ID_PATTERN = /^[\w-]+$/;
+
+const TargetTypeSchema = t.Union([t.Literal("post"), t.Literal("comment")]);
+
+const ReportSchema = t.Object({
+  id: t.String(),
+  targetType: TargetTypeSchema,
+  targetId: t.String(),
+  reporterId: t.String(),
+  reason: t.String(),
+  createdAt: t.Date(),
+  // Null once the reported content has been deleted
+  content: t.Nullable(
+    t.Object({
+      blogId: t.String(),
+      title: t.Nullable(t.String()),
+      text: t.String(),
+      authorId: t.Nullable(t.String()),
+    })
+  ),
+});
+
+async function targetExists(type: ReportTargetType, id: string) {
+  if (type === "post") {
+    return POST_ID_PATTERN.test(id) && existsSync(postPath(id));
+  }
+  if (!ObjectId.isValid(id)) return false;
+  const comment = await commentsCollection.findOne({ _id: new ObjectId(id) });
+  return !!comment && !comment.deleted;
+}
+
+/**
+ * Loads the reported post or comment for display to admins.
+ */
+async function loadReportedContent(type: ReportTargetType, id: string) {
+  if (type === "comment") {
+    if (!ObjectId.isValid(id)) return null;
+    const comment = await commentsCollection.findOne({
+      _id: new ObjectId(id),
+    });
+    if (!comment || comment.deleted) return null;
+    return {
+      blogId: comment.blogId,
+      title: null,
+      text: comment.content,
+      authorId: comment.authorId,
+    };
+  }
+
+  if (!POST_ID_PATTERN.test(id) || !existsSync(postPath(id))) return null;
+  const { data, content } = matter(await Bun.file(postPath(id)).text());
+  return { blogId: id, title: data.title ?? id, text: content, authorId: null };
+}
+
+async function deleteReportedContent(type: ReportTargetType, id: string) {
+  if (type === "comment") {
+    if (ObjectId.isValid(id)) await removeComment(new ObjectId(id));
+    return;
+  }
+
+  if (!POST_ID_PATTERN.test(id)) return;
+  await fs.rm(postPath(id), { force: true });
+  await Promise.all([
+    commentsCollection.deleteMany({ blogId: id }),
+    reactionsCollection.deleteMany({ blogId: id }),
+  ]);
+}
+
+export const reportsRoutes = new Elysia({ prefix: "/report" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers }).catch(() => null);
+    return { currentUser: session?.user };
+  })
+  .onError(handleApiError)
+  .post(
+    "/",
+    async ({ body: { targetType, targetId, reason }, currentUser }) => {
+      if (!currentUser) throw new ApiError(401, "Unauthorized");
+
+      const trimmedReason = reason.trim();
+      if (!trimmedReason) throw new ApiError(400, "A reason is required");
+      if (trimmedReason.length > MAX_REASON_LENGTH) {
+        throw new ApiError(
+          400,
+          `Reason must be at most ${MAX_REASON_LENGTH} characters`
+        );
+      }
+
+      if (!(await targetExists(targetType, targetId))) {
+        throw new ApiError(404, "Reported content not found");
+      }
+
+      // Reporting the same item again while it's open keeps the first report
+      const report = await reportsCollection.findOneAndUpdate(
+        {
+          targetType,
+          targetId,
+          reporterId: currentUser.id,
+          status: "open",
+        },
+        {
+          $setOnInsert: {
+            targetType,
+            targetId,
+            reporterId: currentUser.id,
+            reason: trimmedReason,
+            status: "open",
+            createdAt: new Date(),
+          },
+        },
+        { upsert: true, returnDocument: "after" }
+      );
+
+      return { reportId: report!._id.toString() };
+    },
+    {
+      body: t.Object({
+        targetType: TargetTypeSchema,
+        targetId: t.String({
+          description: "Blog post ID or comment ID being reported",
+        }),
+        reason: t.String({ description: "Why the content is being reported" }),
+      }),
+      response: t.Object({ reportId: t.String() }),
+      detail: {
+        summary: "Report a blog post or comment",
+        tags: ["reports"],
+        security: [{ session: [] }],
+        description:
+          "Files a report for admins to review. Reporting the same item again while the first report is still open returns the existing report.",
+      },
+    }
+  );
+
+export const adminReportsRoutes = new Elysia({ prefix: "/admin/reports" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers });
+    if (!session) {
+      throw new ApiError(401, "Unauthorized: Admin access required");
+    }
+    if (!session.user.role?.includes("admin")) {
+      throw new ApiError(403, "Forbidden: Admin access required");
+    }
+    return { currentUser: session.user };
+  })
+  .onError(handleApiError)
+  .get(
+    "/",
+    async () => {
+      const reports = await reportsCollection
+        .find({ status: "open" })
+        .sort({ createdAt: 1 })
+        .toArray();
+
+      return Promise.all(
+        reports.map(async (report) => ({
+          id: report._id.toString(),
+          targetType: report.targetType,
+          targetId: report.targetId,
+          reporterId: report.reporterId,
+          reason: report.reason,
+          createdAt: report.createdAt,
+          content: await loadReportedContent(
+            report.targetType,
+            report.targetId
+          ),
+        }))
+      );
+    },
+    {
+      response: t.Array(ReportSchema),
+      detail: {
+        summary: "List open reports - Admin only",
+        tags: ["admin", "reports"],
+        security: [{ session: [] }],
+        description:
+          "Returns open reports oldest first, each with the reported post or comment inline.",
+      },
+    }
+  )
+  .post(
+    "/:id/resolve",
+    async ({ params: { id }, body: { action }, currentUser }) => {
+      if (!ObjectId.isValid(id)) {
+        throw new ApiError(400, "Invalid report ID format");
+      }
+
+      const report = await reportsCollection.findOne({
+        _id: new ObjectId(id),
+      });
+      if (!report) throw new ApiError(404, "Report not found");
+      if (report.status !== "open") {
+        throw new ApiError(400, "Report is already resolved");
+      }
+
+      const resolution = {
+        status: "resolved" as const,
+        resolvedAt: new Date(),
+        resolvedBy: currentUser.id,
+        action,
+      };
+
+      if (action === "delete_content") {
+        await deleteReportedContent(report.targetType, report.targetId);
+        // Once the content is gone every open report on it is resolved
+        await reportsCollection.updateMany(
+          {
+            targetType: report.targetType,
+            targetId: report.targetId,
+            status: "open",
+          },
+          { $set: resolution }
+        );
+      } else {
+        await reportsCollection.updateOne(
+          { _id: report._id },
+          { $set: resolution }
+        );
+      }
+
+      await recordAdminAction(
+        currentUser.id,
+        `report.${action}`,
+        { type: "report", id },
+        { targetType: report.targetType, targetId: report.targetId }
+      );
+      return { success: true };
+    },
+    {
+      params: t.Object({
+        id: t.String({ description: "Report ID" }),
+      }),
+      body: t.Object({
+        action: t.Union([
+          t.Literal("dismiss", {
+            description: "Close the report and keep the content",
+          }),
+          t.Literal("delete_content", {
+            description: "Delete the reported post or comment",
+          }),
+        ]),
+      }),
+      response: t.Object({ success: t.Boolean() }),
+      detail: {
+        summary: "Resolve a report - Admin only",
+        tags: ["admin", "reports"],
+        security: [{ session: [] }],
+        description: `
+- **dismiss**: Closes the report and leaves the content in place
+- **delete_content**: Deletes the reported content and closes every open report on it. Comments with replies are tombstoned; deleting a post removes its markdown file along with its comments and reactions.
+        `,
+      },
+    }
+  );
@@ -0,0 +1,101 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TEST_PASSWORD,
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+
+describeWithDb("/sessions", () => {
+  let db: Db;
+  let app: TestApp;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+  });
+
+  // Signs the user in again, as if from another device
+  const signInAgain = async (user: TestUser): Promise<TestUser> => {
+    const res = await request(app, "/auth/api/sign-in/email", {
+      method: "POST",
+      body: { email: user.email, password: TEST_PASSWORD },
+      headers: { "user-agent": "Second Device" },
+    });
+    const { token } = await res.json();
+    return { ...user, token, headers: { authorization: `Bearer ${token}` } };
+  };
+
+  const listSessions = async (user: TestUser) =>
+    (await request(app, "/sessions", { user })).json();
+
+  const revoke = (user: TestUser, body: object) =>
+    request(app, "/sessions/revoke", { method: "POST", user, body });
+
+  test("lists every session and flags the current one", async () => {
+    const laptop = await createTestUser("devices");
+    const phone = await signInAgain(laptop);
+
+    const sessions = await listSessions(phone);
+    expect(sessions).toHaveLength(2);
+    expect(sessions.filter((s: { current: boolean }) => s.current)).toEqual([
+      expect.objectContaining({ userAgent: "Second Device" }),
+    ]);
+    for (const session of sessions) expect(session).not.toHaveProperty("token");
+    expect((await request(app, "/sessions")).status).toBe(401);
+  });
+
+  test("revoking a session by ID signs that device out", async () => {
+    const laptop = await createTestUser("revoker");
+    const phone = await signInAgain(laptop);
+    const other = (await listSessions(laptop)).find(
+      (s: { current: boolean }) => !s.current
+    );
+
+    const res = await revoke(laptop, { sessionId: other.id });
+    expect(await res.json()).toEqual({ message: "Session revoked" });
+    expect((await request(app, "/me", { user: phone })).status).toBe(401);
+    expect(await listSessions(laptop)).toHaveLength(1);
+  });
+
+  test("another user's session can't be revoked", async () => {
+    const owner = await createTestUser("owner");
+    const [session] = await listSessions(owner);
+    const stranger = await createTestUser("stranger");
+
+    const res = await revoke(stranger, { sessionId: session.id });
+    expect(res.status).toBe(404);
+    expect((await request(app, "/me", { user: owner })).status).toBe(200);
+  });
+
+  test("allOthers keeps only the current session", async () => {
+    const laptop = await createTestUser("tidy");
+    const phone = await signInAgain(laptop);
+    const tablet = await signInAgain(laptop);
+
+    await revoke(laptop, { allOthers: true });
+    for (const device of [phone, tablet]) {
+      expect((await request(app, "/me", { user: device })).status).toBe(401);
+    }
+    expect((await request(app, "/me", { user: laptop })).status).toBe(200);
+    expect((await revoke(laptop, {})).status).toBe(400);
+  });
+
+  test("/auth/validate refuses a session whose user was deleted", async () => {
+    const user = await createTestUser("removed");
+    const valid = await request(app, "/auth/validate", { user });
+    expect((await valid.json()).user).toMatchObject({
+      id: user.id,
+      email: user.email,
+    });
+
+    // Removed straight from the database, so the session survives
+    await db.collection("user").deleteOne({ _id: new ObjectId(user.id) });
+    const res = await request(app, "/auth/validate", { user });
+    expect(res.status).toBe(401);
+    expect((await res.json()).error.code).toBe("invalid_session");
+  });
+});
@@ -0,0 +1,139 @@
+import { Elysia, t } from "elysia";
+import { ObjectId } from "mongodb";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { ApiError, handleApiError } from "../utils/errors";
+
+const { userCollection } = await connectToDatabase();
+
+const SessionSchema = t.Object({
+  id: t.String(),
+  createdAt: t.Date(),
+  updatedAt: t.Date(),
+  expiresAt: t.Date(),
+  userAgent: t.Union([t.String(), t.Null()]),
+  ipAddress: t.Union([t.String(), t.Null()]),
+  current: t.Boolean(),
+});
+
+// Session tokens are never sent to clients, sessions are referred to by ID
+export const sessionsRoutes = new Elysia({ prefix: "/sessions" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers }).catch(() => null);
+    return { currentSession: session?.session };
+  })
+  .onError(handleApiError)
+  .get(
+    "/",
+    async ({ request: { headers }, currentSession }) => {
+      if (!currentSession) throw new ApiError(401, "Unauthorized");
+
+      const sessions = await auth.api.listSessions({ headers });
+      return sessions
+        .filter((s) => new Date(s.expiresAt) > new Date())
+        .map((s) => ({
+          id: s.id,
+          createdAt: new Date(s.createdAt),
+          updatedAt: new Date(s.updatedAt),
+          expiresAt: new Date(s.expiresAt),
+          userAgent: s.userAgent ?? null,
+          ipAddress: s.ipAddress ?? null,
+          current: s.id === currentSession.id,
+        }));
+    },
+    {
+      response: t.Array(SessionSchema),
+      detail: {
+        summary: "List the current user's active sessions",
+        tags: ["sessions"],
+        description:
+          "Returns every unexpired session for the logged-in user with its device (user agent) and IP, flagging the one making the request.",
+      },
+    }
+  )
+  .post(
+    "/revoke",
+    async ({ request: { headers }, body, currentSession }) => {
+      if (!currentSession) throw new ApiError(401, "Unauthorized");
+
+      if (body.allOthers) {
+        await auth.api.revokeOtherSessions({ headers });
+        return { message: "Other sessions revoked" };
+      }
+
+      if (!body.sessionId) {
+        throw new ApiError(400, "sessionId or allOthers is required");
+      }
+
+      // Look the token up server-side so only the caller's sessions match
+      const sessions = await auth.api.listSessions({ headers });
+      const target = sessions.find((s) => s.id === body.sessionId);
+      if (!target) throw new ApiError(404, "Session not found");
+
+      await auth.api.revokeSession({ headers, body: { token: target.token } });
+      return { message: "Session revoked" };
+    },
+    {
+      body: t.Object({
+        sessionId: t.Optional(t.String()),
+        allOthers: t.Optional(t.Boolean()),
+      }),
+      response: t.Object({ message: t.String() }),
+      detail: {
+        summary: "Revoke a session",
+        tags: ["sessions"],
+        description:
+          "Revokes one of the current user's sessions by ID, or every session except the current one when `allOthers` is true. Revoking the current session logs the caller out.",
+      },
+    }
+  );
+
+// Unversioned like the rest of /auth, so clients can check a stored token
+// before they know which API version to use
+export const authValidateRoutes = new Elysia({ prefix: "/auth" })
+  .onError(handleApiError)
+  .get(
+    "/validate",
+    async ({ request: { headers } }) => {
+      // Skip any cached session data so revoked sessions are caught
+      const session = await auth.api
+        .getSession({ headers, query: { disableCookieCache: true } })
+        .catch(() => null);
+      if (!session) throw new ApiError(401, "Unauthorized");
+
+      // The session can outlive its account if the user was removed
+      // directly rather than through account deletion
+      const user = ObjectId.isValid(session.user.id)
+        ? await userCollection.findOne({ _id: new ObjectId(session.user.id) })
+        : null;
+      if (!user) throw new ApiError(401, "Unauthorized", "invalid_session");
+
+      return {
+        user: {
+          id: user._id.toString(),
+          name: user.name || "",
+          email: user.email ?? null,
+          role: user.role ?? null,
+        },
+        expiresAt: new Date(session.session.expiresAt),
+      };
+    },
+    {
+      response: t.Object({
+        user: t.Object({
+          id: t.String(),
+          name: t.String(),
+          email: t.Nullable(t.String()),
+          role: t.Nullable(t.String()),
+        }),
+        expiresAt: t.Date(),
+      }),
+      detail: {
+        summary: "Check that the current session is still valid",
+        tags: ["sessions"],
+        security: [{ session: [] }],
+        description:
+          "Returns the user and session expiry when the session cookie or bearer token belongs to an unexpired, unrevoked session whose account still exists. Otherwise returns a 401 with the usual session error codes. Useful for clients checking a stored token on startup.",
+      },
+    }
+  );
@@ -0,0 +1,123 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { writeTestPost } from "../test-utils/posts";
+
+describeWithDb("/stats", () => {
+  let db: Db;
+  let app: TestApp;
+  let admin: TestUser;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+    admin = await createTestUser("admin", { role: "admin" });
+  });
+
+  const addComment = (authorId: string, deleted = false) =>
+    db.collection("comments").insertOne({
+      blogId: "published",
+      authorId,
+      content: "A comment",
+      accepted: true,
+      deleted,
+      createdAt: new Date(),
+      parentId: null,
+    });
+
+  test("admin stats count users, posts, comments and reactions", async () => {
+    const reader = await createTestUser("reader");
+    const veteran = await createTestUser("veteran");
+    await db
+      .collection("user")
+      .updateOne(
+        { _id: new ObjectId(veteran.id) },
+        { $set: { createdAt: new Date("2020-01-01") } }
+      );
+
+    await writeTestPost("published");
+    await writeTestPost("draft", { draft: true });
+    await writeTestPost("scheduled", { publishAt: "2999-01-01T00:00:00Z" });
+
+    const { insertedId } = await addComment(reader.id);
+    await addComment(reader.id, true);
+    await db.collection("reactions").insertOne({
+      blogId: "published",
+      userId: reader.id,
+      type: "like",
+      createdAt: new Date(),
+    });
+    await db.collection("comment_reactions").insertOne({
+      commentId: insertedId.toString(),
+      userId: veteran.id,
+      type: "like",
+      createdAt: new Date(),
+    });
+
+    const res = await request(app, "/admin/stats", { user: admin });
+    expect(await res.json()).toEqual({
+      users: { total: 3, newLastWeek: 2 },
+      posts: { published: 1, draft: 2 },
+      comments: 1,
+      reactions: { posts: 1, comments: 1 },
+      activeChessGames: 0,
+    });
+
+    expect((await request(app, "/admin/stats")).status).toBe(401);
+    const forbidden = await request(app, "/admin/stats", { user: reader });
+    expect(forbidden.status).toBe(403);
+  });
+});
+
+describeWithDb("/stats public", () => {
+  let db: Db;
+  let app: TestApp;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    app = await createTestApp();
+  });
+
+  const react = (blogId: string, userId: string, type: string) =>
+    db
+      .collection("reactions")
+      .insertOne({ blogId, userId, type, createdAt: new Date() });
+
+  test("counts published posts, their likes, users and games", async () => {
+    const reader = await createTestUser("public-reader");
+    const other = await createTestUser("public-other");
+    await writeTestPost("live");
+    await writeTestPost("also-live");
+    await writeTestPost("hidden", { draft: true });
+    await react("live", reader.id, "like");
+    await react("also-live", other.id, "like");
+    await react("live", other.id, "dislike");
+    await react("hidden", reader.id, "like"); // Not on a published post
+    await db.collection("chess_games").insertOne({
+      white: { userId: reader.id, username: reader.name },
+      black: { userId: other.id, username: other.name },
+      winner: "white",
+      reason: "resignation",
+      moves: ["e4", "e5"],
+      finalFen: "8/8/8/8/8/8/8/8 w - - 0 1",
+      timeControl: 300,
+      isPrivate: false,
+      startedAt: new Date(),
+      createdAt: new Date(),
+    });
+
+    const stats = async () => (await request(app, "/stats")).json();
+    const expected = { posts: 2, likes: 2, users: 2, chessGames: 1 };
+    expect(await stats()).toEqual(expected);
+
+    // Served from the cache until PUBLIC_STATS_TTL_MS passes
+    await createTestUser("public-latecomer");
+    expect(await stats()).toEqual(expected);
+  });
+});
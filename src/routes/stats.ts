@@ -0,0 +1,185 @@
+import { Elysia, t } from "elysia";
+import { auth } from "../auth";
+import { connectToDatabase } from "../db/connect";
+import { ApiError, handleApiError } from "../utils/errors";
+import { countActiveGames } from "../webhooks/chess";
+import { getBlogsFromFiles, isPublished } from "./blog";
+import { recomputeStoredImageSizes } from "./images";
+import { recordAdminAction } from "../utils/audit";
+
+const {
+  userCollection,
+  commentsCollection,
+  reactionsCollection,
+  commentReactionsCollection,
+  chessGamesCollection,
+} = await connectToDatabase();
+
+const NEW_USER_WINDOW_MS = 7 * 24 * 60 * 60 * 1000;
+
+// How long the public counts are reused before being recomputed
+const PUBLIC_STATS_TTL_MS = parseInt(
+  process.env.PUBLIC_STATS_TTL_MS || "60000"
+);
+
+interface PublicStats {
+  posts: number;
+  likes: number;
+  users: number;
+  chessGames: number;
+}
+
+let publicStats: { value: Promise<PublicStats>; expiresAt: number } | null =
+  null;
+
+async function computePublicStats(): Promise<PublicStats> {
+  const posts = await getBlogsFromFiles();
+  const [likes, users, chessGames] = await Promise.all([
+    reactionsCollection.countDocuments({
+      type: "like",
+      blogId: { $in: posts.map((p) => p.id) },
+    }),
+    userCollection.estimatedDocumentCount(),
+    chessGamesCollection.estimatedDocumentCount(),
+  ]);
+  return { posts: posts.length, likes, users, chessGames };
+}
+
+/**
+ * The public counts, cached for PUBLIC_STATS_TTL_MS. Concurrent requests
+ * after expiry share one recomputation.
+ */
+function getPublicStats() {
+  const now = Date.now();
+  if (!publicStats || publicStats.expiresAt <= now) {
+    const value = computePublicStats();
+    publicStats = { value, expiresAt: now + PUBLIC_STATS_TTL_MS };
+    // Don't keep serving a failed computation until it expires
+    value.catch(() => {
+      if (publicStats?.value === value) publicStats = null;
+    });
+  }
+  return publicStats.value;
+}
+
+export const publicStatsRoutes = new Elysia({ prefix: "/stats" })
+  .onError(handleApiError)
+  .get("/", () => getPublicStats(), {
+    response: t.Object({
+      posts: t.Number({ description: "Published blog posts" }),
+      likes: t.Number({ description: "Likes across published posts" }),
+      users: t.Number({ description: "Registered users" }),
+      chessGames: t.Number({ description: "Finished, recorded chess games" }),
+    }),
+    detail: {
+      summary: "Get public site statistics",
+      tags: ["stats"],
+      description: `Aggregate counts for the site, safe to show publicly. Cached for ${
+        PUBLIC_STATS_TTL_MS / 1000
+      } seconds, so new posts, likes, users and games can take that long to appear. Games against the engine aren't recorded and so aren't counted.`,
+    },
+  });
+
+export const adminStatsRoutes = new Elysia({ prefix: "/admin" })
+  .derive(async ({ request: { headers } }) => {
+    const session = await auth.api.getSession({ headers });
+    if (!session) {
+      throw new ApiError(401, "Unauthorized: Admin access required");
+    }
+    if (!session.user.role?.includes("admin")) {
+      throw new ApiError(403, "Forbidden: Admin access required");
+    }
+    return { currentUser: session.user };
+  })
+  .onError(handleApiError)
+  .get(
+    "/stats",
+    async () => {
+      const newUsersSince = new Date(Date.now() - NEW_USER_WINDOW_MS);
+
+      const [users, posts, comments, postReactions, commentReactions] =
+        await Promise.all([
+          // Both user counts in one round-trip
+          userCollection
+            .aggregate<{ total: number; newLastWeek: number }>([
+              {
+                $group: {
+                  _id: null,
+                  total: { $sum: 1 },
+                  newLastWeek: {
+                    $sum: {
+                      $cond: [{ $gte: ["$createdAt", newUsersSince] }, 1, 0],
+                    },
+                  },
+                },
+              },
+            ])
+            .next(),
+          getBlogsFromFiles(true),
+          commentsCollection.countDocuments({ deleted: { $ne: true } }),
+          reactionsCollection.estimatedDocumentCount(),
+          commentReactionsCollection.estimatedDocumentCount(),
+        ]);
+
+      // Scheduled posts count as drafts until they go live
+      const draftPosts = posts.filter((p) => !isPublished(p)).length;
+
+      return {
+        users: {
+          total: users?.total ?? 0,
+          newLastWeek: users?.newLastWeek ?? 0,
+        },
+        posts: {
+          published: posts.length - draftPosts,
+          draft: draftPosts,
+        },
+        comments,
+        reactions: {
+          posts: postReactions,
+          comments: commentReactions,
+        },
+        activeChessGames: countActiveGames(),
+      };
+    },
+    {
+      response: t.Object({
+        users: t.Object({ total: t.Number(), newLastWeek: t.Number() }),
+        posts: t.Object({ published: t.Number(), draft: t.Number() }),
+        comments: t.Number(),
+        reactions: t.Object({ posts: t.Number(), comments: t.Number() }),
+        activeChessGames: t.Number(),
+      }),
+      detail: {
+        summary: "Get site statistics - Admin only",
+        tags: ["admin"],
+        security: [{ session: [] }],
+        description:
+          "Returns user, post, comment and reaction counts, sign-ups in the last 7 days and the number of chess games currently being bid on or played. Posts are drafts when their frontmatter has `draft: true`.",
+      },
+    }
+  )
+  .post(
+    "/recompute-counts",
+    async ({ currentUser }) => {
+      const imagesStoredSize = await recomputeStoredImageSizes();
+      await recordAdminAction(currentUser.id, "counts.recompute", null, {
+        imagesStoredSize,
+      });
+      return { imagesStoredSize };
+    },
+    {
+      response: t.Object({
+        imagesStoredSize: t.Object({
+          checked: t.Number({ description: "Users checked" }),
+          corrected: t.Number({ description: "Users whose value was wrong" }),
+        }),
+      }),
+      detail: {
+        summary: "Recompute stored counters - Admin only",
+        tags: ["admin"],
+        security: [{ session: [] }],
+        description:
+          "Repair tool for counters stored on user documents. Recalculates each user's `imagesStoredSize` (the image storage quota) from the files on disk in a single pass and reports how many users were corrected. Post like/dislike counts are always counted live from reactions, so they never need recomputing.",
+      },
+    }
+  );
@@ -0,0 +1,45 @@
+import { Elysia } from "elysia";
+import { avatarRoutes } from "./avatar";
+import { blogRoutes } from "./blog";
+import { feedRoutes } from "./feeds";
+import { commentsRoutes } from "./comments";
+import { imageRoutes } from "./images";
+import { profileRoutes, adminProfileRoutes } from "./profile";
+import { leaderboardsRoutes } from "./leaderboards";
+import { connectionsRoutes } from "./connections"; // Admin routes for connections
+import { notificationsRoutes } from "./notifications";
+import { quizspireRoutes } from "./quizspire";
+import { foldersRoutes } from "./folders";
+import { chessRoutes } from "./chess";
+import { sessionsRoutes } from "./sessions";
+import { meRoutes } from "./me";
+import { mediaRoutes, adminMediaRoutes } from "./media";
+import { reportsRoutes, adminReportsRoutes } from "./reports";
+import { adminStatsRoutes, publicStatsRoutes } from "./stats";
+import { adminAuditRoutes } from "./audit";
+
+// Version 1 of the API, served under /api/v1 and (for existing clients)
+// at the unversioned root paths
+export const v1Routes = new Elysia()
+  .use(avatarRoutes) // Mounts the avatar routes
+  .use(blogRoutes) // Mounts the blog routes
+  .use(feedRoutes) // Mounts the blog feeds
+  .use(commentsRoutes) // Mounts the comment editing routes
+  .use(imageRoutes) // Mounts the image routes
+  .use(profileRoutes) // Mounts the profile routes
+  .use(adminProfileRoutes) // Mounts the admin profile routes
+  .use(leaderboardsRoutes) // Mounts the leaderboards routes
+  .use(connectionsRoutes) // Mounts the connections admin routes
+  .use(notificationsRoutes) // Mounts the notifications routes
+  .use(quizspireRoutes) // Mounts the quizspire routes
+  .use(foldersRoutes) // Mounts the folders routes
+  .use(chessRoutes) // Mounts the chess routes
+  .use(sessionsRoutes) // Mounts the session management routes
+  .use(meRoutes) // Mounts the current user routes
+  .use(mediaRoutes) // Mounts the media serving routes
+  .use(adminMediaRoutes) // Mounts the admin media upload routes
+  .use(reportsRoutes) // Mounts the content report routes
+  .use(adminReportsRoutes) // Mounts the admin report queue routes
+  .use(adminStatsRoutes) // Mounts the admin statistics route
+  .use(publicStatsRoutes) // Mounts the public statistics route
+  .use(adminAuditRoutes); // Mounts the admin audit log
@@ -0,0 +1,5 @@
+import { Elysia } from "elysia";
+
+// Version 2 of the API, served under /api/v2. Endpoints that need breaking
+// changes get their new form here while v1 keeps the old behaviour.
+export const v2Routes = new Elysia();
@@ -0,0 +1,47 @@
+import { Elysia } from "elysia";
+import type { TestUser } from "./db";
+
+/**
+ * The API as src/index.ts mounts it (auth, the session check and the v1
+ * routes) without the rate limits, docs or sockets. Create it after
+ * resetTestDatabase.
+ */
+export async function createTestApp() {
+  const { auth } = await import("../auth");
+  const { authValidateRoutes } = await import("../routes/sessions");
+  const { v1Routes } = await import("../routes/v1");
+  const { withCookieOnlyLogin } = await import("../utils/cookie-login");
+
+  return new Elysia()
+    .use(authValidateRoutes)
+    .mount("/", withCookieOnlyLogin(auth.handler))
+    .use(v1Routes);
+}
+
+export type TestApp = Awaited<ReturnType<typeof createTestApp>>;
+
+export interface RequestOptions {
+  method?: string;
+  body?: unknown; // Sent as JSON, or as multipart for FormData
+  user?: TestUser; // Authenticates with their bearer token
+  headers?: Record<string, string>;
+}
+
+export function request(
+  app: TestApp,
+  path: string,
+  { method = "GET", body, user, headers = {} }: RequestOptions = {}
+) {
+  const init = {
+    method,
+    headers: { ...user?.headers, ...headers },
+    body: undefined as BodyInit | undefined,
+  };
+  if (body instanceof FormData) {
+    init.body = body;
+  } else if (body !== undefined) {
+    init.body = JSON.stringify(body);
+    init.headers["content-type"] = "application/json";
+  }
+  return app.handle(new Request(`http://localhost${path}`, init));
+}
@@ -0,0 +1,92 @@
+import { describe } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import { mkdtempSync } from "node:fs";
+import { mkdir, rm } from "node:fs/promises";
+import { tmpdir } from "node:os";
+import path from "node:path";
+
+// Tests that need MongoDB run against TEST_MONGO_URI and are skipped when
+// it isn't set. The database is dropped before each suite, so its name has
+// to contain "test".
+export const TEST_MONGO_URI = process.env.TEST_MONGO_URI;
+
+export const describeWithDb = describe.skipIf(!TEST_MONGO_URI);
+
+// Uploads and posts go to a fresh directory for each run instead of
+// DATA_DIR and src/blogs
+export const TEST_DATA_DIR = mkdtempSync(path.join(tmpdir(), "website-test-"));
+export const TEST_BLOGS_DIR = path.join(TEST_DATA_DIR, "blogs");
+
+export const TEST_PASSWORD = "correct horse battery staple";
+
+export interface TestUser {
+  id: string;
+  name: string;
+  email: string;
+  token: string; // Session token, sent as a bearer token
+  headers: Record<string, string>;
+}
+
+/**
+ * Points the app at the test database, TEST_DATA_DIR and TEST_BLOGS_DIR,
+ * empties all three and re-runs the migrations. Modules that connect or
+ * read their settings when they're loaded have to be imported afterwards,
+ * with `await import(...)`.
+ */
+export async function resetTestDatabase(): Promise<Db> {
+  process.env.MONGO_URI = TEST_MONGO_URI;
+  process.env.MAIL_TRANSPORT = "log";
+  process.env.RUN_MIGRATIONS = "true";
+  process.env.DATA_DIR = TEST_DATA_DIR;
+  process.env.BLOGS_DIR = TEST_BLOGS_DIR;
+
+  const { connectToDatabase } = await import("../db/connect");
+  const { runMigrations } = await import("../db/migrations");
+  const { db } = await connectToDatabase();
+  if (!db.databaseName.includes("test")) {
+    throw new Error(
+      `Refusing to drop "${db.databaseName}", TEST_MONGO_URI must name a test database`
+    );
+  }
+
+  await db.dropDatabase();
+  await runMigrations(db);
+  await rm(TEST_DATA_DIR, { recursive: true, force: true });
+  await mkdir(TEST_BLOGS_DIR, { recursive: true });
+  return db;
+}
+
+let userCount = 0;
+
+/**
+ * Signs up a user with TEST_PASSWORD. `role` is written straight to the
+ * database, e.g. "admin".
+ */
+export async function createTestUser(
+  name = `user${++userCount}`,
+  { role }: { role?: string } = {}
+): Promise<TestUser> {
+  const { auth } = await import("../auth");
+  const email = `${name.toLowerCase()}@example.com`;
+  const { token, user } = await auth.api.signUpEmail({
+    body: { name, email, password: TEST_PASSWORD },
+  });
+  if (!token) throw new Error(`Sign-up for ${email} didn't start a session`);
+
+  if (role) {
+    const { connectToDatabase } = await import("../db/connect");
+    const { userCollection } = await connectToDatabase();
+    await userCollection.updateOne(
+      { _id: new ObjectId(user.id) },
+      { $set: { role } }
+    );
+  }
+
+  return {
+    id: user.id,
+    name,
+    email,
+    token,
+    headers: { authorization: `Bearer ${token}` },
+  };
+}
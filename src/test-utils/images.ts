@@ -0,0 +1,20 @@
+// A valid 1x1 PNG, small enough to inline and readable by Jimp
+const PIXEL_PNG = Buffer.from(
+  "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==",
+  "base64"
+);
+
+/**
+ * A PNG upload, or a file that only claims to be one when `bytes` is given.
+ */
+export const pngFile = (name = "pixel.png", bytes: BlobPart = PIXEL_PNG) =>
+  new File([bytes], name, { type: "image/png" });
+
+/**
+ * One file as a multipart form under `field`.
+ */
+export function fileForm(field: string, file: File) {
+  const form = new FormData();
+  form.append(field, file);
+  return form;
+}
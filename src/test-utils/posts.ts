@@ -0,0 +1,26 @@
+import matter from "gray-matter";
+import { writeFile } from "node:fs/promises";
+import path from "node:path";
+import { TEST_BLOGS_DIR } from "./db";
+
+/**
+ * Writes a post to TEST_BLOGS_DIR. Anything besides `content` is
+ * frontmatter merged over a valid title, snippet and dates, e.g.
+ * `{ draft: true }` or `{ tags: ["a"] }`.
+ */
+export async function writeTestPost(
+  id: string,
+  { content = `Content of ${id}`, ...frontmatter }: Record<string, any> = {}
+) {
+  const date = new Date("2024-01-01T00:00:00Z").toISOString();
+  await writeFile(
+    path.join(TEST_BLOGS_DIR, `${id}.md`),
+    matter.stringify(content, {
+      title: `Post ${id}`,
+      snippet: `Snippet of ${id}`,
+      createdAt: date,
+      updatedAt: date,
+      ...frontmatter,
+    })
+  );
+}
@@ -0,0 +1,203 @@
+// In-memory stand-ins for Socket.IO namespaces and sockets, so socket
+// handlers can be driven from tests without a server or a network
+
+type Handler = (...args: any[]) => unknown;
+type SocketMiddleware = (packet: any[], next: (error?: Error) => void) => void;
+type ConnectMiddleware = (
+  socket: FakeSocket,
+  next: (error?: Error) => void
+) => void;
+
+let nextSocketId = 1;
+
+export interface ConnectOptions {
+  headers?: Record<string, string>;
+  query?: Record<string, string>;
+  auth?: Record<string, unknown>;
+}
+
+/**
+ * A connected client as the server sees it. What the server emits is kept
+ * in `emitted`; `send` plays a client message through the socket's
+ * middleware and handlers.
+ */
+export class FakeSocket {
+  id = `socket-${nextSocketId++}`;
+  connected = true;
+  data: Record<string, any> = {};
+  rooms = new Set<string>();
+  handshake: Required<ConnectOptions>;
+  emitted: { event: string; args: any[] }[] = [];
+
+  private handlers = new Map<string, Handler[]>();
+  private middleware: SocketMiddleware[] = [];
+  private waiters: { event: string; resolve: (arg: any) => void }[] = [];
+
+  constructor(public nsp: FakeNamespace, options: ConnectOptions = {}) {
+    this.handshake = {
+      headers: options.headers ?? {},
+      query: options.query ?? {},
+      auth: options.auth ?? {},
+    };
+    this.rooms.add(this.id);
+  }
+
+  on(event: string, handler: Handler) {
+    this.handlers.set(event, [...(this.handlers.get(event) ?? []), handler]);
+    return this;
+  }
+
+  use(fn: SocketMiddleware) {
+    this.middleware.push(fn);
+    return this;
+  }
+
+  // Nothing reaches a client after it has disconnected
+  emit(event: string, ...args: any[]) {
+    if (!this.connected) return true;
+    this.emitted.push({ event, args });
+    const waiting = this.waiters.filter((w) => w.event === event);
+    this.waiters = this.waiters.filter((w) => w.event !== event);
+    for (const { resolve } of waiting) resolve(args[0]);
+    return true;
+  }
+
+  join(room: string) {
+    this.rooms.add(room);
+  }
+
+  leave(room: string) {
+    this.rooms.delete(room);
+  }
+
+  disconnect(_close?: boolean) {
+    this.close("server namespace disconnect");
+    return this;
+  }
+
+  /**
+   * The client going away without the server asking, e.g. a closed tab.
+   */
+  drop(reason = "transport close") {
+    this.close(reason);
+  }
+
+  /**
+   * Sends a message from the client. Resolves once its handlers have run.
+   */
+  async send(event: string, ...args: any[]) {
+    const packet = [event, ...args];
+    for (const fn of this.middleware) {
+      let passed = false;
+      fn(packet, (error) => (passed = !error));
+      if (!passed) return;
+    }
+    await this.fire(event, ...args);
+  }
+
+  /**
+   * First argument of every `event` the server sent this socket.
+   */
+  received(event: string): any[] {
+    return this.emitted.filter((e) => e.event === event).map((e) => e.args[0]);
+  }
+
+  /**
+   * First argument of the latest `event`, or undefined if none was sent.
+   */
+  last(event: string): any {
+    const all = this.received(event);
+    return all[all.length - 1];
+  }
+
+  /**
+   * Like `last`, but waits for the event if it hasn't been sent yet, for
+   * handlers that carry on after `send` or `connect` resolves.
+   */
+  waitFor(event: string): Promise<any> {
+    if (this.received(event).length > 0) {
+      return Promise.resolve(this.last(event));
+    }
+    return new Promise((resolve) => this.waiters.push({ event, resolve }));
+  }
+
+  private close(reason: string) {
+    if (!this.connected) return;
+    this.connected = false;
+    this.nsp.sockets.delete(this.id);
+    this.fire("disconnect", reason);
+  }
+
+  private async fire(event: string, ...args: any[]) {
+    const handlers = this.handlers.get(event) ?? [];
+    await Promise.all(handlers.map((handler) => handler(...args)));
+  }
+}
+
+/**
+ * A namespace that clients join with `connect`, which runs the namespace
+ * middleware and waits for every "connection" handler to finish.
+ */
+export class FakeNamespace {
+  sockets = new Map<string, FakeSocket>();
+
+  private handlers: Handler[] = [];
+  private middleware: ConnectMiddleware[] = [];
+
+  constructor(public name = "/") {}
+
+  on(event: string, handler: Handler) {
+    if (event === "connection") this.handlers.push(handler);
+    return this;
+  }
+
+  use(fn: ConnectMiddleware) {
+    this.middleware.push(fn);
+    return this;
+  }
+
+  emit(event: string, ...args: any[]) {
+    for (const socket of this.sockets.values()) socket.emit(event, ...args);
+    return true;
+  }
+
+  to(room: string) {
+    return {
+      emit: (event: string, ...args: any[]) => {
+        for (const socket of this.sockets.values()) {
+          if (socket.rooms.has(room)) socket.emit(event, ...args);
+        }
+        return true;
+      },
+    };
+  }
+
+  /**
+   * Drops every connected client, e.g. between tests.
+   */
+  disconnectAll() {
+    for (const socket of [...this.sockets.values()]) socket.drop();
+  }
+
+  /**
+   * Connects a new client. A middleware rejecting it leaves the socket
+   * disconnected with a "connect_error" instead.
+   */
+  async connect(options: ConnectOptions = {}) {
+    const socket = new FakeSocket(this, options);
+    for (const fn of this.middleware) {
+      const error = await new Promise<Error | undefined>((resolve) =>
+        fn(socket, resolve)
+      );
+      if (error) {
+        socket.emit("connect_error", { message: error.message });
+        socket.connected = false;
+        return socket;
+      }
+    }
+
+    this.sockets.set(socket.id, socket);
+    await Promise.all(this.handlers.map((handler) => handler(socket)));
+    return socket;
+  }
+}
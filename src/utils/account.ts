@@ -0,0 +1,86 @@
+import type { ClientSession } from "mongodb";
+import { connectToDatabase } from "../db/connect";
+
+const {
+  client,
+  commentsCollection,
+  reactionsCollection,
+  commentReactionsCollection,
+  reportsCollection,
+  flashcardsCollection,
+  mediaCollection,
+  chessGamesCollection,
+} = await connectToDatabase();
+
+// Stands in for the author of content whose account was deleted
+export const DELETED_USER_ID = "deleted";
+
+// What a standalone server (no replica set) answers to a transaction
+const ILLEGAL_OPERATION = 20;
+
+/**
+ * Cleans up everything tied to a user before their account is deleted:
+ * active chess games are forfeited, comments, reports and finished games are
+ * kept but anonymized so threads stay intact, and reactions and flashcard
+ * decks are removed.
+ *
+ * The database writes share a transaction. Standalone servers don't support
+ * transactions, so there they're applied one by one; each is idempotent, and
+ * as better-auth only removes the user once this succeeds, a failed
+ * deletion can simply be retried.
+ */
+export async function deleteUserContent(userId: string) {
+  // Imported lazily as the chess module depends on auth. Runs first so the
+  // forfeited games are saved before the history is anonymized
+  const { forfeitUserGames } = await import("../webhooks/chess");
+  await forfeitUserGames(userId);
+
+  const session = client.startSession();
+  try {
+    await session.withTransaction(() => removeUserContent(userId, session));
+  } catch (error: any) {
+    if (error?.code !== ILLEGAL_OPERATION) throw error;
+    await removeUserContent(userId);
+  } finally {
+    await session.endSession();
+  }
+}
+
+/**
+ * The writes behind deleteUserContent. Operations in a transaction can't
+ * run concurrently, so they're awaited in turn.
+ */
+async function removeUserContent(userId: string, session?: ClientSession) {
+  const options = { session };
+  await commentsCollection.updateMany(
+    { authorId: userId },
+    { $set: { authorId: DELETED_USER_ID } },
+    options
+  );
+  await reportsCollection.updateMany(
+    { reporterId: userId },
+    { $set: { reporterId: DELETED_USER_ID } },
+    options
+  );
+  await mediaCollection.updateMany(
+    { uploadedBy: userId },
+    { $set: { uploadedBy: DELETED_USER_ID } },
+    options
+  );
+  for (const side of ["white", "black"] as const) {
+    await chessGamesCollection.updateMany(
+      { [`${side}.userId`]: userId },
+      {
+        $set: {
+          [`${side}.userId`]: DELETED_USER_ID,
+          [`${side}.username`]: DELETED_USER_ID,
+        },
+      },
+      options
+    );
+  }
+  // Post and comment counts are computed from these, so they adjust too
+  await reactionsCollection.deleteMany({ userId }, options);
+  await commentReactionsCollection.deleteMany({ userId }, options);
+  await flashcardsCollection.deleteMany({ userId }, options);
+}
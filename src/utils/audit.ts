@@ -0,0 +1,71 @@
+import { ObjectId } from "mongodb";
+import { connectToDatabase } from "../db/connect";
+import { AuditTargetType } from "../db/models";
+import { mongoCursorFilter, Cursor } from "./pagination";
+import { ApiError } from "./errors";
+
+const { adminAuditCollection } = await connectToDatabase();
+
+export interface AuditFilters {
+  adminId?: string;
+  action?: string;
+}
+
+/**
+ * Records an admin action once it has succeeded. A failed write is logged
+ * rather than thrown, since the action itself has already happened.
+ */
+export async function recordAdminAction(
+  adminId: string,
+  action: string,
+  target: { type: AuditTargetType; id: string } | null,
+  details?: Record<string, unknown>
+) {
+  try {
+    await adminAuditCollection.insertOne({
+      adminId,
+      action,
+      targetType: target?.type ?? null,
+      targetId: target?.id ?? null,
+      ...(details && { details }),
+      createdAt: new Date(),
+    });
+  } catch (error) {
+    console.error(`Failed to record admin action ${action}:`, error);
+  }
+}
+
+const auditFilter = (filters: AuditFilters) => ({
+  ...(filters.adminId && { adminId: filters.adminId }),
+  ...(filters.action && { action: filters.action }),
+});
+
+/**
+ * A page of audit entries, newest first. Fetches one extra entry so callers
+ * can tell whether another page exists.
+ */
+export async function findAdminActions(
+  filters: AuditFilters,
+  page: { limit: number; cursor?: Cursor; offset?: number }
+) {
+  let filter: any = auditFilter(filters);
+  if (page.cursor) {
+    if (!ObjectId.isValid(page.cursor.id)) {
+      throw new ApiError(400, "Invalid cursor");
+    }
+    filter = {
+      ...filter,
+      ...mongoCursorFilter(page.cursor, (id) => new ObjectId(id)),
+    };
+  }
+
+  let find = adminAuditCollection
+    .find(filter)
+    .sort({ createdAt: -1, _id: -1 });
+  if (!page.cursor && page.offset) find = find.skip(page.offset);
+  return find.limit(page.limit + 1).toArray();
+}
+
+export async function countAdminActions(filters: AuditFilters) {
+  return adminAuditCollection.countDocuments(auditFilter(filters));
+}
@@ -0,0 +1,50 @@
+import { describe, expect, test } from "bun:test";
+import { Elysia } from "elysia";
+import {
+  MAX_BODY_BYTES,
+  MAX_UPLOAD_BODY_BYTES,
+  bodyLimit,
+  maxBodyBytes,
+} from "./body-limit";
+
+describe("maxBodyBytes", () => {
+  test("allows more for multipart uploads", () => {
+    expect(maxBodyBytes("application/json")).toBe(MAX_BODY_BYTES);
+    expect(maxBodyBytes(null)).toBe(MAX_BODY_BYTES);
+    expect(maxBodyBytes("Multipart/Form-Data; boundary=x")).toBe(
+      MAX_UPLOAD_BODY_BYTES
+    );
+  });
+});
+
+describe("bodyLimit", () => {
+  const app = new Elysia()
+    .use(bodyLimit)
+    .post("/echo", ({ body }) => ({ received: body }));
+
+  const post = (body: string) =>
+    app.handle(
+      new Request("http://localhost/echo", {
+        method: "POST",
+        headers: {
+          "content-type": "application/json",
+          "content-length": String(Buffer.byteLength(body)),
+        },
+        body,
+      })
+    );
+
+  test("rejects an oversized body with a 413", async () => {
+    const response = await post(
+      JSON.stringify({ content: "x".repeat(MAX_BODY_BYTES) })
+    );
+    expect(response.status).toBe(413);
+    expect((await response.json()).error.code).toBe("payload_too_large");
+  });
+
+  test("accepts a normal body", async () => {
+    const response = await post(JSON.stringify({ content: "hello" }));
+    expect(response.status).toBe(200);
+    expect(await response.json()).toEqual({ received: { content: "hello" } });
+  });
+});
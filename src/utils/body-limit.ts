@@ -0,0 +1,46 @@
+import { Elysia } from "elysia";
+
+// JSON and form bodies, 1 MiB by default
+export const MAX_BODY_BYTES = parseInt(
+  process.env.MAX_BODY_BYTES || String(1024 * 1024)
+);
+
+// Multipart uploads carry images (up to 5MB) plus the form envelope
+export const MAX_UPLOAD_BODY_BYTES = parseInt(
+  process.env.MAX_UPLOAD_BODY_BYTES || String(6 * 1024 * 1024)
+);
+
+/**
+ * The largest body accepted for a request with this content type.
+ */
+export function maxBodyBytes(contentType: string | null): number {
+  return contentType?.toLowerCase().startsWith("multipart/form-data")
+    ? MAX_UPLOAD_BODY_BYTES
+    : MAX_BODY_BYTES;
+}
+
+/**
+ * Rejects requests whose `Content-Length` is over the limit with a 413
+ * before the body is read. Bodies sent without a length are capped by the
+ * server's `maxRequestBodySize` instead.
+ */
+export const bodyLimit = new Elysia({ name: "body-limit" }).onRequest(
+  { as: "global" },
+  ({ request }) => {
+    const length = request.headers.get("content-length");
+    if (!length) return;
+
+    const limit = maxBodyBytes(request.headers.get("content-type"));
+    if (parseInt(length) <= limit) return;
+
+    return Response.json(
+      {
+        error: {
+          code: "payload_too_large",
+          message: `Request body must be at most ${limit} bytes`,
+        },
+      },
+      { status: 413 }
+    );
+  }
+);
@@ -0,0 +1,38 @@
+import { describe, expect, test } from "bun:test";
+import { Chess } from "chess.js";
+import { BOT_DIFFICULTY_DEPTH, findBestMove } from "./chess-engine";
+
+// White to play Ra8#, the black king boxed in by its own pawns
+const BACK_RANK_MATE_FEN = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+describe("findBestMove", () => {
+  test("plays a legal move at every difficulty", () => {
+    for (const depth of Object.values(BOT_DIFFICULTY_DEPTH)) {
+      const chess = new Chess();
+      chess.move("e4");
+      const move = findBestMove(chess, depth);
+      expect(chess.moves()).toContain(move!);
+    }
+  });
+
+  test("finds mate in one", () => {
+    for (const depth of Object.values(BOT_DIFFICULTY_DEPTH)) {
+      expect(findBestMove(new Chess(BACK_RANK_MATE_FEN), depth)).toBe("Ra8#");
+    }
+  });
+
+  test("takes a hanging queen", () => {
+    // Black's queen on d5 is free for the knight on c3
+    const chess = new Chess("4k3/8/8/3q4/8/2N5/8/4K3 w - - 0 1");
+    expect(findBestMove(chess, BOT_DIFFICULTY_DEPTH.easy)).toBe("Nxd5");
+  });
+
+  test("leaves the game alone and gives up when there's no move", () => {
+    const chess = new Chess(BACK_RANK_MATE_FEN);
+    findBestMove(chess, BOT_DIFFICULTY_DEPTH.hard);
+    expect(chess.fen()).toBe(BACK_RANK_MATE_FEN);
+
+    chess.move("Ra8#");
+    expect(findBestMove(chess, BOT_DIFFICULTY_DEPTH.hard)).toBeNull();
+  });
+});
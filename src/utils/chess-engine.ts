@@ -0,0 +1,107 @@
+import { Chess } from "chess.js";
+
+// Centipawn values used by the material evaluation
+const PIECE_VALUES: Record<string, number> = {
+  p: 100,
+  n: 320,
+  b: 330,
+  r: 500,
+  q: 900,
+  k: 0,
+};
+
+const MATE_SCORE = 1_000_000;
+
+/**
+ * Search depth (in plies) for each bot difficulty.
+ */
+export const BOT_DIFFICULTY_DEPTH = {
+  easy: 1,
+  medium: 2,
+  hard: 3,
+} as const;
+
+export type BotDifficulty = keyof typeof BOT_DIFFICULTY_DEPTH;
+
+/**
+ * Material balance from the point of view of the side to move.
+ */
+function evaluate(chess: Chess): number {
+  let score = 0;
+  for (const row of chess.board()) {
+    for (const square of row) {
+      if (!square) continue;
+      const value = PIECE_VALUES[square.type];
+      score += square.color === "w" ? value : -value;
+    }
+  }
+  return chess.turn() === "w" ? score : -score;
+}
+
+/**
+ * Captures first so alpha-beta can prune more of the tree.
+ */
+function orderedMoves(chess: Chess): string[] {
+  const moves = chess.moves();
+  return [
+    ...moves.filter((m) => m.includes("x")),
+    ...moves.filter((m) => !m.includes("x")),
+  ];
+}
+
+function negamax(
+  chess: Chess,
+  depth: number,
+  alpha: number,
+  beta: number
+): number {
+  if (chess.isCheckmate()) {
+    // Prefer quicker mates by scoring deeper ones slightly lower
+    return -MATE_SCORE - depth;
+  }
+  if (chess.isDraw()) {
+    return 0;
+  }
+  if (depth === 0) {
+    return evaluate(chess);
+  }
+
+  let best = -Infinity;
+  for (const move of orderedMoves(chess)) {
+    chess.move(move);
+    const score = -negamax(chess, depth - 1, -beta, -alpha);
+    chess.undo();
+
+    if (score > best) best = score;
+    if (score > alpha) alpha = score;
+    if (alpha >= beta) break;
+  }
+  return best;
+}
+
+/**
+ * Picks a move for the side to move using a material-only minimax search
+ * with alpha-beta pruning. Equal moves are chosen at random so the bot
+ * doesn't play the same game every time.
+ * Returns the move in SAN, or null if there are no legal moves.
+ */
+export function findBestMove(chess: Chess, depth: number): string | null {
+  // Search on a copy so the live game's history is never touched
+  const board = new Chess(chess.fen());
+  const moves = board.moves().sort(() => Math.random() - 0.5);
+  if (moves.length === 0) return null;
+
+  let bestMove = moves[0];
+  let bestScore = -Infinity;
+  for (const move of moves) {
+    board.move(move);
+    const score = -negamax(board, Math.max(0, depth - 1), -Infinity, Infinity);
+    board.undo();
+
+    if (score > bestScore) {
+      bestScore = score;
+      bestMove = move;
+    }
+  }
+  return bestMove;
+}
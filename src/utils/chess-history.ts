@@ -0,0 +1,238 @@
+import { ObjectId } from "mongodb";
+import { connectToDatabase } from "../db/connect";
+import { ChessGameRecord } from "../db/models";
+import { mongoCursorFilter, Cursor } from "./pagination";
+import { ApiError } from "./errors";
+
+const { chessGamesCollection } = await connectToDatabase();
+
+export type GameResult = "win" | "loss" | "draw";
+type Side = "white" | "black";
+
+export interface HistoryFilters {
+  result?: GameResult;
+  color?: Side;
+  from?: Date;
+  to?: Date;
+}
+
+export async function saveChessGame(record: ChessGameRecord) {
+  await chessGamesCollection.insertOne(record);
+}
+
+function winnerFor(side: Side, result: GameResult) {
+  if (result === "draw") return null;
+  if (result === "win") return side;
+  return side === "white" ? "black" : "white";
+}
+
+/**
+ * Mongo filter for games `username` played, narrowed by colour, result and
+ * end date.
+ */
+function playerFilter(
+  username: string,
+  filters: HistoryFilters,
+  includeResult = true
+) {
+  const sides: Side[] = filters.color ? [filters.color] : ["white", "black"];
+  const filter: any = {
+    $or: sides.map((side) => ({
+      [`${side}.username`]: username,
+      ...(includeResult && filters.result
+        ? { winner: winnerFor(side, filters.result) }
+        : {}),
+    })),
+  };
+
+  if (filters.from || filters.to) {
+    filter.createdAt = {
+      ...(filters.from ? { $gte: filters.from } : {}),
+      ...(filters.to ? { $lte: filters.to } : {}),
+    };
+  }
+  return filter;
+}
+
+/**
+ * A page of `username`'s finished games, newest first.
+ * Fetches one extra game so callers can tell whether another page exists.
+ */
+export async function findChessHistory(
+  username: string,
+  filters: HistoryFilters,
+  page: { limit: number; cursor?: Cursor; offset?: number }
+) {
+  let filter = playerFilter(username, filters);
+  if (page.cursor) {
+    if (!ObjectId.isValid(page.cursor.id)) {
+      throw new ApiError(400, "Invalid cursor");
+    }
+    filter = {
+      $and: [filter, mongoCursorFilter(page.cursor, (id) => new ObjectId(id))],
+    };
+  }
+
+  let find = chessGamesCollection
+    .find(filter)
+    .sort({ createdAt: -1, _id: -1 });
+  if (!page.cursor && page.offset) find = find.skip(page.offset);
+  return find.limit(page.limit + 1).toArray();
+}
+
+export async function findChessGame(id: string) {
+  if (!ObjectId.isValid(id)) return null;
+  return chessGamesCollection.findOne({ _id: new ObjectId(id) });
+}
+
+/**
+ * How many of `username`'s games match the filters, across all pages.
+ */
+export async function countChessHistory(
+  username: string,
+  filters: HistoryFilters
+) {
+  return chessGamesCollection.countDocuments(playerFilter(username, filters));
+}
+
+/**
+ * Career summary for a player from their recorded games, computed in one
+ * aggregation.
+ */
+export async function getChessStats(userId: string) {
+  const [stats] = await chessGamesCollection
+    .aggregate<{
+      totals: {
+        total: number;
+        wins: number;
+        draws: number;
+        moves: number;
+      }[];
+      openings: { _id: string }[];
+      namedOpenings: { _id: { eco: string; name: string }; count: number }[];
+    }>([
+      {
+        $match: {
+          $or: [{ "white.userId": userId }, { "black.userId": userId }],
+        },
+      },
+      {
+        $project: {
+          color: {
+            $cond: [{ $eq: ["$white.userId", userId] }, "white", "black"],
+          },
+          winner: 1,
+          moves: 1,
+          opening: 1,
+        },
+      },
+      {
+        $facet: {
+          totals: [
+            {
+              $group: {
+                _id: null,
+                total: { $sum: 1 },
+                wins: {
+                  $sum: { $cond: [{ $eq: ["$winner", "$color"] }, 1, 0] },
+                },
+                draws: {
+                  $sum: { $cond: [{ $eq: ["$winner", null] }, 1, 0] },
+                },
+                // Full moves, so each side's move counts as half
+                moves: {
+                  $sum: { $ceil: { $divide: [{ $size: "$moves" }, 2] } },
+                },
+              },
+            },
+          ],
+          // The player's own first move: ply 1 as white, ply 2 as black
+          openings: [
+            {
+              $project: {
+                firstMove: {
+                  $arrayElemAt: [
+                    "$moves",
+                    { $cond: [{ $eq: ["$color", "white"] }, 0, 1] },
+                  ],
+                },
+              },
+            },
+            { $match: { firstMove: { $ne: null } } },
+            { $group: { _id: "$firstMove", count: { $sum: 1 } } },
+            { $sort: { count: -1, _id: 1 } },
+            { $limit: 1 },
+          ],
+          // Games recorded before openings were named are skipped
+          namedOpenings: [
+            { $match: { opening: { $ne: null } } },
+            {
+              $group: {
+                _id: { eco: "$opening.eco", name: "$opening.name" },
+                count: { $sum: 1 },
+              },
+            },
+            { $sort: { count: -1, "_id.eco": 1 } },
+            { $limit: 1 },
+          ],
+        },
+      },
+    ])
+    .toArray();
+
+  const totals = stats?.totals[0];
+  const total = totals?.total ?? 0;
+  const wins = totals?.wins ?? 0;
+  const draws = totals?.draws ?? 0;
+  return {
+    totalGames: total,
+    wins,
+    losses: total - wins - draws,
+    draws,
+    winRate: total ? wins / total : 0,
+    averageMoves: total ? totals!.moves / total : 0,
+    favoriteFirstMove: stats?.openings[0]?._id ?? null,
+    favoriteOpening: stats?.namedOpenings[0]
+      ? { ...stats.namedOpenings[0]._id, games: stats.namedOpenings[0].count }
+      : null,
+  };
+}
+
+/**
+ * Win/loss/draw totals for `username` matching the colour and date filters,
+ * counted in a single aggregation.
+ */
+export async function countChessResults(
+  username: string,
+  filters: HistoryFilters
+) {
+  const wonAs = (side: Side) => ({
+    $and: [
+      { $eq: [`$${side}.username`, username] },
+      { $eq: ["$winner", side] },
+    ],
+  });
+
+  const [totals] = await chessGamesCollection
+    .aggregate<{ total: number; wins: number; draws: number }>([
+      { $match: playerFilter(username, filters, false) },
+      {
+        $group: {
+          _id: null,
+          total: { $sum: 1 },
+          wins: {
+            $sum: {
+              $cond: [{ $or: [wonAs("white"), wonAs("black")] }, 1, 0],
+            },
+          },
+          draws: { $sum: { $cond: [{ $eq: ["$winner", null] }, 1, 0] } },
+        },
+      },
+    ])
+    .toArray();
+
+  const total = totals?.total ?? 0;
+  const wins = totals?.wins ?? 0;
+  const draws = totals?.draws ?? 0;
+  return { wins, losses: total - wins - draws, draws };
+}
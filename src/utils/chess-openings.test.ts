@@ -0,0 +1,26 @@
+import { describe, expect, test } from "bun:test";
+import { classifyOpening } from "./chess-openings";
+
+describe("classifyOpening", () => {
+  test("tags 1.e4 c5 as a Sicilian", () => {
+    expect(classifyOpening(["e4", "c5", "a3", "a6"])).toEqual({
+      eco: "B20",
+      name: "Sicilian Defence",
+    });
+  });
+
+  test("uses the longest matching book line", () => {
+    const najdorf = "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3".split(" ");
+    expect(classifyOpening(najdorf)?.name).toBe(
+      "Sicilian Defence: Najdorf Variation"
+    );
+  });
+
+  test("falls back for moves outside the book", () => {
+    expect(classifyOpening(["h4"])).toEqual({
+      eco: "A00",
+      name: "Uncommon Opening",
+    });
+    expect(classifyOpening([])).toBeNull();
+  });
+});
@@ -0,0 +1,93 @@
+// Small ECO opening book used to name recorded games. Lines are in SAN from
+// the starting position; a game gets the longest line its moves begin with.
+
+export interface Opening {
+  eco: string;
+  name: string;
+}
+
+const OPENINGS: [eco: string, name: string, moves: string][] = [
+  ["A00", "Uncommon Opening", ""],
+  ["B00", "King's Pawn Opening", "e4"],
+  ["A40", "Queen's Pawn Opening", "d4"],
+  ["A10", "English Opening", "c4"],
+  ["A04", "Réti Opening", "Nf3"],
+  ["A02", "Bird's Opening", "f4"],
+
+  ["B20", "Sicilian Defence", "e4 c5"],
+  ["B22", "Sicilian Defence: Alapin Variation", "e4 c5 c3"],
+  ["B23", "Sicilian Defence: Closed", "e4 c5 Nc3"],
+  ["B27", "Sicilian Defence", "e4 c5 Nf3"],
+  ["B30", "Sicilian Defence", "e4 c5 Nf3 Nc6"],
+  ["B40", "Sicilian Defence", "e4 c5 Nf3 e6"],
+  ["B50", "Sicilian Defence", "e4 c5 Nf3 d6"],
+  ["B54", "Sicilian Defence: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"],
+  [
+    "B90",
+    "Sicilian Defence: Najdorf Variation",
+    "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+  ],
+  ["C00", "French Defence", "e4 e6"],
+  ["C01", "French Defence: Exchange Variation", "e4 e6 d4 d5 exd5"],
+  ["C02", "French Defence: Advance Variation", "e4 e6 d4 d5 e5"],
+  ["B10", "Caro-Kann Defence", "e4 c6"],
+  ["B12", "Caro-Kann Defence: Advance Variation", "e4 c6 d4 d5 e5"],
+  ["B01", "Scandinavian Defence", "e4 d5"],
+  ["B02", "Alekhine's Defence", "e4 Nf6"],
+  ["B07", "Pirc Defence", "e4 d6 d4 Nf6"],
+
+  ["C20", "King's Pawn Game", "e4 e5"],
+  ["C23", "Bishop's Opening", "e4 e5 Bc4"],
+  ["C25", "Vienna Game", "e4 e5 Nc3"],
+  ["C30", "King's Gambit", "e4 e5 f4"],
+  ["C33", "King's Gambit Accepted", "e4 e5 f4 exf4"],
+  ["C40", "King's Knight Opening", "e4 e5 Nf3"],
+  ["C41", "Philidor Defence", "e4 e5 Nf3 d6"],
+  ["C42", "Petrov's Defence", "e4 e5 Nf3 Nf6"],
+  ["C44", "King's Pawn Game", "e4 e5 Nf3 Nc6"],
+  ["C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4"],
+  ["C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"],
+  ["C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"],
+  ["C55", "Italian Game: Two Knights Defence", "e4 e5 Nf3 Nc6 Bc4 Nf6"],
+  ["C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"],
+  ["C65", "Ruy Lopez: Berlin Defence", "e4 e5 Nf3 Nc6 Bb5 Nf6"],
+  ["C68", "Ruy Lopez: Exchange Variation", "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6"],
+
+  ["D00", "Queen's Pawn Game", "d4 d5"],
+  ["D00", "Queen's Pawn Game: London System", "d4 d5 Bf4"],
+  ["D06", "Queen's Gambit", "d4 d5 c4"],
+  ["D10", "Queen's Gambit Declined: Slav Defence", "d4 d5 c4 c6"],
+  ["D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"],
+  ["D30", "Queen's Gambit Declined", "d4 d5 c4 e6"],
+  ["A45", "Indian Defence", "d4 Nf6"],
+  ["A56", "Benoni Defence", "d4 Nf6 c4 c5"],
+  ["A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"],
+  ["E12", "Queen's Indian Defence", "d4 Nf6 c4 e6 Nf3 b6"],
+  ["E20", "Nimzo-Indian Defence", "d4 Nf6 c4 e6 Nc3 Bb4"],
+  ["E60", "King's Indian Defence", "d4 Nf6 c4 g6"],
+  ["D80", "Grünfeld Defence", "d4 Nf6 c4 g6 Nc3 d5"],
+  ["A80", "Dutch Defence", "d4 f5"],
+  ["A20", "English Opening: King's English", "c4 e5"],
+];
+
+const BOOK = OPENINGS.map(([eco, name, moves]) => ({
+  eco,
+  name,
+  moves: moves ? moves.split(" ") : [],
+}));
+
+/**
+ * Names the opening of a game from its moves (SAN), using the longest book
+ * line the game starts with. Null for a game with no moves.
+ */
+export function classifyOpening(moves: string[]): Opening | null {
+  if (moves.length === 0) return null;
+
+  let best = BOOK[0];
+  for (const line of BOOK) {
+    if (line.moves.length <= best.moves.length) continue;
+    if (line.moves.length > moves.length) continue;
+    if (line.moves.every((move, i) => moves[i] === move)) best = line;
+  }
+  return { eco: best.eco, name: best.name };
+}
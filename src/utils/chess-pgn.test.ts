@@ -0,0 +1,58 @@
+import { describe, expect, test } from "bun:test";
+import type { ChessGameRecord } from "../db/models";
+import { toPgn } from "./chess-pgn";
+
+function record(overrides: Partial<ChessGameRecord> = {}): ChessGameRecord {
+  return {
+    white: { userId: "1", username: "alice" },
+    black: { userId: "2", username: "bob" },
+    winner: "white",
+    reason: "checkmate",
+    moves: ["f3", "e5", "g4", "Qh4#"],
+    finalFen: "",
+    timeControl: 300,
+    isPrivate: false,
+    startedAt: new Date("2026-03-04T10:00:00Z"),
+    createdAt: new Date("2026-03-04T10:05:00Z"),
+    ...overrides,
+  };
+}
+
+describe("toPgn", () => {
+  test("escapes quotes and backslashes in tag values", () => {
+    const pgn = toPgn(
+      record({
+        white: { userId: "1", username: 'al"ice' },
+        black: { userId: "2", username: "b\\ob" },
+      })
+    );
+    expect(pgn).toContain('[White "al\\"ice"]');
+    expect(pgn).toContain('[Black "b\\\\ob"]');
+  });
+
+  test("keeps a newline from ending the tag", () => {
+    const pgn = toPgn(record({ reason: 'time\n[Result "0-1"]' }));
+    expect(pgn).toContain('[Termination "time [Result \\"0-1\\"]"]');
+    expect(pgn.match(/^\[Result /gm)).toHaveLength(1);
+  });
+
+  test("writes the standard tags and ends with the result", () => {
+    const pgn = toPgn(record({ winner: "black", reason: "resignation" }));
+    expect(pgn).toContain('[Date "2026.03.04"]');
+    expect(pgn).toContain('[Result "0-1"]');
+    expect(pgn).toContain('[TimeControl "300"]');
+    expect(pgn.endsWith("0-1")).toBe(true);
+  });
+
+  test("adds clock comments when move times were kept", () => {
+    const pgn = toPgn(
+      record({
+        moves: ["e4"],
+        moveTimes: [{ thinkMs: 2500, clock: 297 }],
+        winner: null,
+      })
+    );
+    expect(pgn).toContain("e4 {[%clk 0:04:57] [%emt 0:00:02.5]}");
+    expect(pgn.endsWith("1/2-1/2")).toBe(true);
+  });
+});
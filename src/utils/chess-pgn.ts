@@ -0,0 +1,63 @@
+import { Chess, DEFAULT_POSITION } from "chess.js";
+import { ChessGameRecord } from "../db/models";
+
+// PGN clock values are h:mm:ss; elapsed times keep tenths of a second
+function formatClock(seconds: number, decimals = 0): string {
+  const h = Math.floor(seconds / 3600);
+  const m = Math.floor((seconds % 3600) / 60);
+  const s = (seconds % 60).toFixed(decimals).padStart(decimals ? 4 : 2, "0");
+  return `${h}:${String(m).padStart(2, "0")}:${s}`;
+}
+
+// Tag values are quoted strings: backslashes and quotes must be escaped
+// and a newline would end the tag, so user-chosen names are made safe
+function tagValue(value: string): string {
+  return value
+    .replace(/[\r\n]+/g, " ")
+    .replace(/\\/g, "\\\\")
+    .replace(/"/g, '\\"');
+}
+
+function pgnResult(winner: ChessGameRecord["winner"]): string {
+  if (winner === "white") return "1-0";
+  if (winner === "black") return "0-1";
+  return "1/2-1/2";
+}
+
+/**
+ * Renders a recorded game as PGN. When move times were recorded each move
+ * gets a `{[%clk …] [%emt …]}` comment with the mover's remaining clock and
+ * how long they thought.
+ */
+export function toPgn(game: ChessGameRecord): string {
+  // chess.js adds the SetUp and FEN tags for non-standard positions
+  const chess = new Chess(game.initialFen ?? DEFAULT_POSITION);
+  const date = game.startedAt.toISOString().slice(0, 10).replace(/-/g, ".");
+  chess.setHeader("Event", game.isPrivate ? "Private game" : "Casual game");
+  chess.setHeader("Date", date);
+  chess.setHeader("White", tagValue(game.white.username));
+  chess.setHeader("Black", tagValue(game.black.username));
+  chess.setHeader("Result", pgnResult(game.winner));
+  chess.setHeader("TimeControl", String(game.timeControl));
+  chess.setHeader("Termination", tagValue(game.reason));
+  if (game.opening) {
+    chess.setHeader("ECO", game.opening.eco);
+    chess.setHeader("Opening", tagValue(game.opening.name));
+  }
+
+  for (const [index, move] of game.moves.entries()) {
+    chess.move(move);
+    const time = game.moveTimes?.[index];
+    if (time) {
+      chess.setComment(
+        `[%clk ${formatClock(time.clock)}] ` +
+          `[%emt ${formatClock(time.thinkMs / 1000, 1)}]`
+      );
+    }
+  }
+
+  // The movetext must end with the result, however the game ended
+  const pgn = chess.pgn();
+  const result = pgnResult(game.winner);
+  return pgn.endsWith(result) ? pgn : `${pgn} ${result}`;
+}
@@ -0,0 +1,338 @@
+import { describe, expect, test } from "bun:test";
+import { Chess, DEFAULT_POSITION } from "chess.js";
+import {
+  ColorPreference,
+  assignColors,
+  getClaimableDraw,
+  getDrawReason,
+  getLegalMoves,
+  getPositionStatus,
+  replayMoves,
+  resolveMove,
+  resolveStartPosition,
+  tryMove,
+} from "./chess";
+
+// White pawn on e7 about to promote, kings out of the way
+const PROMOTION_FEN = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+
+describe("resolveMove", () => {
+  test("passes SAN and from/to moves through", () => {
+    const chess = new Chess();
+    expect(resolveMove(chess, { move: "e4" })).toBe("e4");
+    expect(resolveMove(chess, { san: "Nf3" })).toBe("Nf3");
+    expect(resolveMove(chess, { move: { from: "e2", to: "e4" } })).toEqual({
+      from: "e2",
+      to: "e4",
+    });
+  });
+
+  test("adds a separate promotion piece to the move", () => {
+    const chess = new Chess(PROMOTION_FEN);
+    expect(resolveMove(chess, { move: "e8", promotion: "n" })).toBe("e8=N");
+    expect(
+      resolveMove(chess, { move: { from: "e7", to: "e8" }, promotion: "Q" })
+    ).toEqual({ from: "e7", to: "e8", promotion: "q" });
+  });
+
+  test("requires a promotion piece for promoting moves", () => {
+    const chess = new Chess(PROMOTION_FEN);
+    expect(() => resolveMove(chess, { move: "e8" })).toThrow(
+      "promotion piece required"
+    );
+    expect(() =>
+      resolveMove(chess, { move: { from: "e7", to: "e8" } })
+    ).toThrow("promotion piece required");
+    expect(() => resolveMove(chess, { move: "e7e8" })).toThrow(
+      "promotion piece required"
+    );
+  });
+
+  test("rejects unusable payloads", () => {
+    const chess = new Chess();
+    expect(() => resolveMove(chess, {})).toThrow("Invalid move");
+    expect(() => resolveMove(chess, { move: "e4", promotion: "k" })).toThrow(
+      "Invalid promotion piece"
+    );
+  });
+});
+
+describe("getDrawReason", () => {
+  test("is null for a normal position", () => {
+    expect(getDrawReason(new Chess())).toBeNull();
+  });
+
+  test("draws bare kings and a lone minor piece", () => {
+    expect(getDrawReason(new Chess("8/8/8/4k3/8/8/8/4K3 w - - 0 1"))).toBe(
+      "insufficient_material"
+    );
+    expect(getDrawReason(new Chess("8/8/8/4k3/8/8/8/4KN2 w - - 0 1"))).toBe(
+      "insufficient_material"
+    );
+  });
+
+  test("draws stalemate", () => {
+    expect(getDrawReason(new Chess("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"))).toBe(
+      "stalemate"
+    );
+  });
+
+  test("draws after seventy-five moves without a capture or pawn move", () => {
+    const fen = "4k3/8/8/8/8/8/8/R3K3 w - - 150 120";
+    expect(getDrawReason(new Chess(fen))).toBe("seventy_five_move_rule");
+    const claimable = "4k3/8/8/8/8/8/8/R3K3 w - - 100 100";
+    expect(getDrawReason(new Chess(claimable))).toBeNull();
+  });
+});
+
+describe("resolveStartPosition", () => {
+  test("defaults to the standard position", () => {
+    expect(resolveStartPosition()).toEqual({
+      variant: "standard",
+      fen: DEFAULT_POSITION,
+    });
+    expect(resolveStartPosition("standard").fen).toBe(DEFAULT_POSITION);
+  });
+
+  test("treats a FEN on its own as a custom position", () => {
+    const fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1";
+    expect(resolveStartPosition(undefined, fen)).toEqual({
+      variant: "custom",
+      fen,
+    });
+    expect(resolveStartPosition("custom", fen).variant).toBe("custom");
+  });
+
+  test("rejects unusable requests", () => {
+    const fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    expect(() => resolveStartPosition("standard", fen)).toThrow(
+      "only be used with the custom variant"
+    );
+    expect(() => resolveStartPosition("custom")).toThrow("needs a starting");
+    expect(() => resolveStartPosition("atomic")).toThrow("Invalid variant");
+    expect(() => resolveStartPosition("chess960")).toThrow("Invalid variant");
+    expect(() => resolveStartPosition("custom", "not a fen")).toThrow(
+      "Invalid FEN"
+    );
+  });
+
+  test("rejects positions that are already over", () => {
+    const mate =
+      "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+    expect(() => resolveStartPosition("custom", mate)).toThrow(
+      "already checkmate or a draw"
+    );
+  });
+});
+
+describe("tryMove", () => {
+  test("returns SAN for legal moves", () => {
+    const chess = new Chess();
+    expect(tryMove(chess, { from: "e2", to: "e4" })).toEqual({
+      legal: true,
+      san: "e4",
+    });
+    expect(tryMove(chess, { from: "g1", to: "f3" })).toEqual({
+      legal: true,
+      san: "Nf3",
+    });
+  });
+
+  test("disambiguates when two pieces can reach the square", () => {
+    const chess = new Chess("4k3/8/8/8/8/8/4K3/R6R w - - 0 1");
+    expect(tryMove(chess, { from: "a1", to: "d1" })).toEqual({
+      legal: true,
+      san: "Rad1",
+    });
+  });
+
+  test("doesn't play the move", () => {
+    const chess = new Chess();
+    tryMove(chess, { from: "e2", to: "e4" });
+    expect(chess.fen()).toBe(DEFAULT_POSITION);
+  });
+
+  test("explains why a move can't be played", () => {
+    const chess = new Chess();
+    const reason = (data: Parameters<typeof tryMove>[1]) => {
+      const result = tryMove(chess, data);
+      return result.legal ? null : result.reason;
+    };
+    expect(reason({ from: "e2", to: "e9" })).toBe("invalid_square");
+    expect(reason({ from: 12, to: "e4" })).toBe("invalid_square");
+    expect(reason({ from: "e4", to: "e5" })).toBe("no_piece");
+    expect(reason({ from: "e7", to: "e5" })).toBe("not_your_piece");
+    expect(reason({ from: "e2", to: "e5" })).toBe("illegal_move");
+    expect(reason({ from: "e2", to: "e4", promotion: "k" })).toBe(
+      "invalid_promotion"
+    );
+  });
+
+  test("asks for a promotion piece", () => {
+    const chess = new Chess(PROMOTION_FEN);
+    const result = tryMove(chess, { from: "e7", to: "e8" });
+    expect(result).toMatchObject({ reason: "promotion_required" });
+    if (result.legal) return;
+    expect(result.promotions?.sort()).toEqual(["b", "n", "q", "r"]);
+    expect(tryMove(chess, { from: "e7", to: "e8", promotion: "N" })).toEqual({
+      legal: true,
+      san: "e8=N",
+    });
+  });
+});
+
+describe("getClaimableDraw", () => {
+  test("offers threefold repetition without ending the game", () => {
+    const chess = new Chess();
+    for (let i = 0; i < 2; i++) {
+      for (const move of ["Nf3", "Nf6", "Ng1", "Ng8"]) chess.move(move);
+    }
+    expect(getClaimableDraw(chess)).toBe("threefold_repetition");
+    expect(getPositionStatus(chess)).toMatchObject({
+      draw: false,
+      drawReason: null,
+      claimableDraw: "threefold_repetition",
+    });
+  });
+
+  test("offers the fifty-move rule, and playing on is allowed", () => {
+    const chess = new Chess("4k3/8/8/8/8/8/8/R3K3 w - - 100 80");
+    expect(getClaimableDraw(chess)).toBe("fifty_move_rule");
+    expect(getDrawReason(chess)).toBeNull();
+
+    chess.move("Ra2");
+    expect(getClaimableDraw(chess)).toBe("fifty_move_rule");
+    chess.move("Kd7");
+    chess.move("Kd1");
+    expect(chess.fen().split(" ")[4]).toBe("103");
+  });
+
+  test("is null for a fresh game", () => {
+    expect(getClaimableDraw(new Chess())).toBeNull();
+  });
+});
+
+describe("getLegalMoves", () => {
+  test("lists the 20 opening moves in SAN and UCI", () => {
+    const moves = getLegalMoves(new Chess());
+    expect(moves).toHaveLength(20);
+    expect(moves).toContainEqual({
+      san: "Nf3",
+      uci: "g1f3",
+      from: "g1",
+      to: "f3",
+      promotion: null,
+    });
+  });
+
+  test("includes the promotion piece", () => {
+    const promotions = getLegalMoves(new Chess(PROMOTION_FEN))
+      .filter((m) => m.promotion)
+      .map((m) => m.uci);
+    expect(promotions.sort()).toEqual(["e7e8b", "e7e8n", "e7e8q", "e7e8r"]);
+  });
+});
+
+describe("replayMoves", () => {
+  const SHUFFLE = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+  test("keeps the history threefold repetition needs", () => {
+    const moves = [...SHUFFLE, ...SHUFFLE];
+    // The start position has occurred twice before the last move
+    expect(replayMoves(moves.slice(0, -1)).isThreefoldRepetition()).toBe(
+      false
+    );
+    const chess = replayMoves(moves);
+    expect(chess.fen()).toBe(DEFAULT_POSITION.replace("0 1", "8 5"));
+    expect(getClaimableDraw(chess)).toBe("threefold_repetition");
+  });
+
+  test("starts from the given position", () => {
+    const chess = replayMoves(["e8=Q"], PROMOTION_FEN);
+    expect(chess.get("e8")).toEqual({ type: "q", color: "w" });
+  });
+
+  test("names the first illegal move", () => {
+    expect(() => replayMoves(["e4", "e5", "e5"])).toThrow(
+      'Illegal move "e5" at ply 3'
+    );
+  });
+});
+
+describe("getPositionStatus", () => {
+  test("reports fool's mate", () => {
+    const chess = replayMoves(["f3", "e5", "g4", "Qh4#"]);
+    expect(getPositionStatus(chess)).toEqual({
+      check: true,
+      checkmate: true,
+      stalemate: false,
+      draw: false,
+      drawReason: null,
+      claimableDraw: null,
+    });
+  });
+
+  test("reports stalemate as a draw", () => {
+    // Black king cornered by the white queen, black to move
+    const chess = new Chess("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+    expect(getPositionStatus(chess)).toEqual({
+      check: false,
+      checkmate: false,
+      stalemate: true,
+      draw: true,
+      drawReason: "stalemate",
+      claimableDraw: null,
+    });
+  });
+
+  test("reports check on its own", () => {
+    const chess = replayMoves(["e4", "f5", "Qh5+"]);
+    expect(getPositionStatus(chess)).toMatchObject({
+      check: true,
+      checkmate: false,
+      draw: false,
+    });
+  });
+});
+
+describe("assignColors", () => {
+  const player = (name: string, colorPreference?: ColorPreference) => ({
+    name,
+    colorPreference,
+  });
+
+  test("gives each player the color they asked for", () => {
+    const a = player("a", "black");
+    const b = player("b", "random");
+    expect(assignColors(a, b)).toEqual({ white: b, black: a });
+    expect(assignColors(b, a)).toEqual({ white: b, black: a });
+    expect(assignColors(player("c", "white"), player("d", "black"))).toEqual({
+      white: player("c", "white"),
+      black: player("d", "black"),
+    });
+  });
+
+  test("ignores the coin when anyone has a preference", () => {
+    const a = player("a", "white");
+    const b = player("b");
+    for (const roll of [0, 0.99]) {
+      expect(assignColors(b, a, () => roll).white).toBe(a);
+    }
+  });
+
+  test("flips a fair coin when neither player minds", () => {
+    const a = player("a");
+    const b = player("b");
+    expect(assignColors(a, b, () => 0.49).white).toBe(a);
+    expect(assignColors(a, b, () => 0.5).white).toBe(b);
+
+    // An evenly spread sequence of rolls splits white exactly in half
+    let roll = 0;
+    const next = () => (roll++ % 100) / 100;
+    let aWhite = 0;
+    for (let i = 0; i < 1000; i++) {
+      if (assignColors(a, b, next).white === a) aWhite++;
+    }
+    expect(aWhite).toBe(500);
+  });
+});
@@ -0,0 +1,298 @@
+import { Chess, DEFAULT_POSITION, validateFen, Square } from "chess.js";
+
+export const PROMOTION_PIECES = ["q", "r", "b", "n"];
+
+export type MoveObject = { from: string; to: string; promotion?: string };
+
+/**
+ * Move payload sent by clients.
+ * Either `move` (SAN/LAN string or from/to object) or `san` may be used,
+ * with an optional separate `promotion` piece.
+ */
+export interface MoveInput {
+  move?: string | MoveObject;
+  san?: string;
+  promotion?: string;
+}
+
+/**
+ * Normalises a client move payload into something chess.js accepts.
+ * Throws with a client-facing message when the payload is unusable,
+ * including when a promoting pawn move is missing its promotion piece.
+ */
+export function resolveMove(chess: Chess, data: MoveInput): string | MoveObject {
+  const promotion = data?.promotion?.toLowerCase();
+  if (promotion !== undefined && !PROMOTION_PIECES.includes(promotion)) {
+    throw new Error("Invalid promotion piece");
+  }
+
+  let move = data?.move ?? data?.san;
+  if (typeof move === "string") {
+    // Assemble SAN such as "e8=Q" from an explicit promotion piece
+    if (promotion && !move.includes("=")) {
+      move = `${move.replace(/[+#]$/, "")}=${promotion.toUpperCase()}`;
+    }
+  } else if (move && typeof move === "object") {
+    if (promotion && !move.promotion) {
+      move = { ...move, promotion };
+    }
+  } else {
+    throw new Error("Invalid move");
+  }
+
+  if (isMissingPromotion(chess, move)) {
+    throw new Error("promotion piece required");
+  }
+
+  return move;
+}
+
+/**
+ * Whether the move is a pawn reaching the last rank without a promotion piece.
+ */
+function isMissingPromotion(chess: Chess, move: string | MoveObject): boolean {
+  const promotions = chess.moves({ verbose: true }).filter((m) => m.promotion);
+  if (promotions.length === 0) return false;
+
+  if (typeof move === "object") {
+    return (
+      !move.promotion &&
+      promotions.some((m) => m.from === move.from && m.to === move.to)
+    );
+  }
+
+  if (move.includes("=")) return false;
+  const target = move.replace(/[+#]$/, "");
+  return promotions.some(
+    (m) =>
+      m.san.replace(/=[QRBN]/, "").replace(/[+#]$/, "") === target ||
+      m.lan.slice(0, 4) === target
+  );
+}
+
+const SQUARE_PATTERN = /^[a-h][1-8]$/;
+
+export type TryMoveResult =
+  | { legal: true; san: string }
+  | {
+      legal: false;
+      reason:
+        | "invalid_square"
+        | "invalid_promotion"
+        | "no_piece"
+        | "not_your_piece"
+        | "illegal_move"
+        | "promotion_required";
+      promotions?: string[]; // Pieces allowed when promotion_required
+    };
+
+/**
+ * Checks a from/to move (as made by dragging a piece) for the side to move
+ * without playing it. Legal moves come back as SAN, disambiguated as
+ * needed; a pawn reaching the last rank without `promotion` is rejected
+ * with the pieces it may promote to.
+ */
+export function tryMove(
+  chess: Chess,
+  data: { from?: unknown; to?: unknown; promotion?: unknown }
+): TryMoveResult {
+  const { from, to } = data ?? {};
+  if (
+    typeof from !== "string" ||
+    typeof to !== "string" ||
+    !SQUARE_PATTERN.test(from) ||
+    !SQUARE_PATTERN.test(to)
+  ) {
+    return { legal: false, reason: "invalid_square" };
+  }
+  const promotion =
+    typeof data.promotion === "string" ? data.promotion.toLowerCase() : null;
+  if (
+    data.promotion !== undefined &&
+    (!promotion || !PROMOTION_PIECES.includes(promotion))
+  ) {
+    return { legal: false, reason: "invalid_promotion" };
+  }
+
+  const piece = chess.get(from as Square);
+  if (!piece) return { legal: false, reason: "no_piece" };
+  if (piece.color !== chess.turn()) {
+    return { legal: false, reason: "not_your_piece" };
+  }
+
+  const candidates = chess
+    .moves({ square: from as Square, verbose: true })
+    .filter((m) => m.to === to);
+  if (candidates.length === 0) return { legal: false, reason: "illegal_move" };
+
+  if (!candidates[0].promotion) return { legal: true, san: candidates[0].san };
+  const promoted = candidates.find((m) => m.promotion === promotion);
+  if (promoted) return { legal: true, san: promoted.san };
+  return {
+    legal: false,
+    reason: "promotion_required",
+    promotions: candidates.map((m) => m.promotion!),
+  };
+}
+
+/**
+ * All legal moves for the side to move, in both SAN and UCI notation.
+ */
+export function getLegalMoves(chess: Chess) {
+  return chess.moves({ verbose: true }).map((m) => ({
+    san: m.san,
+    uci: m.lan, // chess.js LAN matches UCI, e.g. "e7e8q"
+    from: m.from,
+    to: m.to,
+    promotion: m.promotion ?? null,
+  }));
+}
+
+/**
+ * Rebuilds a game by replaying its moves from the starting position.
+ * Loading only the final FEN would lose the position history chess.js uses
+ * for threefold repetition (and the halfmove clock would have to be
+ * trusted), so restored games must be rebuilt this way.
+ * Throws if a stored move is illegal in the replayed position.
+ */
+export function replayMoves(moves: string[], initialFen?: string): Chess {
+  const chess = initialFen ? new Chess(initialFen) : new Chess();
+  for (const [index, move] of moves.entries()) {
+    try {
+      chess.move(move);
+    } catch {
+      throw new Error(`Illegal move "${move}" at ply ${index + 1}`);
+    }
+  }
+  return chess;
+}
+
+// After 75 moves each without a capture or pawn move the game is drawn
+// even if nobody claims it (FIDE 9.6.2)
+const SEVENTY_FIVE_MOVE_PLIES = 150;
+
+export type ClaimableDraw = "threefold_repetition" | "fifty_move_rule";
+
+/**
+ * Flags describing the current position, sent with every "update" so
+ * clients don't have to recompute them from the FEN. `draw` is only set for
+ * draws that end the game; `claimableDraw` is a draw the side to move may
+ * claim but can also play on from.
+ */
+export function getPositionStatus(chess: Chess) {
+  const drawReason = getDrawReason(chess);
+  return {
+    check: chess.isCheck(),
+    checkmate: chess.isCheckmate(),
+    stalemate: chess.isStalemate(),
+    draw: drawReason !== null,
+    drawReason,
+    claimableDraw: drawReason ? null : getClaimableDraw(chess),
+  };
+}
+
+/**
+ * Why a position is drawn without anyone claiming it, or null if it isn't.
+ * Insufficient material is checked first so bare kings (or a lone minor
+ * piece) end the game immediately with a specific reason. Threefold
+ * repetition and the fifty-move rule only draw when claimed, see
+ * `getClaimableDraw`.
+ */
+export function getDrawReason(chess: Chess): string | null {
+  if (chess.isInsufficientMaterial()) return "insufficient_material";
+  if (chess.isStalemate()) return "stalemate";
+  const halfmoveClock = parseInt(chess.fen().split(" ")[4]);
+  if (halfmoveClock >= SEVENTY_FIVE_MOVE_PLIES) return "seventy_five_move_rule";
+  return null;
+}
+
+/**
+ * A draw the side to move is entitled to claim, or null.
+ */
+export function getClaimableDraw(chess: Chess): ClaimableDraw | null {
+  if (chess.isThreefoldRepetition()) return "threefold_repetition";
+  if (chess.isDrawByFiftyMoves()) return "fifty_move_rule";
+  return null;
+}
+
+/**
+ * Whether the game ends in this position without either player acting.
+ */
+export function isGameFinished(chess: Chess): boolean {
+  return chess.isCheckmate() || getDrawReason(chess) !== null;
+}
+
+export type ChessVariant = "standard" | "custom";
+export const CHESS_VARIANTS: ChessVariant[] = ["standard", "custom"];
+
+export interface StartPosition {
+  variant: ChessVariant;
+  fen: string;
+}
+
+export const STANDARD_START: StartPosition = {
+  variant: "standard",
+  fen: DEFAULT_POSITION,
+};
+
+/**
+ * Works out where a new game starts from the requested variant and/or
+ * FEN. A FEN on its own means a custom position. Throws with a
+ * client-facing message for an unknown variant or an invalid or already
+ * finished position.
+ */
+export function resolveStartPosition(
+  variant?: string,
+  fen?: string
+): StartPosition {
+  if (fen !== undefined && variant !== undefined && variant !== "custom") {
+    throw new Error("A starting FEN can only be used with the custom variant");
+  }
+  if (variant === undefined || variant === "standard") {
+    if (fen === undefined) return STANDARD_START;
+  } else if (variant !== "custom") {
+    throw new Error(
+      `Invalid variant, must be one of: ${CHESS_VARIANTS.join(", ")}`
+    );
+  }
+
+  if (!fen) throw new Error("The custom variant needs a starting FEN");
+  const { ok, error } = validateFen(fen);
+  if (!ok) throw new Error(`Invalid FEN: ${error}`);
+  const chess = new Chess(fen);
+  if (isGameFinished(chess)) {
+    throw new Error("The starting position is already checkmate or a draw");
+  }
+  return { variant: "custom", fen: chess.fen() };
+}
+
+export type ColorPreference = "white" | "black" | "random";
+export const COLOR_PREFERENCES: ColorPreference[] = ["white", "black", "random"];
+
+/**
+ * Picks colors for two players, honouring explicit preferences and falling
+ * back to a coin flip from `random` when neither player minds.
+ * Assumes the preferences are compatible (only two identical explicit
+ * colors clash).
+ */
+export function assignColors<P extends { colorPreference?: ColorPreference }>(
+  newPlayer: P,
+  opponent: P,
+  random: () => number = Math.random
+): { white: P; black: P } {
+  const newPreference = newPlayer.colorPreference ?? "random";
+  const opponentPreference = opponent.colorPreference ?? "random";
+
+  let isNewWhite: boolean;
+  if (newPreference !== "random") {
+    isNewWhite = newPreference === "white";
+  } else if (opponentPreference !== "random") {
+    isNewWhite = opponentPreference === "black";
+  } else {
+    isNewWhite = random() < 0.5;
+  }
+
+  return isNewWhite
+    ? { white: newPlayer, black: opponent }
+    : { white: opponent, black: newPlayer };
+}
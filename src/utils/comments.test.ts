@@ -0,0 +1,59 @@
+import { describe, expect, test } from "bun:test";
+import { ObjectId } from "mongodb";
+import { CommentWithId, MAX_COMMENT_DEPTH, buildCommentTree } from "./comments";
+
+let minute = 0;
+
+function comment(parent?: CommentWithId): CommentWithId {
+  return {
+    _id: new ObjectId(),
+    blogId: "hello-world",
+    authorId: "user-1",
+    content: "Hi",
+    accepted: true,
+    createdAt: new Date(Date.UTC(2026, 0, 1, 0, minute++)),
+    parentId: parent ? parent._id.toString() : null,
+  };
+}
+
+describe("buildCommentTree", () => {
+  test("nests a two-level thread, oldest reply first", () => {
+    const root = comment();
+    const first = comment(root);
+    const second = comment(root);
+    const nested = comment(first);
+
+    const [tree] = buildCommentTree([root], [nested, second, first]);
+    expect(tree._id).toBe(root._id.toString());
+    expect(tree.replies.map((r) => r._id)).toEqual([
+      first._id.toString(),
+      second._id.toString(),
+    ]);
+    expect(tree.replies[0].replies.map((r) => r._id)).toEqual([
+      nested._id.toString(),
+    ]);
+    expect(tree.replies[1].replies).toEqual([]);
+  });
+
+  test("stops at the maximum depth", () => {
+    const root = comment();
+    const chain = [root];
+    for (let i = 0; i < MAX_COMMENT_DEPTH + 1; i++) {
+      chain.push(comment(chain[chain.length - 1]));
+    }
+
+    let node = buildCommentTree([root], chain.slice(1))[0];
+    let depth = 1;
+    while (node.replies.length > 0) {
+      node = node.replies[0];
+      depth++;
+    }
+    expect(depth).toBe(MAX_COMMENT_DEPTH);
+  });
+
+  test("drops replies whose parent is missing", () => {
+    const root = comment();
+    const orphan = comment(comment());
+    expect(buildCommentTree([root], [orphan])[0].replies).toEqual([]);
+  });
+});
@@ -0,0 +1,146 @@
+import { ObjectId } from "mongodb";
+import { marked } from "marked";
+import sanitizeHtml from "sanitize-html";
+import { connectToDatabase } from "../db/connect";
+import { Comment, ReactionType } from "../db/models";
+import { ApiError } from "./errors";
+
+export const MAX_COMMENT_DEPTH = 5; // Top-level comments are depth 1
+export const MAX_COMMENT_LENGTH = 5000; // Characters of markdown
+
+export type CommentWithId = Comment & { _id: ObjectId };
+
+export type CommentNode = Omit<Comment, "_id"> & {
+  _id: string;
+  replies: CommentNode[];
+};
+
+export interface CommentReactionSummary {
+  likes: number;
+  dislikes: number;
+  userReaction: ReactionType | null; // The caller's reaction, if any
+}
+
+/**
+ * Renders comment markdown to sanitized HTML.
+ */
+export async function renderCommentContent(content: string): Promise<string> {
+  return sanitizeHtml(await marked(content.trim()));
+}
+
+/**
+ * Checks a reply's parent exists on the same post and hasn't been deleted,
+ * and that the reply wouldn't nest deeper than MAX_COMMENT_DEPTH.
+ */
+export async function validateParentComment(blogId: string, parentId: string) {
+  if (!ObjectId.isValid(parentId)) {
+    throw new ApiError(400, "Invalid parent comment ID format");
+  }
+
+  const { commentsCollection } = await connectToDatabase();
+  let depth = 1;
+  let current = await commentsCollection.findOne({
+    _id: new ObjectId(parentId),
+  });
+  if (!current || current.blogId !== blogId || current.deleted) {
+    throw new ApiError(400, "Parent comment not found");
+  }
+
+  while (current?.parentId) {
+    depth++;
+    current = await commentsCollection.findOne({
+      _id: new ObjectId(current.parentId),
+    });
+  }
+  if (depth >= MAX_COMMENT_DEPTH) {
+    throw new ApiError(400, "Maximum reply depth reached");
+  }
+}
+
+/**
+ * Deletes a comment. Comments with replies are tombstoned instead so the
+ * thread below them stays intact.
+ * Returns whether a comment was found.
+ */
+export async function removeComment(commentId: ObjectId): Promise<boolean> {
+  const { commentsCollection, commentReactionsCollection } =
+    await connectToDatabase();
+  const hasReplies = await commentsCollection.findOne({
+    parentId: commentId.toString(),
+  });
+
+  if (hasReplies) {
+    const result = await commentsCollection.updateOne(
+      { _id: commentId },
+      { $set: { deleted: true, content: "" } }
+    );
+    return result.matchedCount === 1;
+  }
+
+  const result = await commentsCollection.deleteOne({ _id: commentId });
+  await commentReactionsCollection.deleteMany({
+    commentId: commentId.toString(),
+  });
+  return result.deletedCount === 1;
+}
+
+/**
+ * Adds like/dislike counts and the caller's own reaction to each comment.
+ */
+export async function withCommentReactions<T extends { _id: ObjectId }>(
+  comments: T[],
+  userId?: string
+): Promise<(T & CommentReactionSummary)[]> {
+  const { commentReactionsCollection } = await connectToDatabase();
+  const ids = comments.map((c) => c._id.toString());
+  const reactions = await commentReactionsCollection
+    .find({ commentId: { $in: ids } })
+    .toArray();
+
+  const summaries = new Map<string, CommentReactionSummary>();
+  for (const id of ids) {
+    summaries.set(id, { likes: 0, dislikes: 0, userReaction: null });
+  }
+  for (const reaction of reactions) {
+    const summary = summaries.get(reaction.commentId)!;
+    if (reaction.type === "like") summary.likes++;
+    else summary.dislikes++;
+    if (reaction.userId === userId) summary.userReaction = reaction.type;
+  }
+
+  return comments.map((c) => ({
+    ...c,
+    ...summaries.get(c._id.toString())!,
+  }));
+}
+
+/**
+ * Nests replies under their parents, stopping at MAX_COMMENT_DEPTH.
+ * Replies whose parent isn't in `roots` or `replies` are dropped.
+ */
+export function buildCommentTree(
+  roots: CommentWithId[],
+  replies: CommentWithId[]
+): CommentNode[] {
+  const childrenOf = new Map<string, CommentWithId[]>();
+  for (const reply of replies) {
+    const siblings = childrenOf.get(reply.parentId!) ?? [];
+    siblings.push(reply);
+    childrenOf.set(reply.parentId!, siblings);
+  }
+
+  const toNode = (comment: CommentWithId, depth: number): CommentNode => {
+    const id = comment._id.toString();
+    const children =
+      depth < MAX_COMMENT_DEPTH ? childrenOf.get(id) ?? [] : [];
+    return {
+      ...comment,
+      _id: id,
+      replies: children
+        .sort((a, b) => a.createdAt.getTime() - b.createdAt.getTime())
+        .map((child) => toNode(child, depth + 1)),
+    };
+  };
+
+  return roots.map((root) => toNode(root, 1));
+}
@@ -0,0 +1,50 @@
+import { describe, expect, test } from "bun:test";
+import { Elysia } from "elysia";
+import { gunzipSync } from "node:zlib";
+import { compression, negotiateEncoding } from "./compression";
+
+describe("negotiateEncoding", () => {
+  test("prefers brotli, then gzip", () => {
+    expect(negotiateEncoding("gzip, deflate, br")).toBe("br");
+    expect(negotiateEncoding("gzip")).toBe("gzip");
+    expect(negotiateEncoding("br;q=0.5, gzip")).toBe("gzip");
+    expect(negotiateEncoding("*")).toBe("br");
+  });
+
+  test("never uses an encoding given q=0", () => {
+    expect(negotiateEncoding("br;q=0, gzip;q=0")).toBeNull();
+    expect(negotiateEncoding("*;q=0")).toBeNull();
+    expect(negotiateEncoding("identity")).toBeNull();
+    expect(negotiateEncoding(null)).toBeNull();
+  });
+});
+
+describe("compression", () => {
+  const items = Array.from({ length: 200 }, (_, i) => ({ id: i }));
+  const app = new Elysia()
+    .use(compression)
+    .get("/large", () => ({ items }))
+    .get("/small", () => ({ ok: true }));
+
+  test("gzips large JSON responses", async () => {
+    const response = await app.handle(
+      new Request("http://localhost/large", {
+        headers: { "accept-encoding": "gzip" },
+      })
+    );
+    expect(response.headers.get("content-encoding")).toBe("gzip");
+    expect(response.headers.get("vary")).toBe("accept-encoding");
+    const body = gunzipSync(Buffer.from(await response.arrayBuffer()));
+    expect(JSON.parse(body.toString())).toEqual({ items });
+  });
+
+  test("leaves small responses uncompressed", async () => {
+    const response = await app.handle(
+      new Request("http://localhost/small", {
+        headers: { "accept-encoding": "gzip" },
+      })
+    );
+    expect(response.headers.get("content-encoding")).toBeNull();
+    expect(await response.json()).toEqual({ ok: true });
+  });
+});
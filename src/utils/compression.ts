@@ -0,0 +1,75 @@
+import { Elysia, StatusMap } from "elysia";
+import { brotliCompressSync, gzipSync } from "node:zlib";
+
+// Smaller bodies aren't worth the CPU or the extra headers
+const COMPRESSION_MIN_BYTES = parseInt(
+  process.env.COMPRESSION_MIN_BYTES || "1024"
+);
+
+type Encoding = "br" | "gzip";
+
+/**
+ * Picks the encoding to use from an `Accept-Encoding` header, preferring
+ * brotli. Encodings the client gives `q=0` are never used.
+ */
+export function negotiateEncoding(header: string | null): Encoding | null {
+  if (!header) return null;
+
+  const accepted = new Map<string, number>();
+  for (const part of header.toLowerCase().split(",")) {
+    const [name, ...params] = part.trim().split(";");
+    const q = params.find((p) => p.trim().startsWith("q="));
+    accepted.set(name, q ? parseFloat(q.trim().slice(2)) || 0 : 1);
+  }
+
+  const weight = (encoding: Encoding) =>
+    accepted.get(encoding) ?? accepted.get("*") ?? 0;
+  if (weight("br") > 0 && weight("br") >= weight("gzip")) return "br";
+  if (weight("gzip") > 0) return "gzip";
+  return null;
+}
+
+/**
+ * Compresses JSON and text responses with gzip or brotli, negotiated with
+ * the client's `Accept-Encoding`. Handlers returning their own `Response`
+ * (files, redirects, metrics) are passed through untouched.
+ */
+export const compression = new Elysia({ name: "compression" }).mapResponse(
+  { as: "global" },
+  ({ request, responseValue, set }) => {
+    if (responseValue instanceof Response || responseValue == null) return;
+
+    let body: string;
+    let contentType: string;
+    if (typeof responseValue === "string") {
+      body = responseValue;
+      contentType = "text/plain; charset=utf-8";
+    } else if (typeof responseValue === "object") {
+      if (responseValue instanceof Blob) return;
+      body = JSON.stringify(responseValue);
+      contentType = "application/json";
+    } else {
+      return;
+    }
+
+    // Caches must keep compressed and plain copies apart
+    set.headers["vary"] = "accept-encoding";
+
+    const encoding = negotiateEncoding(request.headers.get("accept-encoding"));
+    if (!encoding || Buffer.byteLength(body) < COMPRESSION_MIN_BYTES) return;
+
+    const compressed =
+      encoding === "br" ? brotliCompressSync(body) : gzipSync(body);
+    return new Response(compressed, {
+      status:
+        typeof set.status === "number"
+          ? set.status
+          : StatusMap[set.status as keyof typeof StatusMap] ?? 200,
+      headers: {
+        "content-type": contentType,
+        ...(set.headers as Record<string, string>),
+        "content-encoding": encoding,
+      },
+    });
+  }
+);
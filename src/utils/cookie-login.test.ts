@@ -0,0 +1,82 @@
+import { beforeAll, describe, expect, test } from "bun:test";
+import { TestApp, createTestApp, request } from "../test-utils/app";
+import {
+  TEST_PASSWORD,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { withCookieOnlyLogin } from "./cookie-login";
+
+const SESSION_COOKIE =
+  "better-auth.session_token=abc.def; Path=/; HttpOnly; SameSite=Lax; Secure";
+
+// Answers like better-auth's sign-in: tokens in the body and a header
+const signIn = async () => {
+  const headers = new Headers({
+    "content-type": "application/json",
+    "set-auth-token": "abc",
+  });
+  headers.append("set-cookie", SESSION_COOKIE);
+  return new Response(
+    JSON.stringify({ token: "abc", refreshToken: "xyz", user: { id: "1" } }),
+    { headers }
+  );
+};
+
+const post = (path: string) =>
+  withCookieOnlyLogin(signIn)(
+    new Request(`http://localhost${path}`, { method: "POST" })
+  );
+
+describe("withCookieOnlyLogin", () => {
+  test("strips the tokens with ?cookie=true", async () => {
+    const res = await post("/auth/api/sign-in/email?cookie=true");
+    expect(await res.json()).toEqual({ user: { id: "1" } });
+    expect(res.headers.get("set-auth-token")).toBeNull();
+    expect(res.headers.getSetCookie()).toEqual([SESSION_COOKIE]);
+  });
+
+  test("leaves other requests alone", async () => {
+    for (const path of [
+      "/auth/api/sign-in/email",
+      "/auth/api/get-session?cookie=true",
+    ]) {
+      const res = await post(path);
+      expect(await res.json()).toMatchObject({ token: "abc" });
+      expect(res.headers.get("set-auth-token")).toBe("abc");
+    }
+  });
+});
+
+describeWithDb("cookie-only sign-in", () => {
+  let app: TestApp;
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    app = await createTestApp();
+  });
+
+  test("the session cookie alone authenticates", async () => {
+    const user = await createTestUser("cookies");
+    const res = await request(app, "/auth/api/sign-in/email?cookie=true", {
+      method: "POST",
+      body: { email: user.email, password: TEST_PASSWORD },
+    });
+    expect(res.status).toBe(200);
+    const body = await res.json();
+    expect(body.token).toBeUndefined();
+    expect(body.refreshToken).toBeUndefined();
+    expect(res.headers.get("set-auth-token")).toBeNull();
+
+    const cookies = res.headers.getSetCookie();
+    expect(cookies.length).toBeGreaterThan(0);
+    for (const cookie of cookies) expect(cookie).toContain("HttpOnly");
+
+    // Only name=value goes back, as a browser would send it
+    const cookie = cookies.map((c) => c.split(";")[0]).join("; ");
+    const me = await request(app, "/me", { headers: { cookie } });
+    expect(me.status).toBe(200);
+    expect((await me.json()).email).toBe(user.email);
+  });
+});
@@ -0,0 +1,48 @@
+// Sign-in routes whose responses carry the session and refresh tokens
+const SIGN_IN_PATHS = [
+  "/auth/api/sign-in/email",
+  "/auth/api/sign-up/email",
+  "/auth/api/refresh",
+];
+
+/**
+ * Wraps the better-auth handler so browsers can sign in with `?cookie=true`
+ * and keep the session in HttpOnly cookies alone. The session and refresh
+ * tokens are removed from the JSON body and the bearer plugin's
+ * `set-auth-token` header, so page scripts never see them. Without the
+ * flag responses are unchanged and other clients still get the tokens.
+ */
+export function withCookieOnlyLogin(
+  handler: (request: Request) => Promise<Response>
+) {
+  return async (request: Request) => {
+    const response = await handler(request);
+
+    const url = new URL(request.url);
+    if (
+      url.searchParams.get("cookie") !== "true" ||
+      !SIGN_IN_PATHS.includes(url.pathname) ||
+      !response.ok
+    ) {
+      return response;
+    }
+
+    // The session cookie itself (HttpOnly, SameSite=Lax and Secure over
+    // HTTPS) is set by better-auth and passed through as is
+    const headers = new Headers(response.headers);
+    headers.delete("set-auth-token");
+    headers.delete("content-length");
+
+    const body = await response.json().catch(() => null);
+    if (body && typeof body === "object") {
+      delete body.token;
+      delete body.refreshToken;
+    }
+
+    return new Response(JSON.stringify(body), {
+      status: response.status,
+      statusText: response.statusText,
+      headers,
+    });
+  };
+}
@@ -0,0 +1,27 @@
+import { describe, expect, test } from "bun:test";
+import { corsAllowedOrigins, isAllowedSocketOrigin } from "./cors";
+
+const upgrade = (origin?: string) =>
+  new Request("http://api.example/sockets/chess/?EIO=4&transport=websocket", {
+    headers: {
+      upgrade: "websocket",
+      ...(origin && { origin }),
+    },
+  });
+
+describe("isAllowedSocketOrigin", () => {
+  test("refuses an upgrade from a disallowed origin", () => {
+    expect(isAllowedSocketOrigin(upgrade("https://evil.example"))).toBe(false);
+  });
+
+  test("allows configured origins and the server's own", () => {
+    for (const origin of corsAllowedOrigins) {
+      expect(isAllowedSocketOrigin(upgrade(origin))).toBe(true);
+    }
+    expect(isAllowedSocketOrigin(upgrade("http://api.example"))).toBe(true);
+  });
+
+  test("allows clients that send no Origin", () => {
+    expect(isAllowedSocketOrigin(upgrade())).toBe(true);
+  });
+});
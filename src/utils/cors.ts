@@ -0,0 +1,51 @@
+// CORS settings shared by the HTTP app and Socket.IO, read from the environment
+
+const DEV_ORIGINS = ["http://localhost:5173"];
+
+function parseList(value: string | undefined): string[] | undefined {
+  if (value === undefined) return undefined;
+  return value
+    .split(",")
+    .map((item) => item.trim())
+    .filter(Boolean);
+}
+
+/**
+ * Allowed origins from CORS_ALLOWED_ORIGINS (or the older CORS_ORIGINS).
+ * When neither is set, only the local dev frontend is allowed outside
+ * production, and cross-origin requests are denied in production.
+ */
+export const corsAllowedOrigins: string[] =
+  parseList(process.env.CORS_ALLOWED_ORIGINS ?? process.env.CORS_ORIGINS) ??
+  (process.env.NODE_ENV === "production" ? [] : DEV_ORIGINS);
+
+export const corsAllowedMethods: string[] = parseList(
+  process.env.CORS_ALLOWED_METHODS
+) ?? ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+export const corsAllowedHeaders: string[] = parseList(
+  process.env.CORS_ALLOWED_HEADERS
+) ?? ["Content-Type", "Authorization"];
+
+export const corsAllowCredentials =
+  (process.env.CORS_ALLOW_CREDENTIALS ?? "true") === "true";
+
+/**
+ * Whether a Socket.IO request (polling or WebSocket upgrade) may proceed.
+ * CORS doesn't cover WebSocket upgrades, so browsers' `Origin` is checked
+ * here: it must be an allowed origin or the server's own. Requests without
+ * one come from non-browser clients, where no other site's page is
+ * involved, and are allowed.
+ */
+export function isAllowedSocketOrigin(request: Request): boolean {
+  const origin = request.headers.get("origin");
+  if (!origin) return true;
+  return (
+    corsAllowedOrigins.includes(origin) ||
+    origin === new URL(request.url).origin
+  );
+}
+
+if (corsAllowedOrigins.length === 0) {
+  console.warn("No CORS origins configured, cross-origin requests are denied");
+}
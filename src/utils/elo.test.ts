@@ -0,0 +1,25 @@
+import { describe, expect, test } from "bun:test";
+import { calculateEloDelta } from "./elo";
+
+describe("calculateEloDelta", () => {
+  test("equal ratings split K evenly", () => {
+    expect(calculateEloDelta(1200, 1200, 1, 32)).toBe(16);
+    expect(calculateEloDelta(1200, 1200, 0, 32)).toBe(-16);
+    expect(calculateEloDelta(1200, 1200, 0.5, 32)).toBe(0);
+  });
+
+  test("an upset is worth more than an expected win", () => {
+    // 400 points apart: the favourite is expected to score 10/11
+    expect(calculateEloDelta(1600, 2000, 1, 32)).toBe(29);
+    expect(calculateEloDelta(2000, 1600, 1, 32)).toBe(3);
+  });
+
+  test("a draw moves the lower-rated player up", () => {
+    expect(calculateEloDelta(1400, 1600, 0.5, 32)).toBe(8);
+    expect(calculateEloDelta(1600, 1400, 0.5, 32)).toBe(-8);
+  });
+
+  test("scales with the K-factor", () => {
+    expect(calculateEloDelta(1200, 1200, 1, 16)).toBe(8);
+  });
+});
@@ -0,0 +1,16 @@
+export const DEFAULT_CHESS_RATING = 1200;
+const CHESS_K_FACTOR = parseInt(process.env.CHESS_K_FACTOR || "32");
+
+/**
+ * Elo rating change for player A against player B.
+ * scoreA is 1 for a win, 0.5 for a draw and 0 for a loss.
+ */
+export function calculateEloDelta(
+  ratingA: number,
+  ratingB: number,
+  scoreA: number,
+  kFactor: number = CHESS_K_FACTOR
+): number {
+  const expectedA = 1 / (1 + 10 ** ((ratingB - ratingA) / 400));
+  return Math.round(kFactor * (scoreA - expectedA));
+}
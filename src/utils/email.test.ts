@@ -0,0 +1,47 @@
+import { beforeAll, describe, expect, test } from "bun:test";
+import {
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+
+// The mailer is picked when the module loads, so choose it first
+process.env.MAIL_TRANSPORT = "log";
+const email = await import("./email");
+
+describe("LogMailer", () => {
+  test("keeps what it was asked to send", async () => {
+    const mailer = new email.LogMailer();
+    const message = { to: "a@example.com", subject: "Hi", text: "Hello" };
+    await mailer.send(message);
+    expect(mailer.sent).toEqual([message]);
+  });
+
+  test("is used when MAIL_TRANSPORT=log", () => {
+    expect(email.mailer).toBeInstanceOf(email.LogMailer);
+  });
+});
+
+describeWithDb("password reset email", () => {
+  beforeAll(async () => {
+    await resetTestDatabase();
+  });
+
+  test("a reset request mails the user a link", async () => {
+    const { auth } = await import("../auth");
+    const user = await createTestUser("forgetful");
+    const { sent } = email.mailer as InstanceType<typeof email.LogMailer>;
+    sent.length = 0;
+
+    await auth.api.requestPasswordReset({
+      body: { email: user.email, redirectTo: "/reset-password" },
+    });
+
+    expect(sent).toHaveLength(1);
+    expect(sent[0]).toMatchObject({
+      to: user.email,
+      subject: "Reset your password",
+    });
+    expect(sent[0].text).toMatch(/reset-password\/[\w-]+/);
+  });
+});
@@ -1,41 +1,6 @@
 import nodemailer from "nodemailer";
 
-// Load environment variables for SMTP configuration
-const smtpHost = process.env.SMTP_HOST as string;
-const smtpPort = process.env.SMTP_PORT
-  ? parseInt(process.env.SMTP_PORT, 10)
-  : 587;
-const smtpUser = process.env.SMTP_USER as string;
-const smtpPass = process.env.SMTP_PASS as string;
-const emailFrom = process.env.EMAIL_FROM as string; // Default 'from' address, e.g., 'no-reply@yourapp.com'
-
-// Validate required env vars
-if (!smtpHost) throw new Error("SMTP_HOST not set in .env");
-if (!smtpUser) throw new Error("SMTP_USER not set in .env");
-if (!smtpPass) throw new Error("SMTP_PASS not set in .env");
-if (!emailFrom) throw new Error("EMAIL_FROM not set in .env");
-
-// Create a reusable transporter object using SMTP transport
-const transporter = nodemailer.createTransport({
-  host: smtpHost,
-  port: smtpPort,
-  secure: smtpPort === 465, // Use true for port 465 (SSL), false for other ports (TLS)
-  auth: {
-    user: smtpUser,
-    pass: smtpPass,
-  },
-});
-
-// Verify the transporter configuration (optional but recommended for initial setup)
-transporter.verify((error, success) => {
-  if (error) {
-    console.error("SMTP connection error:", error);
-  } else {
-    console.log("SMTP server is ready to take messages");
-  }
-});
-
-interface SendEmailOptions {
+export interface SendEmailOptions {
   to: string;
   subject: string;
   text: string;
@@ -46,21 +11,113 @@ interface SendEmailOptions {
   attachments?: Array<{ filename: string; path: string }>; // Optional attachments
 }
 
-export async function sendEmail(options: SendEmailOptions): Promise<void> {
-  const mailOptions = {
-    from: options.from || emailFrom,
-    to: options.to,
-    subject: options.subject,
-    text: options.text,
-    html: options.html,
-    cc: options.cc,
-    bcc: options.bcc,
-    attachments: options.attachments,
-  };
+/**
+ * Something that can deliver an email. Selected with MAIL_TRANSPORT.
+ */
+export interface Mailer {
+  send(options: SendEmailOptions): Promise<void>;
+}
+
+/**
+ * Sends mail through an SMTP server configured with the SMTP_* variables.
+ */
+export class SmtpMailer implements Mailer {
+  private transporter: nodemailer.Transporter;
+  private from: string;
+
+  constructor() {
+    // Load environment variables for SMTP configuration
+    const smtpHost = process.env.SMTP_HOST as string;
+    const smtpPort = process.env.SMTP_PORT
+      ? parseInt(process.env.SMTP_PORT, 10)
+      : 587;
+    const smtpUser = process.env.SMTP_USER as string;
+    const smtpPass = process.env.SMTP_PASS as string;
+    const emailFrom = process.env.EMAIL_FROM as string; // Default 'from' address, e.g., 'no-reply@yourapp.com'
+
+    // Validate required env vars
+    if (!smtpHost) throw new Error("SMTP_HOST not set in .env");
+    if (!smtpUser) throw new Error("SMTP_USER not set in .env");
+    if (!smtpPass) throw new Error("SMTP_PASS not set in .env");
+    if (!emailFrom) throw new Error("EMAIL_FROM not set in .env");
+
+    this.from = emailFrom;
+
+    // Create a reusable transporter object using SMTP transport
+    this.transporter = nodemailer.createTransport({
+      host: smtpHost,
+      port: smtpPort,
+      secure: smtpPort === 465, // Use true for port 465 (SSL), false for other ports (TLS)
+      auth: {
+        user: smtpUser,
+        pass: smtpPass,
+      },
+    });
+
+    // Verify the transporter configuration (optional but recommended for initial setup)
+    this.transporter.verify((error) => {
+      if (error) {
+        console.error("SMTP connection error:", error);
+      } else {
+        console.log("SMTP server is ready to take messages");
+      }
+    });
+  }
 
+  async send(options: SendEmailOptions): Promise<void> {
+    await this.transporter.sendMail({
+      from: options.from || this.from,
+      to: options.to,
+      subject: options.subject,
+      text: options.text,
+      html: options.html,
+      cc: options.cc,
+      bcc: options.bcc,
+      attachments: options.attachments,
+    });
+  }
+}
+
+/**
+ * Logs emails instead of sending them, for development without SMTP.
+ * Sent messages are kept in `sent` so they can be inspected.
+ */
+export class LogMailer implements Mailer {
+  sent: SendEmailOptions[] = [];
+
+  async send(options: SendEmailOptions): Promise<void> {
+    this.sent.push(options);
+    console.log(
+      `[mail] To: ${options.to}\n[mail] Subject: ${options.subject}\n${options.text}`
+    );
+  }
+}
+
+function createMailer(): Mailer {
+  // Production always needs real mail; in development SMTP is optional
+  const transport =
+    process.env.MAIL_TRANSPORT ||
+    (process.env.NODE_ENV === "production" || process.env.SMTP_HOST
+      ? "smtp"
+      : "log");
+
+  switch (transport) {
+    case "smtp":
+      return new SmtpMailer();
+    case "log":
+      console.log("MAIL_TRANSPORT=log: emails will be logged, not sent");
+      return new LogMailer();
+    default:
+      throw new Error(`Unknown MAIL_TRANSPORT "${transport}"`);
+  }
+}
+
+export const mailer: Mailer = createMailer();
+
+export async function sendEmail(options: SendEmailOptions): Promise<void> {
   try {
     console.log(`Sending email to ${options.to}`);
-    await transporter.sendMail(mailOptions);
+    await mailer.send(options);
     console.log(`Email sent successfully to ${options.to}`);
   } catch (error) {
     console.error("Error sending email:", error);
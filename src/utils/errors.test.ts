@@ -0,0 +1,108 @@
+import { describe, expect, test } from "bun:test";
+import { ApiError, FieldErrors, handleApiError } from "./errors";
+
+function handle(code: string, error: unknown, headers: HeadersInit = {}) {
+  const set: { status?: number | string; headers: Record<string, any> } = {
+    headers: {},
+  };
+  const request = new Request("http://localhost/blog", {
+    method: "POST",
+    headers,
+  });
+  return handleApiError({ code, error, set, request }).then((body) => ({
+    body,
+    set,
+  }));
+}
+
+describe("handleApiError", () => {
+  test("answers an unparseable JSON body with a structured 400", async () => {
+    const { body, set } = await handle("PARSE", new Error("Unexpected EOF"), {
+      "content-type": "application/json",
+    });
+    expect(set.status).toBe(400);
+    expect(body).toEqual({
+      error: {
+        code: "invalid_body",
+        message:
+          "Request body could not be parsed, expected valid JSON with " +
+          "Content-Type: application/json",
+      },
+    });
+  });
+
+  test("names the content type that failed to parse", async () => {
+    const { body } = await handle("PARSE", new Error("bad form"), {
+      "content-type": "multipart/form-data; boundary=x",
+    });
+    expect((body as any).error.message).toEndWith(
+      "expected a valid multipart/form-data body"
+    );
+  });
+
+  test("says why a 401 has no session", async () => {
+    const { body, set } = await handle(
+      "UNKNOWN",
+      new ApiError(401, "Unauthorized")
+    );
+    expect(set.status).toBe(401);
+    expect(set.headers["www-authenticate"]).toBe(
+      'Session error="missing_session"'
+    );
+    expect(body).toEqual({
+      error: { code: "unauthorized", message: "Unauthorized" },
+    });
+  });
+
+  test("hides unexpected errors behind a 500", async () => {
+    const { body, set } = await handle("UNKNOWN", new Error("db is down"));
+    expect(set.status).toBe(500);
+    expect(body).toEqual({
+      error: { code: "internal_error", message: "Internal server error" },
+    });
+  });
+});
+
+describe("validation errors", () => {
+  test("list every invalid field in a 422", async () => {
+    const fields = new FieldErrors();
+    fields.add("title", "Required");
+    fields.add("content", "Too long");
+    fields.add("content", "Too long");
+
+    let thrown: unknown;
+    try {
+      fields.throwIfAny();
+    } catch (error) {
+      thrown = error;
+    }
+    const { body, set } = await handle("UNKNOWN", thrown);
+    expect(set.status).toBe(422);
+    expect(body).toEqual({
+      error: {
+        code: "validation_error",
+        message: "Invalid values for title, content",
+        fields: { title: ["Required"], content: ["Too long"] },
+      },
+    });
+  });
+
+  test("turn schema failures into field names", async () => {
+    const error = {
+      all: [
+        { path: "/deck/0/term", message: "Expected string" },
+        { path: "", message: "Expected object" },
+      ],
+    };
+    const { body, set } = await handle("VALIDATION", error);
+    expect(set.status).toBe(422);
+    expect((body as any).error.fields).toEqual({
+      "deck.0.term": ["Expected string"],
+      request: ["Expected object"],
+    });
+  });
+
+  test("don't throw when every field is valid", () => {
+    expect(() => new FieldErrors().throwIfAny()).not.toThrow();
+  });
+});
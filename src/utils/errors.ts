@@ -0,0 +1,172 @@
+import { t } from "elysia";
+import { diagnoseSession } from "./session-errors";
+
+// Codes sent instead of "unauthorized" when the session cookie is the problem
+const SESSION_FAILURE_CODES = {
+  missing_session: "unauthorized",
+  session_expired: "session_expired",
+  invalid_session: "invalid_session",
+};
+
+// Machine-readable codes for each status we return
+const DEFAULT_CODES: Record<number, string> = {
+  400: "bad_request",
+  401: "unauthorized",
+  403: "forbidden",
+  404: "not_found",
+  409: "conflict",
+  413: "payload_too_large",
+  422: "validation_error",
+  429: "rate_limited",
+  500: "internal_error",
+};
+
+/**
+ * Error with an HTTP status, serialised by `handleApiError` as
+ * `{ error: { code, message } }`. The message is shown to clients.
+ */
+export class ApiError extends Error {
+  status: number;
+  code: string;
+
+  constructor(status: number, message: string, code?: string) {
+    super(message);
+    this.name = "ApiError";
+    this.status = status;
+    this.code = code ?? DEFAULT_CODES[status] ?? "error";
+  }
+}
+
+export type FieldErrorMap = Record<string, string[]>;
+
+/**
+ * 422 listing every invalid field, serialised with an extra `fields` map of
+ * field name to messages so forms can highlight each one.
+ */
+export class ValidationError extends ApiError {
+  fields: FieldErrorMap;
+
+  constructor(fields: FieldErrorMap, message = "Invalid request") {
+    super(422, message);
+    this.name = "ValidationError";
+    this.fields = fields;
+  }
+}
+
+/**
+ * Collects field errors so a handler can report all of them at once:
+ * call `add` for each problem, then `throwIfAny` before using the input.
+ */
+export class FieldErrors {
+  private fields: FieldErrorMap = {};
+
+  add(field: string, message: string) {
+    const messages = (this.fields[field] ??= []);
+    if (!messages.includes(message)) messages.push(message);
+  }
+
+  /**
+   * One-line message for the whole set, used as the error's `message`.
+   */
+  summary(fallback: string) {
+    const fields = Object.keys(this.fields);
+    if (fields.length === 0) return fallback;
+    if (fields.length === 1) {
+      return `Invalid value for ${fields[0]}: ${this.fields[fields[0]][0]}`;
+    }
+    return `Invalid values for ${fields.join(", ")}`;
+  }
+
+  toJSON(): FieldErrorMap {
+    return this.fields;
+  }
+
+  throwIfAny() {
+    if (Object.keys(this.fields).length === 0) return;
+    throw new ValidationError(this.fields, this.summary("Invalid request"));
+  }
+}
+
+export const ErrorResponseSchema = t.Object({
+  error: t.Object({
+    code: t.String(),
+    message: t.String(),
+    fields: t.Optional(t.Record(t.String(), t.Array(t.String()))),
+  }),
+});
+
+function errorBody(code: string, message: string, fields?: FieldErrorMap) {
+  return { error: fields ? { code, message, fields } : { code, message } };
+}
+
+// Turns a schema path like "/deck/0/term" into "deck.0.term"
+const fieldName = (path: string) =>
+  path.replace(/^\//, "").replace(/\//g, ".") || "request";
+
+/**
+ * Shared `.onError` handler for routes using `ApiError`.
+ * Validation failures become 422s listing every bad field, and bodies that
+ * can't be parsed a 400 `invalid_body`; anything unexpected (including
+ * database errors) becomes a generic 500 so internals never leak to clients.
+ * 401s carry a `WWW-Authenticate` header saying whether the session was
+ * missing, expired or invalid.
+ */
+export async function handleApiError({
+  code,
+  error: err,
+  set,
+  request,
+}: {
+  code: string | number;
+  error: unknown;
+  set: { status?: number | string; headers: Record<string, any> };
+  request: Request;
+}) {
+  if (err instanceof ApiError) {
+    set.status = err.status;
+    if (err.status === 401 && err.code === "unauthorized") {
+      const failure = await diagnoseSession(request.headers);
+      set.headers["www-authenticate"] = `Session error="${failure}"`;
+      return errorBody(SESSION_FAILURE_CODES[failure], err.message);
+    }
+    if (err instanceof ValidationError) {
+      return errorBody(err.code, err.message, err.fields);
+    }
+    return errorBody(err.code, err.message);
+  }
+  if (code === "VALIDATION") {
+    // Report every offending field without echoing the whole schema
+    const fields = new FieldErrors();
+    for (const { path, message } of (err as any)?.all ?? []) {
+      fields.add(fieldName(path), message);
+    }
+    set.status = 422;
+    return errorBody(
+      "validation_error",
+      fields.summary("Invalid request"),
+      fields.toJSON()
+    );
+  }
+  if (code === "PARSE") {
+    // Elysia parses bodies by content type, so say which one failed
+    const contentType =
+      request.headers.get("content-type")?.split(";")[0].trim() || null;
+    const expected =
+      contentType === "application/json" || !contentType
+        ? "valid JSON with Content-Type: application/json"
+        : `a valid ${contentType} body`;
+    set.status = 400;
+    return errorBody(
+      "invalid_body",
+      `Request body could not be parsed, expected ${expected}`
+    );
+  }
+  if (code === "NOT_FOUND") {
+    set.status = 404;
+    return errorBody("not_found", "Not found");
+  }
+
+  console.error("Unhandled error:", err instanceof Error ? err.message : err);
+  set.status = 500;
+  return errorBody("internal_error", "Internal server error");
+}
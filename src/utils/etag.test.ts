@@ -0,0 +1,27 @@
+import { describe, expect, test } from "bun:test";
+import { etagFor, matchesEtag, notModified } from "./etag";
+
+describe("ETags", () => {
+  test("change with the body", () => {
+    const etag = etagFor({ id: "post", likes: 1 });
+    expect(etag).toMatch(/^"[\w-]{27}"$/);
+    expect(etagFor({ id: "post", likes: 1 })).toBe(etag);
+    expect(etagFor({ id: "post", likes: 2 })).not.toBe(etag);
+  });
+
+  test("match If-None-Match lists, weak tags and *", () => {
+    const etag = etagFor("body");
+    expect(matchesEtag(etag, etag)).toBe(true);
+    expect(matchesEtag(`"other", W/${etag}`, etag)).toBe(true);
+    expect(matchesEtag("*", etag)).toBe(true);
+    expect(matchesEtag('"other"', etag)).toBe(false);
+    expect(matchesEtag(null, etag)).toBe(false);
+  });
+
+  test("answer a match with an empty 304", async () => {
+    const response = notModified('"abc"');
+    expect(response.status).toBe(304);
+    expect(response.headers.get("etag")).toBe('"abc"');
+    expect(await response.text()).toBe("");
+  });
+});
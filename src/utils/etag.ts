@@ -0,0 +1,28 @@
+import { createHash } from "node:crypto";
+
+/**
+ * Strong ETag for a JSON response body, so any change to what the client
+ * would receive (content, counts, comments) yields a new tag.
+ */
+export function etagFor(body: unknown): string {
+  const hash = createHash("sha256")
+    .update(JSON.stringify(body))
+    .digest("base64url");
+  return `"${hash.slice(0, 27)}"`;
+}
+
+/**
+ * Whether an `If-None-Match` header matches `etag`. Weak tags match too,
+ * as RFC 9110 requires for this header.
+ */
+export function matchesEtag(header: string | null, etag: string): boolean {
+  if (!header) return false;
+  if (header.trim() === "*") return true;
+  return header
+    .split(",")
+    .some((tag) => tag.trim().replace(/^W\//, "") === etag);
+}
+
+export function notModified(etag: string): Response {
+  return new Response(null, { status: 304, headers: { ETag: etag } });
+}
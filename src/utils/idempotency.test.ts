@@ -0,0 +1,86 @@
+import { describe, expect, test } from "bun:test";
+import { ApiError } from "./errors";
+import { withIdempotency } from "./idempotency";
+
+let nextKey = 1;
+
+function request(key: string | null, userId = "user-1") {
+  const headers = new Headers();
+  if (key) headers.set("idempotency-key", key);
+  return { headers, set: { headers: {} }, userId, scope: "POST /comments" };
+}
+
+function counter() {
+  const run = async () => ({ id: ++run.calls });
+  run.calls = 0;
+  return run;
+}
+
+describe("withIdempotency", () => {
+  test("replays the first result for a repeated key", async () => {
+    const key = `key-${nextKey++}`;
+    const run = counter();
+
+    expect(await withIdempotency(request(key), run)).toEqual({ id: 1 });
+    const replay = request(key);
+    expect(await withIdempotency(replay, run)).toEqual({ id: 1 });
+    expect(replay.set.headers).toEqual({ "idempotent-replayed": "true" });
+    expect(run.calls).toBe(1);
+  });
+
+  test("keeps keys apart per user", async () => {
+    const key = `key-${nextKey++}`;
+    const run = counter();
+    await withIdempotency(request(key, "user-1"), run);
+    expect(await withIdempotency(request(key, "user-2"), run)).toEqual({
+      id: 2,
+    });
+  });
+
+  test("runs every request without a key", async () => {
+    const run = counter();
+    await withIdempotency(request(null), run);
+    await withIdempotency(request(null), run);
+    expect(run.calls).toBe(2);
+  });
+
+  test("makes a concurrent repeat wait for the first", async () => {
+    const key = `key-${nextKey++}`;
+    let finish!: () => void;
+    let calls = 0;
+    const run = () =>
+      new Promise<string>((resolve) => {
+        calls++;
+        finish = () => resolve("created");
+      });
+
+    const first = withIdempotency(request(key), run);
+    const second = withIdempotency(request(key), run);
+    finish();
+    expect(await Promise.all([first, second])).toEqual(["created", "created"]);
+    expect(calls).toBe(1);
+  });
+
+  test("doesn't cache failures", async () => {
+    const key = `key-${nextKey++}`;
+    let calls = 0;
+    const run = async () => {
+      if (++calls === 1) throw new Error("database unavailable");
+      return "created";
+    };
+
+    await expect(withIdempotency(request(key), run)).rejects.toThrow(
+      "database unavailable"
+    );
+    expect(await withIdempotency(request(key), run)).toBe("created");
+    expect(calls).toBe(2);
+  });
+
+  test("rejects overly long keys", async () => {
+    const run = counter();
+    await expect(
+      withIdempotency(request("k".repeat(256)), run)
+    ).rejects.toThrow(ApiError);
+    expect(run.calls).toBe(0);
+  });
+});
@@ -0,0 +1,72 @@
+import { ApiError } from "./errors";
+
+// How long a key's result is kept for replays
+const IDEMPOTENCY_TTL_MS = parseInt(
+  process.env.IDEMPOTENCY_TTL_MS || String(24 * 60 * 60 * 1000)
+);
+const MAX_KEY_LENGTH = 255;
+const SWEEP_INTERVAL_MS = 60 * 1000;
+
+interface Entry {
+  result: Promise<unknown>;
+  expiresAt: number;
+}
+
+const entries = new Map<string, Entry>();
+let lastSweep = 0;
+
+function sweep(now: number) {
+  if (now - lastSweep < SWEEP_INTERVAL_MS) return;
+  lastSweep = now;
+  for (const [key, entry] of entries) {
+    if (entry.expiresAt <= now) entries.delete(key);
+  }
+}
+
+/**
+ * Runs `run` at most once per `Idempotency-Key` header value, user and
+ * endpoint (`scope`). A repeat within the TTL gets the first result (with
+ * an `Idempotent-Replayed` header), and one sent while the first is still
+ * running waits for it. Failed requests aren't cached so they can be
+ * retried. Without the header `run` is simply called.
+ */
+export async function withIdempotency<T>(
+  {
+    headers,
+    set,
+    userId,
+    scope,
+  }: {
+    headers: Headers;
+    set: { headers: Record<string, any> };
+    userId: string;
+    scope: string;
+  },
+  run: () => Promise<T>
+): Promise<T> {
+  const key = headers.get("idempotency-key");
+  if (!key) return run();
+  if (key.length > MAX_KEY_LENGTH) {
+    throw new ApiError(
+      400,
+      `Idempotency-Key must be at most ${MAX_KEY_LENGTH} characters`
+    );
+  }
+
+  const now = Date.now();
+  sweep(now);
+
+  const id = `${userId}:${scope}:${key}`;
+  const existing = entries.get(id);
+  if (existing && existing.expiresAt > now) {
+    set.headers["idempotent-replayed"] = "true";
+    return existing.result as Promise<T>;
+  }
+
+  const result = run();
+  entries.set(id, { result, expiresAt: now + IDEMPOTENCY_TTL_MS });
+  result.catch(() => {
+    if (entries.get(id)?.result === result) entries.delete(id);
+  });
+  return result;
+}
@@ -0,0 +1,61 @@
+import { describe, expect, test } from "bun:test";
+import type { Namespace } from "socket.io";
+import { FakeNamespace } from "../test-utils/sockets";
+import {
+  recordHttpRequest,
+  registerGauge,
+  renderMetrics,
+  trackSocketConnections,
+} from "./metrics";
+
+// The registry is shared, so each test uses labels nobody else records
+const lines = () => renderMetrics().split("\n");
+
+describe("renderMetrics", () => {
+  test("counts requests by method and status", () => {
+    recordHttpRequest("PATCH", 204, 0.02);
+    recordHttpRequest("PATCH", 204, 0.3);
+    recordHttpRequest("PATCH", 500, 0.001);
+
+    const out = lines();
+    expect(out).toContain("# TYPE http_requests_total counter");
+    expect(out).toContain('http_requests_total{method="PATCH",status="204"} 2');
+    expect(out).toContain('http_requests_total{method="PATCH",status="500"} 1');
+  });
+
+  test("fills latency buckets cumulatively", () => {
+    recordHttpRequest("OPTIONS", 200, 0.02);
+    recordHttpRequest("OPTIONS", 200, 0.3);
+
+    const bucket = (le: string) =>
+      `http_request_duration_seconds_bucket{method="OPTIONS",le="${le}"}`;
+    const out = lines();
+    expect(out).toContain(`${bucket("0.01")} 0`);
+    expect(out).toContain(`${bucket("0.025")} 1`);
+    expect(out).toContain(`${bucket("0.5")} 2`);
+    expect(out).toContain(`${bucket("+Inf")} 2`);
+    expect(out).toContain(
+      'http_request_duration_seconds_count{method="OPTIONS"} 2'
+    );
+  });
+
+  test("reads registered gauges at scrape time", () => {
+    let value = 3;
+    registerGauge("test_widgets", "Widgets in stock", () => value);
+    expect(lines()).toContain("test_widgets 3");
+    value = 5;
+    expect(lines()).toContain("test_widgets 5");
+  });
+
+  test("tracks socket connections per namespace", async () => {
+    const nsp = new FakeNamespace("/sockets/metrics-test");
+    trackSocketConnections(nsp as unknown as Namespace);
+    const gauge = 'socket_connections{namespace="/sockets/metrics-test"}';
+
+    const first = await nsp.connect();
+    await nsp.connect();
+    expect(lines()).toContain(`${gauge} 2`);
+    first.drop();
+    expect(lines()).toContain(`${gauge} 1`);
+  });
+});
@@ -0,0 +1,200 @@
+// Minimal Prometheus metrics registry rendered in the text exposition format
+
+import type { Namespace } from "socket.io";
+
+type Labels = Record<string, string>;
+
+interface Metric {
+  render(): string[];
+}
+
+const registry: Metric[] = [];
+
+function escapeLabel(value: string): string {
+  return value
+    .replace(/\\/g, "\\\\")
+    .replace(/"/g, '\\"')
+    .replace(/\n/g, "\\n");
+}
+
+function labelKey(labels: Labels): string {
+  return Object.keys(labels)
+    .sort()
+    .map((key) => `${key}="${escapeLabel(labels[key])}"`)
+    .join(",");
+}
+
+function sample(name: string, key: string, value: number): string {
+  return key ? `${name}{${key}} ${value}` : `${name} ${value}`;
+}
+
+class Counter implements Metric {
+  private values = new Map<string, number>();
+
+  constructor(private name: string, private help: string) {
+    registry.push(this);
+  }
+
+  inc(labels: Labels = {}, value = 1) {
+    const key = labelKey(labels);
+    this.values.set(key, (this.values.get(key) ?? 0) + value);
+  }
+
+  render() {
+    return [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} counter`,
+      ...[...this.values].map(([key, value]) => sample(this.name, key, value)),
+    ];
+  }
+}
+
+class Gauge implements Metric {
+  private values = new Map<string, number>();
+
+  /**
+   * `collect`, when given, is called at scrape time to read the value.
+   */
+  constructor(
+    private name: string,
+    private help: string,
+    private collect?: () => number
+  ) {
+    registry.push(this);
+  }
+
+  inc(labels: Labels = {}, value = 1) {
+    const key = labelKey(labels);
+    this.values.set(key, (this.values.get(key) ?? 0) + value);
+  }
+
+  dec(labels: Labels = {}, value = 1) {
+    this.inc(labels, -value);
+  }
+
+  render() {
+    if (this.collect) this.values.set("", this.collect());
+    return [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} gauge`,
+      ...[...this.values].map(([key, value]) => sample(this.name, key, value)),
+    ];
+  }
+}
+
+class Histogram implements Metric {
+  private series = new Map<
+    string,
+    { buckets: number[]; sum: number; count: number }
+  >();
+
+  constructor(
+    private name: string,
+    private help: string,
+    private bounds: number[]
+  ) {
+    registry.push(this);
+  }
+
+  observe(labels: Labels, value: number) {
+    const key = labelKey(labels);
+    let series = this.series.get(key);
+    if (!series) {
+      series = { buckets: this.bounds.map(() => 0), sum: 0, count: 0 };
+      this.series.set(key, series);
+    }
+    this.bounds.forEach((bound, i) => {
+      if (value <= bound) series!.buckets[i]++;
+    });
+    series.sum += value;
+    series.count++;
+  }
+
+  render() {
+    const lines = [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} histogram`,
+    ];
+    for (const [key, series] of this.series) {
+      const prefix = key ? `${key},` : "";
+      this.bounds.forEach((bound, i) => {
+        lines.push(
+          `${this.name}_bucket{${prefix}le="${bound}"} ${series.buckets[i]}`
+        );
+      });
+      lines.push(`${this.name}_bucket{${prefix}le="+Inf"} ${series.count}`);
+      lines.push(sample(`${this.name}_sum`, key, series.sum));
+      lines.push(sample(`${this.name}_count`, key, series.count));
+    }
+    return lines;
+  }
+}
+
+export const httpRequestsTotal = new Counter(
+  "http_requests_total",
+  "Total HTTP requests by method and status"
+);
+
+export const httpRequestDuration = new Histogram(
+  "http_request_duration_seconds",
+  "HTTP request latency in seconds",
+  [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10]
+);
+
+export const socketConnections = new Gauge(
+  "socket_connections",
+  "Currently connected Socket.IO clients by namespace"
+);
+
+export const socketInvalidMessages = new Counter(
+  "socket_invalid_messages_total",
+  "Socket.IO messages dropped for being binary or malformed"
+);
+
+/**
+ * Registers a gauge whose value is read when the metrics are scraped.
+ */
+export function registerGauge(
+  name: string,
+  help: string,
+  collect: () => number
+) {
+  return new Gauge(name, help, collect);
+}
+
+/**
+ * Records one finished HTTP request.
+ */
+export function recordHttpRequest(
+  method: string,
+  status: number,
+  durationSeconds: number
+) {
+  httpRequestsTotal.inc({ method, status: String(status) });
+  httpRequestDuration.observe({ method }, durationSeconds);
+}
+
+/**
+ * Keeps `socket_connections` up to date for a namespace.
+ */
+export function trackSocketConnections(nsp: Namespace) {
+  nsp.on("connection", (socket) => {
+    socketConnections.inc({ namespace: nsp.name });
+    socket.on("disconnect", () => {
+      socketConnections.dec({ namespace: nsp.name });
+    });
+  });
+}
+
+/**
+ * Renders every metric in the Prometheus text format.
+ */
+export function renderMetrics(): string {
+  return registry.flatMap((metric) => metric.render()).join("\n") + "\n";
+}
+
+export function metricsResponse(): Response {
+  return new Response(renderMetrics(), {
+    headers: { "Content-Type": "text/plain; version=0.0.4; charset=utf-8" },
+  });
+}
@@ -0,0 +1,141 @@
+import { describe, expect, test } from "bun:test";
+import { ApiError } from "./errors";
+import {
+  DEFAULT_PAGE_SIZE,
+  MAX_PAGE_SIZE,
+  decodeCursor,
+  encodeCursor,
+  isAfterCursor,
+  mongoCursorFilter,
+  paginate,
+  parseLimit,
+  setPaginationHeaders,
+} from "./pagination";
+
+// Newest first, with a createdAt tie broken by id
+const ITEMS = [
+  { id: "e", createdAt: new Date("2026-01-05T00:00:00Z") },
+  { id: "d", createdAt: new Date("2026-01-04T00:00:00Z") },
+  { id: "c2", createdAt: new Date("2026-01-03T00:00:00Z") },
+  { id: "c1", createdAt: new Date("2026-01-03T00:00:00Z") },
+  { id: "a", createdAt: new Date("2026-01-01T00:00:00Z") },
+];
+
+describe("cursors", () => {
+  test("round-trip through encode and decode", () => {
+    const createdAt = new Date("2026-01-03T12:34:56.789Z");
+    expect(decodeCursor(encodeCursor(createdAt, "abc"))).toEqual({
+      createdAt,
+      id: "abc",
+    });
+  });
+
+  test("reject malformed cursors", () => {
+    const bad = [
+      "not base64 json",
+      Buffer.from("[1,2]").toString("base64url"),
+      Buffer.from('["not a date","id"]').toString("base64url"),
+    ];
+    for (const cursor of bad) {
+      expect(() => decodeCursor(cursor)).toThrow(ApiError);
+    }
+  });
+
+  test("order by createdAt then id, newest first", () => {
+    const cursor = { createdAt: ITEMS[2].createdAt, id: "c2" };
+    expect(ITEMS.filter((item) => isAfterCursor(item, cursor))).toEqual([
+      ITEMS[3],
+      ITEMS[4],
+    ]);
+  });
+
+  test("build the matching MongoDB filter", () => {
+    const createdAt = new Date("2026-01-03T00:00:00Z");
+    expect(mongoCursorFilter({ createdAt, id: "c2" }, (id) => id)).toEqual({
+      $or: [
+        { createdAt: { $lt: createdAt } },
+        { createdAt, _id: { $lt: "c2" } },
+      ],
+    });
+  });
+});
+
+describe("parseLimit", () => {
+  test("defaults, clamps and ignores junk", () => {
+    expect(parseLimit(undefined)).toBe(DEFAULT_PAGE_SIZE);
+    expect(parseLimit("5")).toBe(5);
+    expect(parseLimit("0")).toBe(DEFAULT_PAGE_SIZE);
+    expect(parseLimit("abc")).toBe(DEFAULT_PAGE_SIZE);
+    expect(parseLimit("100000")).toBe(MAX_PAGE_SIZE);
+  });
+});
+
+describe("paginate", () => {
+  test("follows cursors across a createdAt tie", () => {
+    const first = paginate(ITEMS, { limit: "3" });
+    expect(first.items.map((item) => item.id)).toEqual(["e", "d", "c2"]);
+    expect(first.nextCursor).not.toBeNull();
+
+    const second = paginate(ITEMS, {
+      limit: "3",
+      after: first.nextCursor!,
+    });
+    expect(second.items.map((item) => item.id)).toEqual(["c1", "a"]);
+    expect(second.nextCursor).toBeNull();
+  });
+
+  test("still supports offsets", () => {
+    const page = paginate(ITEMS, { limit: "2", offset: "3" });
+    expect(page.items.map((item) => item.id)).toEqual(["c1", "a"]);
+    expect(page.nextCursor).toBeNull();
+  });
+});
+
+describe("setPaginationHeaders", () => {
+  function headersFor(
+    url: string,
+    page: { total: number; limit: number; nextCursor: string | null }
+  ) {
+    const set = { headers: {} as Record<string, any> };
+    setPaginationHeaders({ request: new Request(url), set }, page);
+    return set.headers;
+  }
+
+  test("links both neighbours from a middle page", () => {
+    const headers = headersFor("http://localhost/comments?limit=2&offset=2", {
+      total: 6,
+      limit: 2,
+      nextCursor: "cursor",
+    });
+    expect(headers["x-total-count"]).toBe("6");
+    expect(headers["link"].split(", ")).toEqual([
+      '</comments?limit=2>; rel="first"',
+      '</comments?limit=2>; rel="prev"',
+      '</comments?limit=2&after=cursor>; rel="next"',
+      '</comments?limit=2&offset=4>; rel="last"',
+    ]);
+  });
+
+  test("only links first and next when paging by cursor", () => {
+    const headers = headersFor(
+      "http://localhost/comments?limit=2&after=abc&sort=new",
+      { total: 6, limit: 2, nextCursor: "def" }
+    );
+    expect(headers["link"].split(", ")).toEqual([
+      '</comments?limit=2&sort=new>; rel="first"',
+      '</comments?limit=2&sort=new&after=def>; rel="next"',
+    ]);
+  });
+
+  test("has no next link on the last page", () => {
+    const headers = headersFor("http://localhost/comments?offset=4&limit=2", {
+      total: 6,
+      limit: 2,
+      nextCursor: null,
+    });
+    expect(headers["link"]).not.toContain('rel="next"');
+    expect(headers["link"]).toContain(
+      '</comments?limit=2&offset=2>; rel="prev"'
+    );
+  });
+});
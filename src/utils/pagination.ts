@@ -0,0 +1,148 @@
+import { t } from "elysia";
+import { ApiError } from "./errors";
+
+export const DEFAULT_PAGE_SIZE = 20;
+export const MAX_PAGE_SIZE = 100;
+
+/**
+ * Query parameters accepted by paginated listings. `after` (a cursor from a
+ * previous page) is preferred over `offset`, which can skip or repeat items
+ * when new ones are added while paging.
+ */
+export const PaginationQuerySchema = t.Object({
+  limit: t.Optional(
+    t.String({ description: `Page size, max ${MAX_PAGE_SIZE}` })
+  ),
+  after: t.Optional(
+    t.String({ description: "Opaque cursor from a previous page" })
+  ),
+  offset: t.Optional(
+    t.String({ description: "Items to skip (prefer `after`)" })
+  ),
+});
+
+export interface Cursor {
+  createdAt: Date;
+  id: string;
+}
+
+/**
+ * Encodes the position of the last item on a page as an opaque cursor.
+ */
+export function encodeCursor(createdAt: Date, id: string): string {
+  return Buffer.from(JSON.stringify([createdAt.toISOString(), id])).toString(
+    "base64url"
+  );
+}
+
+export function decodeCursor(cursor: string): Cursor {
+  try {
+    const [createdAt, id] = JSON.parse(
+      Buffer.from(cursor, "base64url").toString()
+    );
+    const date = new Date(createdAt);
+    if (typeof id !== "string" || isNaN(date.getTime())) throw new Error();
+    return { createdAt: date, id };
+  } catch {
+    throw new ApiError(400, "Invalid cursor");
+  }
+}
+
+export function parseLimit(limit: string | undefined): number {
+  const parsed = parseInt(limit || String(DEFAULT_PAGE_SIZE));
+  if (isNaN(parsed) || parsed < 1) return DEFAULT_PAGE_SIZE;
+  return Math.min(parsed, MAX_PAGE_SIZE);
+}
+
+/**
+ * Whether `item` comes after the cursor in newest-first order
+ * (createdAt descending, then id descending to break ties).
+ */
+export function isAfterCursor(
+  item: { createdAt: Date; id: string },
+  cursor: Cursor
+): boolean {
+  const diff = item.createdAt.getTime() - cursor.createdAt.getTime();
+  return diff < 0 || (diff === 0 && item.id < cursor.id);
+}
+
+/**
+ * Pages an in-memory list that is already sorted newest first.
+ */
+export function paginate<T extends { createdAt: Date; id: string }>(
+  items: T[],
+  query: { limit?: string; after?: string; offset?: string }
+): { items: T[]; nextCursor: string | null } {
+  const limit = parseLimit(query.limit);
+
+  let remaining = items;
+  if (query.after) {
+    const cursor = decodeCursor(query.after);
+    remaining = items.filter((item) => isAfterCursor(item, cursor));
+  } else if (query.offset) {
+    remaining = items.slice(Math.max(0, parseInt(query.offset) || 0));
+  }
+
+  const page = remaining.slice(0, limit);
+  const last = page[page.length - 1];
+  const nextCursor =
+    remaining.length > limit && last
+      ? encodeCursor(last.createdAt, last.id)
+      : null;
+  return { items: page, nextCursor };
+}
+
+/**
+ * MongoDB filter for documents after a cursor, matching the sort
+ * `{ createdAt: -1, _id: -1 }`.
+ */
+export function mongoCursorFilter(cursor: Cursor, toId: (id: string) => any) {
+  return {
+    $or: [
+      { createdAt: { $lt: cursor.createdAt } },
+      { createdAt: cursor.createdAt, _id: { $lt: toId(cursor.id) } },
+    ],
+  };
+}
+
+/**
+ * Sets `X-Total-Count` and an RFC 8288 `Link` header for a page of results.
+ * `first` and `next` are always linked; `prev` and `last` need a position,
+ * so they're only linked for `offset` paging.
+ */
+export function setPaginationHeaders(
+  { request, set }: { request: Request; set: { headers: Record<string, any> } },
+  page: { total: number; limit: number; nextCursor: string | null }
+) {
+  const url = new URL(request.url);
+  const offset = url.searchParams.has("after")
+    ? null
+    : Math.max(0, parseInt(url.searchParams.get("offset") || "0") || 0);
+
+  const link = (params: Record<string, string>) => {
+    const target = new URL(url);
+    target.searchParams.delete("after");
+    target.searchParams.delete("offset");
+    target.searchParams.set("limit", String(page.limit));
+    for (const [key, value] of Object.entries(params)) {
+      target.searchParams.set(key, value);
+    }
+    return `<${target.pathname}${target.search}>`;
+  };
+
+  const links = [`${link({})}; rel="first"`];
+  if (offset !== null && offset > 0) {
+    const prev = Math.max(0, offset - page.limit);
+    links.push(`${link(prev ? { offset: String(prev) } : {})}; rel="prev"`);
+  }
+  if (page.nextCursor) {
+    links.push(`${link({ after: page.nextCursor })}; rel="next"`);
+  }
+  if (offset !== null && page.total > 0) {
+    const last = Math.floor((page.total - 1) / page.limit) * page.limit;
+    links.push(`${link(last ? { offset: String(last) } : {})}; rel="last"`);
+  }
+
+  set.headers["x-total-count"] = String(page.total);
+  set.headers["link"] = links.join(", ");
+}
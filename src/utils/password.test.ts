@@ -0,0 +1,40 @@
+import { describe, expect, test } from "bun:test";
+import { hashUserPassword, verifyUserPassword } from "./password";
+
+const PEPPER = "test-pepper";
+
+describe("password pepper", () => {
+  test("hashes and verifies without a pepper", async () => {
+    const hash = await hashUserPassword("hunter22", "");
+    expect(hash.startsWith("peppered:")).toBe(false);
+    expect(await verifyUserPassword({ hash, password: "hunter22" }, "")).toBe(
+      true
+    );
+    expect(await verifyUserPassword({ hash, password: "wrong" }, "")).toBe(
+      false
+    );
+  });
+
+  test("marks and verifies peppered hashes", async () => {
+    const hash = await hashUserPassword("hunter22", PEPPER);
+    expect(hash.startsWith("peppered:")).toBe(true);
+    const check = (password: string, secret?: string) =>
+      verifyUserPassword({ hash, password }, secret);
+    expect(await check("hunter22", PEPPER)).toBe(true);
+    expect(await check("wrong", PEPPER)).toBe(false);
+  });
+
+  test("keeps hashes from before the pepper working", async () => {
+    const hash = await hashUserPassword("hunter22", "");
+    expect(
+      await verifyUserPassword({ hash, password: "hunter22" }, PEPPER)
+    ).toBe(true);
+  });
+
+  test("rejects peppered hashes without the same pepper", async () => {
+    const hash = await hashUserPassword("hunter22", PEPPER);
+    const password = "hunter22";
+    expect(await verifyUserPassword({ hash, password }, "other")).toBe(false);
+    expect(await verifyUserPassword({ hash, password }, "")).toBe(false);
+  });
+});
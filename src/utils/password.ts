@@ -0,0 +1,46 @@
+import { createHmac } from "node:crypto";
+import { hashPassword, verifyPassword } from "better-auth/crypto";
+
+/**
+ * Optional secret mixed into every new password hash, kept out of the
+ * database so a leaked user table alone can't be cracked offline.
+ *
+ * Rotation caveat: hashes made with a pepper only verify with that same
+ * pepper. Changing or removing PASSWORD_PEPPER locks out everyone whose
+ * hash was made with the old one until they reset their password, so treat
+ * it like an encryption key and never change it casually.
+ */
+const PASSWORD_PEPPER = process.env.PASSWORD_PEPPER || undefined;
+
+// Marks peppered hashes so hashes from before the pepper keep working
+const PEPPERED_PREFIX = "peppered:";
+
+const pepper = (password: string, secret: string) =>
+  createHmac("sha256", secret).update(password).digest("hex");
+
+// `secret` defaults to PASSWORD_PEPPER; better-auth never passes one
+export async function hashUserPassword(
+  password: string,
+  secret = PASSWORD_PEPPER
+) {
+  if (!secret) return hashPassword(password);
+  const hash = await hashPassword(pepper(password, secret));
+  return PEPPERED_PREFIX + hash;
+}
+
+export async function verifyUserPassword(
+  { hash, password }: { hash: string; password: string },
+  secret = PASSWORD_PEPPER
+) {
+  if (!hash.startsWith(PEPPERED_PREFIX)) {
+    return verifyPassword({ hash, password });
+  }
+  if (!secret) {
+    console.error("Peppered password hash found but PASSWORD_PEPPER is unset");
+    return false;
+  }
+  return verifyPassword({
+    hash: hash.slice(PEPPERED_PREFIX.length),
+    password: pepper(password, secret),
+  });
+}
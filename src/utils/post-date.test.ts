@@ -0,0 +1,29 @@
+import { describe, expect, test } from "bun:test";
+import { parsePostDate } from "./post-date";
+
+describe("parsePostDate", () => {
+  test("reads times without an offset as UTC", () => {
+    const utc = "2025-10-18T10:00:00.000Z";
+    expect(parsePostDate("2025-10-18 10:00")?.toISOString()).toBe(utc);
+    expect(parsePostDate("2025-10-18T10:00")?.toISOString()).toBe(utc);
+    expect(parsePostDate(" 2025-10-18 10:00:00 ")?.toISOString()).toBe(utc);
+  });
+
+  test("keeps an explicit offset", () => {
+    expect(parsePostDate("2025-10-18T10:00:00+02:00")?.toISOString()).toBe(
+      "2025-10-18T08:00:00.000Z"
+    );
+  });
+
+  test("passes YAML timestamps through", () => {
+    const date = new Date("2025-10-18T10:00:00Z");
+    expect(parsePostDate(date)).toBe(date);
+  });
+
+  test("returns null for anything else", () => {
+    expect(parsePostDate("next tuesday")).toBeNull();
+    expect(parsePostDate(new Date("invalid"))).toBeNull();
+    expect(parsePostDate(20251018)).toBeNull();
+    expect(parsePostDate(undefined)).toBeNull();
+  });
+});
@@ -0,0 +1,19 @@
+// Date and time without a UTC offset, e.g. "2025-10-18 10:00"
+const LOCAL_DATE_TIME = /^\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(:\d{2}(\.\d+)?)?$/;
+
+/**
+ * Reads a frontmatter timestamp. Times written without an offset are taken
+ * as UTC rather than the server's timezone, so hand-written posts mean the
+ * same thing everywhere. Returns null when the value isn't a valid date.
+ */
+export function parsePostDate(value: unknown): Date | null {
+  let date: Date;
+  if (value instanceof Date) date = value; // Unquoted YAML timestamps
+  else if (typeof value === "string") {
+    const text = value.trim();
+    date = new Date(
+      LOCAL_DATE_TIME.test(text) ? `${text.replace(" ", "T")}Z` : text
+    );
+  } else return null;
+  return isNaN(date.getTime()) ? null : date;
+}
@@ -0,0 +1,5 @@
+// Posts are markdown files named after their ID, kept in src/blogs unless
+// BLOGS_DIR says otherwise
+export const BLOGS_DIR = process.env.BLOGS_DIR || "src/blogs";
+
+export const postPath = (id: string) => `${BLOGS_DIR}/${id}.md`;
@@ -0,0 +1,51 @@
+import { beforeAll, expect, test } from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import {
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import { DEFAULT_CHESS_RATING } from "./elo";
+
+describeWithDb("getChessRatings", () => {
+  let db: Db;
+  let profile: typeof import("./profile");
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    profile = await import("./profile");
+  });
+
+  const rated = async (name: string, chessRating?: number) => {
+    const { id } = await createTestUser(name);
+    if (chessRating === undefined) return;
+    await db
+      .collection("user")
+      .updateOne({ _id: new ObjectId(id) }, { $set: { chessRating } });
+  };
+
+  test("ranks players with ties and leaves unrated ones off", async () => {
+    await rated("first", 1600);
+    await rated("tiedA", 1500);
+    await rated("tiedB", 1500);
+    await rated("fourth", 1400);
+    await rated("newcomer");
+
+    const ratings = await profile.getChessRatings([
+      "tiedB",
+      "first",
+      "newcomer",
+      "fourth",
+      "tiedA",
+      "nobody",
+    ]);
+    expect(ratings).toEqual([
+      { username: "tiedB", rating: 1500, rank: 2 },
+      { username: "first", rating: 1600, rank: 1 },
+      { username: "newcomer", rating: DEFAULT_CHESS_RATING, rank: null },
+      { username: "fourth", rating: 1400, rank: 4 },
+      { username: "tiedA", rating: 1500, rank: 2 },
+      { username: "nobody", rating: null, rank: null },
+    ]);
+  });
+});
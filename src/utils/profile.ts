@@ -2,14 +2,19 @@ import { connectToDatabase } from "../db/connect";
 import { ObjectId } from "mongodb";
 import { PublicUser } from "../routes/profile";
 import { sendNotification } from "./notifications";
+import { ApiError } from "./errors";
+import { DEFAULT_CHESS_RATING, calculateEloDelta } from "./elo";
 
 const { userCollection } = await connectToDatabase();
 
+export { DEFAULT_CHESS_RATING, calculateEloDelta } from "./elo";
+
 // Allowed attributes for leaderboards (whitelist for security)
 export const ALLOWED_LEADERBOARD_ATTRIBUTES: Array<keyof PublicUser> = [
   "age",
   "chessWins",
   "chessLosses",
+  "chessRating",
   "draughtsWins",
   "draughtsLosses",
   "name",
@@ -25,13 +30,13 @@ export async function getPublicUser(userId: string): Promise<PublicUser> {
   try {
     parsedUserId = new ObjectId(userId);
   } catch (error) {
-    throw new Error("Invalid user ID format");
+    throw new ApiError(400, "Invalid user ID format");
   }
 
   const user = await userCollection.findOne({ _id: parsedUserId });
 
   if (!user) {
-    throw new Error("User not found");
+    throw new ApiError(404, "User not found");
   }
 
   // New verification system: use verified fields for public display
@@ -128,11 +133,14 @@ export async function getPublicUser(userId: string): Promise<PublicUser> {
     name: verifiedName,
     image: user.verifiedImage || null,
     isVerified,
+    displayName: user.displayName || null,
+    bio: user.bio || null,
     createdAt: user.createdAt,
     updatedAt: user.updatedAt,
     age: user.age || null,
     chessWins: user.chessWins || 0,
     chessLosses: user.chessLosses || 0,
+    chessRating: user.chessRating ?? DEFAULT_CHESS_RATING,
     draughtsWins: user.draughtsWins || 0,
     draughtsLosses: user.draughtsLosses || 0,
     arithmeticScore: user.arithmeticScore || 0,
@@ -186,6 +194,7 @@ export async function getLeaderboards(
     "age",
     "chessWins",
     "chessLosses",
+    "chessRating",
     "draughtsWins",
     "draughtsLosses",
     "arithmeticScore",
@@ -275,3 +284,123 @@ export async function updateHighScore(
 
   await userCollection.updateOne({ _id: id }, { $max: { [field]: newScore } });
 }
+
+export async function getChessRating(userId: string): Promise<number> {
+  const user = await userCollection.findOne(
+    { _id: new ObjectId(userId) },
+    { projection: { chessRating: 1 } }
+  );
+  return user?.chessRating ?? DEFAULT_CHESS_RATING;
+}
+
+/**
+ * Adds `delta` to a user's rating in a single atomic update, so games ending
+ * at the same time both apply. Users without a rating start from the
+ * default. Returns the new rating.
+ */
+async function applyRatingChange(userId: string, delta: number) {
+  const user = await userCollection.findOneAndUpdate(
+    { _id: new ObjectId(userId) },
+    [
+      {
+        $set: {
+          chessRating: {
+            $add: [{ $ifNull: ["$chessRating", DEFAULT_CHESS_RATING] }, delta],
+          },
+        },
+      },
+      // $max skips a missing peak, so the first rated game sets it
+      {
+        $set: {
+          chessPeakRating: { $max: ["$chessPeakRating", "$chessRating"] },
+        },
+      },
+    ],
+    { returnDocument: "after", projection: { chessRating: 1 } }
+  );
+  return user?.chessRating ?? DEFAULT_CHESS_RATING + delta;
+}
+
+/**
+ * Update both players' chess ratings after a game.
+ * The change is zero-sum: whatever white gains, black loses. The delta is
+ * worked out from the ratings read here, but applied as an increment so a
+ * concurrent game's change to either player is never overwritten.
+ */
+export async function updateChessRatings(
+  whiteId: string,
+  blackId: string,
+  whiteScore: number
+): Promise<{ white: number; black: number; delta: number }> {
+  const [whiteRating, blackRating] = await Promise.all([
+    getChessRating(whiteId),
+    getChessRating(blackId),
+  ]);
+  const delta = calculateEloDelta(whiteRating, blackRating, whiteScore);
+
+  const [white, black] = await Promise.all([
+    applyRatingChange(whiteId, delta),
+    applyRatingChange(blackId, -delta),
+  ]);
+  return { white, black, delta };
+}
+
+/**
+ * Get the top chess players ranked by rating.
+ */
+export async function getChessLeaderboard(limit: number): Promise<PublicUser[]> {
+  const users = await userCollection
+    .find({ chessRating: { $exists: true } })
+    .sort({ chessRating: -1 })
+    .limit(limit)
+    .toArray();
+
+  return await Promise.all(
+    users.map((user) => getPublicUser(user._id.toString()))
+  );
+}
+
+/**
+ * Current rating and leaderboard rank for each username, in one
+ * aggregation. Ranks count players with a higher rating (ties share a
+ * rank); players who have never been rated aren't on the leaderboard, so
+ * their rank is null. Unknown usernames get a null rating.
+ */
+export async function getChessRatings(usernames: string[]) {
+  const found = await userCollection
+    .aggregate<{ name: string; chessRating?: number; rank: number }>([
+      {
+        $match: {
+          $or: [
+            { chessRating: { $exists: true } },
+            { name: { $in: usernames } },
+          ],
+        },
+      },
+      // Unrated users sort after every rated one, so rated ranks are exact
+      {
+        $setWindowFields: {
+          sortBy: { chessRating: -1 },
+          output: { rank: { $rank: {} } },
+        },
+      },
+      { $match: { name: { $in: usernames } } },
+      { $project: { name: 1, chessRating: 1, rank: 1 } },
+    ])
+    .toArray();
+
+  const byName = new Map<string, (typeof found)[number]>();
+  for (const user of found) {
+    if (!byName.has(user.name)) byName.set(user.name, user);
+  }
+
+  return usernames.map((username) => {
+    const user = byName.get(username);
+    if (!user) return { username, rating: null, rank: null };
+    return {
+      username,
+      rating: user.chessRating ?? DEFAULT_CHESS_RATING,
+      rank: user.chessRating !== undefined ? user.rank : null,
+    };
+  });
+}
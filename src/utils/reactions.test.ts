@@ -0,0 +1,69 @@
+import { describe, expect, test } from "bun:test";
+import { toggleReaction } from "./reactions";
+
+type Doc = Record<string, any>;
+
+const matches = (doc: Doc, filter: Doc) =>
+  Object.entries(filter).every(([key, value]) => doc[key] === value);
+
+// Just enough of a MongoDB collection for toggleReaction
+function memoryCollection() {
+  const docs: Doc[] = [];
+  let nextId = 0;
+  const collection = {
+    findOne: async (filter: Doc) =>
+      docs.find((doc) => matches(doc, filter)) ?? null,
+    insertOne: async (doc: Doc) => {
+      docs.push({ _id: nextId++, ...doc });
+    },
+    deleteOne: async (filter: Doc) => {
+      const index = docs.findIndex((doc) => matches(doc, filter));
+      if (index >= 0) docs.splice(index, 1);
+      return { deletedCount: index >= 0 ? 1 : 0 };
+    },
+    updateOne: async (filter: Doc, update: { $set: Doc }) => {
+      const doc = docs.find((d) => matches(d, filter));
+      if (doc) Object.assign(doc, update.$set);
+      return { modifiedCount: doc ? 1 : 0 };
+    },
+  };
+  return { docs, collection: collection as any };
+}
+
+describe("toggleReaction", () => {
+  const target = { blogId: "post" };
+
+  test("none → like adds the reaction", async () => {
+    const { docs, collection } = memoryCollection();
+    expect(await toggleReaction(collection, target, "u1", "like")).toBe(
+      "like"
+    );
+    expect(docs).toHaveLength(1);
+    expect(docs[0]).toMatchObject({ blogId: "post", userId: "u1" });
+  });
+
+  test("like → none removes it", async () => {
+    const { docs, collection } = memoryCollection();
+    await toggleReaction(collection, target, "u1", "like");
+    expect(await toggleReaction(collection, target, "u1", "like")).toBeNull();
+    expect(docs).toHaveLength(0);
+  });
+
+  test("like → dislike switches it", async () => {
+    const { docs, collection } = memoryCollection();
+    await toggleReaction(collection, target, "u1", "like");
+    expect(await toggleReaction(collection, target, "u1", "dislike")).toBe(
+      "dislike"
+    );
+    expect(docs).toHaveLength(1);
+    expect(docs[0].type).toBe("dislike");
+  });
+
+  test("keeps other users' reactions apart", async () => {
+    const { docs, collection } = memoryCollection();
+    await toggleReaction(collection, target, "u1", "like");
+    await toggleReaction(collection, target, "u2", "like");
+    await toggleReaction(collection, target, "u1", "like");
+    expect(docs.map((doc) => doc.userId)).toEqual(["u2"]);
+  });
+});
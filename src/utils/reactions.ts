@@ -0,0 +1,64 @@
+import { Collection } from "mongodb";
+import { ReactionType } from "../db/models";
+import { ApiError } from "./errors";
+
+// Concurrent clicks by the same user rarely collide more than once
+const MAX_TOGGLE_ATTEMPTS = 3;
+
+const DUPLICATE_KEY = 11000;
+
+/**
+ * Applies a like/dislike click for `userId` on the target matched by
+ * `target` (e.g. `{ blogId }` or `{ commentId }`): a first click adds the
+ * reaction, clicking the same type again removes it and clicking the other
+ * type switches it.
+ * Each write only applies if the reaction is still as it was read (the
+ * unique index stops double inserts), otherwise the click is re-evaluated,
+ * so concurrent clicks can't leave duplicate or stale reactions.
+ * Counts are always computed from the reactions, so no counters are kept.
+ * Returns the user's reaction afterwards, or null if it was removed.
+ */
+export async function toggleReaction(
+  collection: Collection<any>,
+  target: Record<string, string>,
+  userId: string,
+  type: ReactionType
+): Promise<ReactionType | null> {
+  for (let attempt = 0; attempt < MAX_TOGGLE_ATTEMPTS; attempt++) {
+    const existingReaction = await collection.findOne({ ...target, userId });
+
+    if (!existingReaction) {
+      // Insert new reaction
+      try {
+        await collection.insertOne({
+          ...target,
+          userId,
+          type,
+          createdAt: new Date(),
+        });
+        return type;
+      } catch (error: any) {
+        if (error?.code === DUPLICATE_KEY) continue;
+        throw error;
+      }
+    }
+
+    if (existingReaction.type === type) {
+      // Toggle off
+      const { deletedCount } = await collection.deleteOne({
+        _id: existingReaction._id,
+        type,
+      });
+      if (deletedCount) return null;
+      continue;
+    }
+
+    // Switch type
+    const { modifiedCount } = await collection.updateOne(
+      { _id: existingReaction._id, type: existingReaction.type },
+      { $set: { type } }
+    );
+    if (modifiedCount) return type;
+  }
+  throw new ApiError(409, "Reaction changed concurrently, try again");
+}
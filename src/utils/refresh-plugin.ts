@@ -0,0 +1,134 @@
+import type { BetterAuthPlugin } from "better-auth";
+import {
+  APIError,
+  createAuthEndpoint,
+  createAuthMiddleware,
+} from "better-auth/api";
+import { setSessionCookie } from "better-auth/cookies";
+import {
+  REFRESH_TOKEN_EXPIRES_IN,
+  RefreshTokenStore,
+  issueRefreshToken,
+  revokeRefreshToken,
+  useRefreshToken,
+} from "./refresh-tokens";
+
+const REFRESH_COOKIE = "refresh_token";
+// Sign-in routes that start a new refresh token family
+const ISSUING_PATHS = ["/sign-in/email", "/sign-up/email"];
+
+const REFRESH_ERRORS = {
+  invalid: "Invalid refresh token",
+  expired: "Refresh token expired",
+  reused: "Refresh token reused, all sessions in its chain were revoked",
+};
+
+/**
+ * Short-lived sessions (the access token) plus a long-lived, rotating
+ * refresh token. Sign-in returns `refreshToken` in the body and sets it as
+ * an HttpOnly cookie; POST /auth/api/refresh, with the token in the body or
+ * that cookie, ends the current session and returns a new session token and
+ * refresh token. A refresh token can be exchanged once, reuse revokes its
+ * whole chain.
+ */
+export function refreshTokens(store: RefreshTokenStore) {
+  return {
+    id: "refresh-tokens",
+    endpoints: {
+      refreshSession: createAuthEndpoint(
+        "/refresh",
+        { method: "POST" },
+        async (ctx) => {
+          const cookie = ctx.context.createAuthCookie(REFRESH_COOKIE, {
+            maxAge: REFRESH_TOKEN_EXPIRES_IN,
+          });
+          const body = ctx.body as { refreshToken?: unknown } | undefined;
+          const token = body?.refreshToken ?? ctx.getCookie(cookie.name);
+          if (typeof token !== "string" || !token) {
+            throw new APIError("UNAUTHORIZED", {
+              message: "Refresh token required",
+            });
+          }
+
+          const result = await useRefreshToken(store, token);
+          if (!result.ok) {
+            for (const sessionToken of result.revokedSessions) {
+              await ctx.context.internalAdapter.deleteSession(sessionToken);
+            }
+            ctx.setCookie(cookie.name, "", { ...cookie.attributes, maxAge: 0 });
+            throw new APIError("UNAUTHORIZED", {
+              message: REFRESH_ERRORS[result.reason],
+            });
+          }
+
+          const user = await ctx.context.internalAdapter.findUserById(
+            result.userId
+          );
+          if (!user) {
+            throw new APIError("UNAUTHORIZED", {
+              message: REFRESH_ERRORS.invalid,
+            });
+          }
+
+          // Only the newest access token in a chain stays valid
+          await ctx.context.internalAdapter.deleteSession(result.sessionToken);
+          const session = await ctx.context.internalAdapter.createSession(
+            user.id,
+            ctx
+          );
+          await setSessionCookie(ctx, { session, user });
+
+          const refreshToken = await issueRefreshToken(
+            store,
+            user.id,
+            session.token,
+            result.family
+          );
+          ctx.setCookie(cookie.name, refreshToken, cookie.attributes);
+          return ctx.json({ token: session.token, refreshToken, user });
+        }
+      ),
+    },
+    hooks: {
+      after: [
+        {
+          matcher: (ctx) => ISSUING_PATHS.includes(ctx.path),
+          handler: createAuthMiddleware(async (ctx) => {
+            // Missing when sign-up waits for email verification
+            const newSession = ctx.context.newSession;
+            const returned = ctx.context.returned;
+            if (!newSession || !returned || returned instanceof APIError) {
+              return;
+            }
+
+            const refreshToken = await issueRefreshToken(
+              store,
+              newSession.user.id,
+              newSession.session.token
+            );
+            const cookie = ctx.context.createAuthCookie(REFRESH_COOKIE, {
+              maxAge: REFRESH_TOKEN_EXPIRES_IN,
+            });
+            ctx.setCookie(cookie.name, refreshToken, cookie.attributes);
+            return ctx.json({ ...(returned as object), refreshToken });
+          }),
+        },
+        {
+          matcher: (ctx) => ctx.path === "/sign-out",
+          handler: createAuthMiddleware(async (ctx) => {
+            const cookie = ctx.context.createAuthCookie(REFRESH_COOKIE);
+            const body = ctx.body as { refreshToken?: unknown } | undefined;
+            const token = body?.refreshToken ?? ctx.getCookie(cookie.name);
+            if (typeof token !== "string" || !token) return;
+
+            const sessions = await revokeRefreshToken(store, token);
+            for (const sessionToken of sessions) {
+              await ctx.context.internalAdapter.deleteSession(sessionToken);
+            }
+            ctx.setCookie(cookie.name, "", { ...cookie.attributes, maxAge: 0 });
+          }),
+        },
+      ],
+    },
+  } satisfies BetterAuthPlugin;
+}
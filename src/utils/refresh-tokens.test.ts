@@ -0,0 +1,134 @@
+import { describe, expect, test } from "bun:test";
+import type { RefreshToken } from "../db/models";
+import {
+  RefreshTokenStore,
+  hashRefreshToken,
+  issueRefreshToken,
+  revokeRefreshToken,
+  useRefreshToken,
+} from "./refresh-tokens";
+
+function memoryStore() {
+  const docs: RefreshToken[] = [];
+  const store: RefreshTokenStore = {
+    insert: async (doc) => {
+      docs.push({ ...doc });
+    },
+    find: async (tokenHash) =>
+      docs.find((doc) => doc.tokenHash === tokenHash) ?? null,
+    markUsed: async (tokenHash, usedAt) => {
+      const doc = docs.find((d) => d.tokenHash === tokenHash && !d.usedAt);
+      if (!doc) return null;
+      doc.usedAt = usedAt;
+      return { ...doc, usedAt: null };
+    },
+    revokeFamily: async (family, revokedAt) => {
+      const chain = docs.filter((doc) => doc.family === family);
+      for (const doc of chain) doc.revokedAt ??= revokedAt;
+      return chain.map((doc) => doc.sessionToken);
+    },
+  };
+  return { docs, store };
+}
+
+describe("refresh tokens", () => {
+  test("are stored hashed", async () => {
+    const { docs, store } = memoryStore();
+    const token = await issueRefreshToken(store, "user-1", "session-1");
+    expect(docs).toHaveLength(1);
+    expect(docs[0].tokenHash).toBe(hashRefreshToken(token));
+    expect(docs[0].tokenHash).not.toBe(token);
+  });
+
+  test("rotate within the same family", async () => {
+    const { store } = memoryStore();
+    const first = await issueRefreshToken(store, "user-1", "session-1");
+
+    const used = await useRefreshToken(store, first);
+    expect(used).toMatchObject({
+      ok: true,
+      userId: "user-1",
+      sessionToken: "session-1",
+    });
+    if (!used.ok) return;
+
+    const second = await issueRefreshToken(
+      store,
+      "user-1",
+      "session-2",
+      used.family
+    );
+    expect(await useRefreshToken(store, second)).toMatchObject({
+      ok: true,
+      family: used.family,
+      sessionToken: "session-2",
+    });
+  });
+
+  test("reuse revokes the whole chain", async () => {
+    const { store } = memoryStore();
+    const first = await issueRefreshToken(store, "user-1", "session-1");
+    const used = await useRefreshToken(store, first);
+    if (!used.ok) throw new Error("first exchange failed");
+    const second = await issueRefreshToken(
+      store,
+      "user-1",
+      "session-2",
+      used.family
+    );
+
+    const reused = await useRefreshToken(store, first);
+    expect(reused).toEqual({
+      ok: false,
+      reason: "reused",
+      revokedSessions: ["session-1", "session-2"],
+    });
+    expect(await useRefreshToken(store, second)).toMatchObject({
+      ok: false,
+      reason: "invalid",
+    });
+  });
+
+  test("other families are unaffected by reuse", async () => {
+    const { store } = memoryStore();
+    const stolen = await issueRefreshToken(store, "user-1", "session-1");
+    const other = await issueRefreshToken(store, "user-1", "session-2");
+    await useRefreshToken(store, stolen);
+    await useRefreshToken(store, stolen);
+
+    expect(await useRefreshToken(store, other)).toMatchObject({ ok: true });
+  });
+
+  test("reject unknown and expired tokens", async () => {
+    const { store } = memoryStore();
+    expect(await useRefreshToken(store, "not-a-token")).toEqual({
+      ok: false,
+      reason: "invalid",
+      revokedSessions: [],
+    });
+
+    const issuedAt = new Date("2026-01-01T00:00:00Z");
+    const token = await issueRefreshToken(
+      store,
+      "user-1",
+      "session-1",
+      undefined,
+      issuedAt
+    );
+    const later = new Date("2027-01-01T00:00:00Z");
+    expect(await useRefreshToken(store, token, later)).toMatchObject({
+      ok: false,
+      reason: "expired",
+    });
+  });
+
+  test("sign-out revokes the chain", async () => {
+    const { store } = memoryStore();
+    const token = await issueRefreshToken(store, "user-1", "session-1");
+    expect(await revokeRefreshToken(store, token)).toEqual(["session-1"]);
+    expect(await useRefreshToken(store, token)).toMatchObject({
+      ok: false,
+      reason: "invalid",
+    });
+  });
+});
@@ -0,0 +1,126 @@
+import { createHash, randomBytes, randomUUID } from "node:crypto";
+import type { Collection } from "mongodb";
+import type { RefreshToken } from "../db/models";
+
+export const REFRESH_TOKEN_EXPIRES_IN = parseInt(
+  process.env.REFRESH_TOKEN_EXPIRES_IN || String(60 * 60 * 24 * 30)
+); // Seconds, 30 days
+
+/**
+ * Refresh tokens are only stored as their SHA-256, so a leaked collection
+ * can't be used to mint sessions.
+ */
+export function hashRefreshToken(token: string) {
+  return createHash("sha256").update(token).digest("hex");
+}
+
+export interface RefreshTokenStore {
+  insert(doc: RefreshToken): Promise<void>;
+  find(tokenHash: string): Promise<RefreshToken | null>;
+  // Atomically marks an unused token as used, null if it already was
+  markUsed(tokenHash: string, usedAt: Date): Promise<RefreshToken | null>;
+  // Revokes every token in the family, returning their session tokens
+  revokeFamily(family: string, revokedAt: Date): Promise<string[]>;
+}
+
+export function mongoRefreshTokenStore(
+  collection: Collection<RefreshToken>
+): RefreshTokenStore {
+  return {
+    insert: async (doc) => {
+      await collection.insertOne(doc);
+    },
+    find: (tokenHash) => collection.findOne({ tokenHash }),
+    markUsed: (tokenHash, usedAt) =>
+      collection.findOneAndUpdate(
+        { tokenHash, usedAt: null },
+        { $set: { usedAt } }
+      ),
+    revokeFamily: async (family, revokedAt) => {
+      await collection.updateMany(
+        { family, revokedAt: null },
+        { $set: { revokedAt } }
+      );
+      return collection.distinct("sessionToken", { family });
+    },
+  };
+}
+
+/**
+ * Issues a refresh token for a session. Pass the family of the token being
+ * rotated to continue its chain; a new sign-in starts a new family.
+ */
+export async function issueRefreshToken(
+  store: RefreshTokenStore,
+  userId: string,
+  sessionToken: string,
+  family: string = randomUUID(),
+  now = new Date()
+) {
+  const token = randomBytes(32).toString("base64url");
+  await store.insert({
+    tokenHash: hashRefreshToken(token),
+    family,
+    userId,
+    sessionToken,
+    createdAt: now,
+    expiresAt: new Date(now.getTime() + REFRESH_TOKEN_EXPIRES_IN * 1000),
+    usedAt: null,
+    revokedAt: null,
+  });
+  return token;
+}
+
+export type RefreshResult =
+  | { ok: true; userId: string; family: string; sessionToken: string }
+  | {
+      ok: false;
+      reason: "invalid" | "expired" | "reused";
+      revokedSessions: string[]; // Sessions to end along with the chain
+    };
+
+/**
+ * Exchanges a refresh token, which can only succeed once. Presenting a
+ * token that was already exchanged means it was copied, so the whole
+ * family is revoked and both the thief and the owner have to sign in again.
+ */
+export async function useRefreshToken(
+  store: RefreshTokenStore,
+  token: string,
+  now = new Date()
+): Promise<RefreshResult> {
+  const tokenHash = hashRefreshToken(token);
+  const doc = await store.markUsed(tokenHash, now);
+
+  if (!doc) {
+    const used = await store.find(tokenHash);
+    if (!used) return { ok: false, reason: "invalid", revokedSessions: [] };
+    const revokedSessions = await store.revokeFamily(used.family, now);
+    return { ok: false, reason: "reused", revokedSessions };
+  }
+  if (doc.revokedAt) {
+    return { ok: false, reason: "invalid", revokedSessions: [] };
+  }
+  if (doc.expiresAt <= now) {
+    return { ok: false, reason: "expired", revokedSessions: [] };
+  }
+  return {
+    ok: true,
+    userId: doc.userId,
+    family: doc.family,
+    sessionToken: doc.sessionToken,
+  };
+}
+
+/**
+ * Revokes the chain a refresh token belongs to, e.g. on sign-out.
+ * Returns the session tokens to end, empty for an unknown token.
+ */
+export async function revokeRefreshToken(
+  store: RefreshTokenStore,
+  token: string,
+  now = new Date()
+) {
+  const doc = await store.find(hashRefreshToken(token));
+  return doc ? store.revokeFamily(doc.family, now) : [];
+}
@@ -0,0 +1,26 @@
+import { describe, expect, test } from "bun:test";
+import { withRequestTimeout } from "./request-timeout";
+
+const never = new Promise<Response>(() => {});
+
+describe("withRequestTimeout", () => {
+  test("answers a slow handler with a 503", async () => {
+    const res = await withRequestTimeout(never, 10);
+    expect(res.status).toBe(503);
+    expect(res.headers.get("retry-after")).toBe("5");
+    expect((await res.json()).error.code).toBe("request_timeout");
+  });
+
+  test("passes a response through in time", async () => {
+    const res = await withRequestTimeout(
+      Promise.resolve(new Response("ok")),
+      1000
+    );
+    expect(res.status).toBe(200);
+    expect(await res.text()).toBe("ok");
+  });
+
+  test("is disabled by a timeout of 0", () => {
+    expect(withRequestTimeout(never, 0)).toBe(never);
+  });
+});
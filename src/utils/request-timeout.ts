@@ -0,0 +1,34 @@
+// How long a handler may take to produce a response, 0 disables the limit
+export const REQUEST_TIMEOUT_MS = parseInt(
+  process.env.REQUEST_TIMEOUT_MS || "10000"
+);
+
+const timeoutResponse = () =>
+  Response.json(
+    {
+      error: {
+        code: "request_timeout",
+        message: "The server took too long to respond, try again later",
+      },
+    },
+    { status: 503, headers: { "Retry-After": "5" } }
+  );
+
+/**
+ * Resolves with a 503 if `response` isn't ready within `timeoutMs`.
+ * The handler can't be interrupted, so it keeps running and its result is
+ * discarded; the client just isn't left waiting on it. Only the time to the
+ * response's headers counts, streamed bodies aren't cut off.
+ */
+export function withRequestTimeout(
+  response: Promise<Response>,
+  timeoutMs = REQUEST_TIMEOUT_MS
+): Promise<Response> {
+  if (timeoutMs <= 0) return response;
+
+  let timer: NodeJS.Timeout | undefined;
+  const timeout = new Promise<Response>((resolve) => {
+    timer = setTimeout(() => resolve(timeoutResponse()), timeoutMs);
+  });
+  return Promise.race([response, timeout]).finally(() => clearTimeout(timer));
+}
@@ -0,0 +1,49 @@
+import { describe, expect, test } from "bun:test";
+import { diagnoseSession, readSessionToken } from "./session-errors";
+
+const EXPIRED = new Date("2020-01-01T00:00:00Z");
+
+// Only "expired-token" is stored, and it has expired
+const findSession = async (token: string) =>
+  token === "expired-token" ? { expiresAt: EXPIRED } : null;
+
+const withCookie = (value: string) =>
+  new Headers({ cookie: `theme=dark; better-auth.session_token=${value}` });
+
+describe("diagnoseSession", () => {
+  test("tells an expired session from a malformed one", async () => {
+    expect(
+      await diagnoseSession(withCookie("expired-token.sig"), findSession)
+    ).toBe("session_expired");
+    expect(await diagnoseSession(withCookie("garbage"), findSession)).toBe(
+      "invalid_session"
+    );
+  });
+
+  test("reports a request without a session as missing", async () => {
+    expect(await diagnoseSession(new Headers(), findSession)).toBe(
+      "missing_session"
+    );
+  });
+
+  test("treats a failed lookup as invalid", async () => {
+    const failing = async () => {
+      throw new Error("db is down");
+    };
+    expect(await diagnoseSession(withCookie("expired-token"), failing)).toBe(
+      "invalid_session"
+    );
+  });
+});
+
+describe("readSessionToken", () => {
+  test("reads bearer tokens and cookies without the signature", () => {
+    const bearer = new Headers({ authorization: "Bearer abc.def" });
+    expect(readSessionToken(bearer)).toBe("abc");
+    expect(readSessionToken(withCookie("abc%2Edef.sig"))).toBe("abc.def");
+    const secure = new Headers({
+      cookie: "__Secure-better-auth.session_token=abc.sig",
+    });
+    expect(readSessionToken(secure)).toBe("abc");
+  });
+});
@@ -0,0 +1,62 @@
+import { connectToDatabase } from "../db/connect";
+
+// better-auth adds the __Secure- prefix when cookies are served over HTTPS
+const SESSION_COOKIES = [
+  "better-auth.session_token",
+  "__Secure-better-auth.session_token",
+];
+
+export type SessionFailure =
+  | "missing_session"
+  | "session_expired"
+  | "invalid_session";
+
+// Both the cookie and a bearer token hold "<token>.<signature>"
+function stripSignature(value: string): string {
+  const dot = value.lastIndexOf(".");
+  return dot > 0 ? value.slice(0, dot) : value;
+}
+
+// The session token sent as a bearer token or cookie, without its signature
+export function readSessionToken(headers: Headers): string | null {
+  const bearer = headers.get("authorization")?.match(/^Bearer\s+(.+)$/i);
+  if (bearer) return stripSignature(decodeURIComponent(bearer[1]));
+
+  const cookie = headers.get("cookie");
+  if (!cookie) return null;
+
+  for (const part of cookie.split(";")) {
+    const [name, ...rest] = part.trim().split("=");
+    if (!SESSION_COOKIES.includes(name)) continue;
+    return stripSignature(decodeURIComponent(rest.join("=")));
+  }
+  return null;
+}
+
+type SessionLookup = (token: string) => Promise<{ expiresAt: Date } | null>;
+
+async function findStoredSession(token: string) {
+  const { db } = await connectToDatabase();
+  return db
+    .collection<{ token: string; expiresAt: Date }>("session")
+    .findOne({ token }, { projection: { expiresAt: 1 } });
+}
+
+/**
+ * Works out why a request has no valid session, so clients can tell an
+ * expired session (log in again silently) from a missing or bad cookie.
+ * Only used to pick an error code, never to authenticate.
+ */
+export async function diagnoseSession(
+  headers: Headers,
+  findSession: SessionLookup = findStoredSession
+): Promise<SessionFailure> {
+  const token = readSessionToken(headers);
+  if (!token) return "missing_session";
+
+  const session = await findSession(token).catch(() => null);
+  if (session && new Date(session.expiresAt) <= new Date()) {
+    return "session_expired";
+  }
+  return "invalid_session";
+}
@@ -0,0 +1,31 @@
+import { describe, expect, test } from "bun:test";
+import { Elysia } from "elysia";
+import { ip } from "elysia-ip";
+import { SIGNUPS_PER_IP, signupRateLimit } from "./signup-limit";
+
+const app = new Elysia()
+  .use(ip())
+  .use(signupRateLimit)
+  .post("/auth/api/sign-up/email", () => "created")
+  .post("/auth/api/sign-in/email", () => "signed in");
+
+const post = (path: string, from: string) =>
+  app.handle(
+    new Request(`http://localhost/auth/api/${path}`, {
+      method: "POST",
+      headers: { "x-forwarded-for": from },
+    })
+  );
+
+describe("signupRateLimit", () => {
+  test("refuses sign-ups past the limit for one IP", async () => {
+    for (let i = 0; i < SIGNUPS_PER_IP; i++) {
+      expect((await post("sign-up/email", "203.0.113.1")).status).toBe(200);
+    }
+    expect((await post("sign-up/email", "203.0.113.1")).status).toBe(429);
+
+    // Other addresses and other auth routes aren't affected
+    expect((await post("sign-up/email", "203.0.113.2")).status).toBe(200);
+    expect((await post("sign-in/email", "203.0.113.1")).status).toBe(200);
+  });
+});
@@ -0,0 +1,44 @@
+import { rateLimit } from "elysia-rate-limit";
+
+const SIGNUP_PATHS = ["/auth/api/sign-up/email"];
+const SIGNUP_WINDOW_MS = 60 * 60 * 1000; // 1 hour
+export const SIGNUPS_PER_IP = parseInt(process.env.SIGNUP_LIMIT_PER_IP || "5"); // Per window
+const SIGNUP_SPIKE_THRESHOLD = parseInt(
+  process.env.SIGNUP_SPIKE_THRESHOLD || "50"
+); // Site-wide sign-ups per window before warning
+const SPIKE_WARNING_INTERVAL_MS = 10 * 60 * 1000;
+
+/**
+ * Limits how many accounts a single IP can register per hour.
+ */
+export const signupRateLimit = rateLimit({
+  duration: SIGNUP_WINDOW_MS,
+  max: SIGNUPS_PER_IP,
+  skip: (req) =>
+    req.method !== "POST" ||
+    !SIGNUP_PATHS.includes(new URL(req.url).pathname),
+  errorResponse: "Too many accounts created from this IP. Try again later.",
+  generator: (req, server, { ip }) => ip, // Custom generator as getting IP on BunJS is different
+});
+
+const recentSignups: number[] = [];
+let lastSpikeWarning = 0;
+
+/**
+ * Tracks sign-ups site-wide and logs a warning when they spike, which can
+ * mean bots are spreading registrations across many IPs.
+ */
+export function recordSignup(now = Date.now()) {
+  recentSignups.push(now);
+  while (recentSignups[0] < now - SIGNUP_WINDOW_MS) recentSignups.shift();
+
+  if (
+    recentSignups.length > SIGNUP_SPIKE_THRESHOLD &&
+    now - lastSpikeWarning > SPIKE_WARNING_INTERVAL_MS
+  ) {
+    lastSpikeWarning = now;
+    console.warn(
+      `Sign-up spike: ${recentSignups.length} accounts created in the last hour`
+    );
+  }
+}
@@ -0,0 +1,38 @@
+import { describe, expect, test } from "bun:test";
+import { generateSnippet, stripMarkdown } from "./snippet";
+
+describe("generateSnippet", () => {
+  test("keeps short content whole, without an ellipsis", () => {
+    expect(generateSnippet("# Hello\n\nA **short** post.")).toBe(
+      "Hello A short post."
+    );
+  });
+
+  test("cuts long content at a word boundary", () => {
+    const snippet = generateSnippet("alpha beta gamma delta", 15);
+    expect(snippet).toBe("alpha beta…");
+    expect(snippet.length).toBeLessThanOrEqual(15);
+  });
+
+  test("drops trailing punctuation before the ellipsis", () => {
+    expect(generateSnippet("one two, three four", 10)).toBe("one two…");
+  });
+
+  test("splits a single long word when it has to", () => {
+    expect(generateSnippet("abcdefghijklmnop", 8)).toBe("abcdefg…");
+  });
+});
+
+describe("stripMarkdown", () => {
+  test("keeps link text and drops code, images and HTML", () => {
+    const markdown = [
+      "Read [the docs](https://example.com) first.",
+      "![diagram](d.png) <b>bold</b>",
+      "```ts\nconst x = 1;\n```",
+      "- `inline` item",
+    ].join("\n");
+    expect(stripMarkdown(markdown)).toBe(
+      "Read the docs first. bold inline item"
+    );
+  });
+});
@@ -0,0 +1,35 @@
+export const SNIPPET_LENGTH = 160;
+
+/**
+ * Plain text of a markdown document: code blocks, images and HTML are
+ * dropped, links keep their text and formatting marks are removed.
+ */
+export function stripMarkdown(markdown: string): string {
+  return markdown
+    .replace(/```[\s\S]*?```/g, " ")
+    .replace(/!\[[^\]]*\]\([^)]*\)/g, " ")
+    .replace(/\[([^\]]*)\]\([^)]*\)/g, "$1")
+    .replace(/<[^>]*>/g, " ")
+    .replace(/^\s{0,3}(#{1,6}|>|[-*+]|\d+\.)\s+/gm, "")
+    .replace(/[`*_~]/g, "")
+    .replace(/\s+/g, " ")
+    .trim();
+}
+
+/**
+ * A post summary from its markdown: the first `maxLength` characters of
+ * the plain text, cut at a word boundary with an ellipsis when shortened.
+ */
+export function generateSnippet(
+  markdown: string,
+  maxLength = SNIPPET_LENGTH
+): string {
+  const text = stripMarkdown(markdown);
+  if (text.length <= maxLength) return text;
+
+  // Leave room for the ellipsis, and don't split a word unless it's the
+  // only one
+  const cut = text.lastIndexOf(" ", maxLength - 1);
+  const truncated = text.slice(0, cut > 0 ? cut : maxLength - 1);
+  return `${truncated.replace(/[\s.,;:!?-]+$/, "")}…`;
+}
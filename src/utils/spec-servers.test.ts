@@ -0,0 +1,22 @@
+import { describe, expect, test } from "bun:test";
+import { specServers } from "./spec-servers";
+
+describe("specServers", () => {
+  test("declares PUBLIC_BASE_URL as the only server", () => {
+    expect(specServers("https://api.example.com")).toEqual([
+      { url: "https://api.example.com" },
+    ]);
+  });
+
+  test("drops trailing slashes so paths join cleanly", () => {
+    expect(specServers("https://api.example.com/base//")).toEqual([
+      { url: "https://api.example.com/base" },
+    ]);
+  });
+
+  test("declares none when unset or blank", () => {
+    expect(specServers(undefined)).toBeUndefined();
+    expect(specServers("")).toBeUndefined();
+    expect(specServers(" / ")).toBeUndefined();
+  });
+});
@@ -0,0 +1,12 @@
+/**
+ * The `servers` of the OpenAPI specs, from the deployment's public origin
+ * (e.g. https://api.example.com) so the docs UI's "Try it out" calls the
+ * real host. Without one no server is declared and the UI uses whatever
+ * host served it.
+ */
+export function specServers(
+  publicBaseUrl = process.env.PUBLIC_BASE_URL
+): { url: string }[] | undefined {
+  const url = publicBaseUrl?.trim().replace(/\/+$/, "");
+  return url ? [{ url }] : undefined;
+}
@@ -0,0 +1,36 @@
+import { describe, expect, test } from "bun:test";
+import { claimsMatch, tokenClaims } from "./token-audience";
+
+const EXPECTED = { issuer: "https://my-website.space", audience: "web" };
+
+describe("token claims", () => {
+  test("are recorded from the configured values", () => {
+    expect(tokenClaims(EXPECTED)).toEqual(EXPECTED);
+    expect(tokenClaims({ issuer: null, audience: "web" })).toEqual({
+      audience: "web",
+    });
+    expect(tokenClaims({ issuer: null, audience: null })).toEqual({});
+  });
+
+  test("accept a matching issuer and audience", () => {
+    expect(claimsMatch(tokenClaims(EXPECTED), EXPECTED)).toBe(true);
+  });
+
+  test("reject a session minted for another audience", () => {
+    expect(claimsMatch({ ...EXPECTED, audience: "admin" }, EXPECTED)).toBe(
+      false
+    );
+    expect(claimsMatch({ issuer: EXPECTED.issuer }, EXPECTED)).toBe(false);
+  });
+
+  test("reject a session from another issuer", () => {
+    const foreign = { issuer: "https://other.example", audience: "web" };
+    expect(claimsMatch(foreign, EXPECTED)).toBe(false);
+  });
+
+  test("accept anything when nothing is configured", () => {
+    const lenient = { issuer: null, audience: null };
+    expect(claimsMatch({}, lenient)).toBe(true);
+    expect(claimsMatch({ audience: "other" }, lenient)).toBe(true);
+  });
+});
@@ -0,0 +1,41 @@
+/**
+ * Issuer and audience recorded on every session this deployment creates.
+ * A session created by another app sharing the database or secret carries
+ * different values (or none) and is rejected. Both are unset by default,
+ * which accepts any session, so dev setups need no configuration.
+ */
+export const AUTH_ISSUER = process.env.AUTH_ISSUER || null;
+export const AUTH_AUDIENCE = process.env.AUTH_AUDIENCE || null;
+
+export interface TokenClaims {
+  issuer?: string | null;
+  audience?: string | null;
+}
+
+const EXPECTED_CLAIMS: TokenClaims = {
+  issuer: AUTH_ISSUER,
+  audience: AUTH_AUDIENCE,
+};
+
+export const CHECKS_TOKEN_CLAIMS = !!(AUTH_ISSUER || AUTH_AUDIENCE);
+
+/**
+ * Claims to store on a new session.
+ */
+export function tokenClaims(expected = EXPECTED_CLAIMS): TokenClaims {
+  return {
+    ...(expected.issuer && { issuer: expected.issuer }),
+    ...(expected.audience && { audience: expected.audience }),
+  };
+}
+
+/**
+ * Whether a session's claims match this deployment. Only configured
+ * claims are compared, and a configured claim must be present.
+ */
+export function claimsMatch(claims: TokenClaims, expected = EXPECTED_CLAIMS) {
+  return (
+    (!expected.issuer || claims.issuer === expected.issuer) &&
+    (!expected.audience || claims.audience === expected.audience)
+  );
+}
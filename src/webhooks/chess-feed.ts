@@ -0,0 +1,53 @@
+import { Socket, Namespace } from "socket.io";
+import { auth } from "../auth";
+import { rejectMalformedMessages, handshakeHeaders } from "./index";
+
+// Set CHESS_FEED_ADMIN_ONLY=true to restrict the feed to admins
+const CHESS_FEED_ADMIN_ONLY = process.env.CHESS_FEED_ADMIN_ONLY === "true";
+
+/**
+ * Reference to the /sockets/chess-feed namespace, set once it is set up.
+ */
+let feedNamespace: Namespace | undefined;
+
+async function isAdmin(socket: Socket) {
+  const session = await auth.api
+    .getSession({ headers: handshakeHeaders(socket) })
+    .catch(() => null);
+  return !!session?.user.role?.includes("admin");
+}
+
+/**
+ * Sets up the /sockets/chess-feed namespace, a read-only stream of every
+ * move played in public live games (see `broadcastChessMove`). Clients
+ * send nothing; Socket.IO forgets a socket as soon as it disconnects, so
+ * there is nothing to clean up.
+ */
+export function setupChessFeed(nsp: Namespace) {
+  feedNamespace = nsp;
+
+  // Checked before the socket joins, so no move reaches it while the
+  // session lookup is pending. Clients get a `connect_error`.
+  if (CHESS_FEED_ADMIN_ONLY) {
+    nsp.use(async (socket, next) => {
+      if (await isAdmin(socket)) next();
+      else next(new Error("Admin access required"));
+    });
+  }
+
+  nsp.on("connection", (socket: Socket) => {
+    rejectMalformedMessages(socket);
+  });
+}
+
+/**
+ * Sends a move to every feed client. Only the move itself is sent, never
+ * the board, clocks or players.
+ */
+export function broadcastChessMove(move: {
+  gameId: string;
+  ply: number;
+  san: string;
+}) {
+  feedNamespace?.emit("move", move);
+}
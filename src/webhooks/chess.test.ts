@@ -0,0 +1,367 @@
+import {
+  afterEach,
+  beforeAll,
+  expect,
+  setSystemTime,
+  test,
+} from "bun:test";
+import { ObjectId, type Db } from "mongodb";
+import type { Namespace } from "socket.io";
+import {
+  TestUser,
+  createTestUser,
+  describeWithDb,
+  resetTestDatabase,
+} from "../test-utils/db";
+import {
+  ConnectOptions,
+  FakeNamespace,
+  FakeSocket,
+} from "../test-utils/sockets";
+
+// Far beyond the idle timeout, as a `now` for the sweeper
+const MUCH_LATER = () => Date.now() + 24 * 60 * 60 * 1000;
+
+describeWithDb("chess sockets", () => {
+  let db: Db;
+  let chess: typeof import("./chess");
+  let nsp: FakeNamespace;
+  let alice: TestUser;
+  let bob: TestUser;
+  let carol: TestUser;
+
+  beforeAll(async () => {
+    db = await resetTestDatabase();
+    chess = await import("./chess");
+    nsp = new FakeNamespace("/sockets/chess");
+    chess.setupChess(nsp as unknown as Namespace);
+    alice = await createTestUser("alice");
+    bob = await createTestUser("bob");
+    carol = await createTestUser("carol");
+  });
+
+  afterEach(() => {
+    chess.shutdownChess();
+    nsp.disconnectAll();
+    setSystemTime(); // Back to the real clock
+  });
+
+  const connect = (
+    user: TestUser,
+    query: ConnectOptions["query"] = {}
+  ) => nsp.connect({ auth: { token: user.token }, query });
+
+  // Pairs alice (white) with bob (black) through the queue and starts play
+  const playHumanGame = async () => {
+    const white = await connect(alice, { color: "white" });
+    const black = await connect(bob, { color: "black" });
+    await white.send("bid", { time: 300 });
+    await black.send("bid", { time: 300 });
+    return { white, black };
+  };
+
+  const play = async (socket: FakeSocket, ...moves: string[]) => {
+    for (const move of moves) await socket.send("move", { move });
+  };
+
+  const rated = async (chessRating: number) => {
+    const user = await createTestUser();
+    await db
+      .collection("user")
+      .updateOne({ _id: new ObjectId(user.id) }, { $set: { chessRating } });
+    return user;
+  };
+
+  test("an idle single-player game is reaped after the timeout", async () => {
+    const socket = await connect(alice, { vs: "bot" });
+    const gameId = socket.data.gameId;
+    await socket.send("resign");
+
+    // A running clock ends a game on its own, a finished one doesn't
+    chess.sweepChessGames(MUCH_LATER());
+    expect(socket.last("game_abandoned")).toEqual({
+      gameId,
+      reason: "idle timeout",
+    });
+    expect(socket.data.gameId).toBeNull();
+  });
+
+  test("a game whose only player vanished is reaped", async () => {
+    const socket = await connect(alice, { vs: "bot", color: "white" });
+    expect(chess.listUserGames(alice.id)).toHaveLength(1);
+
+    // The connection died without a disconnect event
+    socket.connected = false;
+    chess.sweepChessGames();
+    expect(chess.listUserGames(alice.id)).toEqual([]);
+  });
+
+  test("a running clock isn't mistaken for an idle game", async () => {
+    const socket = await connect(alice, { vs: "bot", color: "white" });
+    chess.sweepChessGames(MUCH_LATER());
+    expect(socket.received("game_abandoned")).toEqual([]);
+    expect(chess.listUserGames(alice.id)).toHaveLength(1);
+  });
+
+  test("invite codes only pair the players presenting them", async () => {
+    const { code } = chess.createInvite(alice.id);
+    expect(code).toMatch(/^[A-HJKMNP-Z2-9]{8}$/);
+
+    const host = await connect(alice, { invite: code });
+    expect(host.last("waiting")).toEqual({ invite: code });
+    const stranger = await connect(carol);
+    expect(stranger.received("paired")).toEqual([]);
+
+    // Codes aren't case-sensitive
+    const guest = await connect(bob, { invite: code.toLowerCase() });
+    expect(host.last("paired")).toEqual({ opponent: bob.id });
+    expect(guest.last("paired")).toEqual({ opponent: alice.id });
+
+    expect(stranger.received("paired")).toEqual([]);
+    expect(chess.listOpenGames().map((p) => p.userId)).toEqual([carol.id]);
+  });
+
+  test("an unknown invite code is refused", async () => {
+    const socket = await connect(bob, { invite: "NOPE2345" });
+    expect(socket.last("error")).toEqual({
+      message: "Invite not found or expired",
+    });
+    expect(socket.connected).toBe(false);
+  });
+
+  test("the matchmaker pairs the closest ratings first", async () => {
+    // Too far apart to pair as soon as they queue
+    const low = await connect(await rated(1500));
+    const middle = await connect(await rated(1700));
+    const high = await connect(await rated(2000));
+    chess.matchWaitingPlayers();
+    expect(chess.listOpenGames()).toHaveLength(3);
+
+    // After 40s the bands have widened to 300, enough for either pair
+    chess.matchWaitingPlayers(Date.now() + 40_000);
+    expect(low.last("paired")).toEqual({ opponent: middle.data.userId });
+    expect(middle.last("paired")).toEqual({ opponent: low.data.userId });
+    expect(high.received("paired")).toEqual([]);
+    expect(chess.listOpenGames()).toHaveLength(1);
+  });
+
+  test("a takeback of your own move undoes one ply", async () => {
+    const { white, black } = await playHumanGame();
+    await play(white, "e4");
+    await play(black, "e5");
+    const afterE5 = white.last("update").fen;
+    await play(white, "Nf3");
+
+    await white.send("takeback_request");
+    expect(black.last("takeback_requested")).toEqual({ from: "white" });
+    await black.send("takeback_respond", { accept: true });
+
+    expect(white.last("takeback_accepted")).toEqual({ plies: 1 });
+    expect(black.last("update").fen).toBe(afterE5);
+    expect(afterE5.split(" ")[1]).toBe("w");
+  });
+
+  test("a takeback after the reply undoes both plies", async () => {
+    const { white, black } = await playHumanGame();
+    await play(white, "e4");
+    const afterE4 = white.last("update").fen;
+    await play(black, "e5");
+    await play(white, "Nf3");
+
+    // Black asks with white's reply on the board, so both moves go
+    await black.send("takeback_request");
+    await white.send("takeback_respond", { accept: true });
+
+    expect(black.last("takeback_accepted")).toEqual({ plies: 2 });
+    expect(black.last("update").fen).toBe(afterE4);
+    expect(afterE4.split(" ")[1]).toBe("b");
+
+    // Black is to move again and can play something else
+    await play(black, "c5");
+    expect(black.last("error")).toBeUndefined();
+  });
+
+  test("each move records how long its player thought", async () => {
+    const start = Date.parse("2024-01-01T12:00:00Z");
+    setSystemTime(start);
+    const { white, black } = await playHumanGame();
+
+    setSystemTime(start + 1500);
+    await play(white, "e4");
+    expect(black.last("update").moveTime).toEqual({
+      thinkMs: 1500,
+      clock: 300,
+    });
+
+    // Black's turn started when white moved
+    setSystemTime(start + 1500 + 4200);
+    await play(black, "e5");
+    expect(white.last("update").moveTime).toEqual({
+      thinkMs: 4200,
+      clock: 300,
+    });
+  });
+
+  test("a rematch starts once both players ask, colours swapped", async () => {
+    const { white, black } = await playHumanGame();
+    const firstGame = white.data.gameId;
+    await white.send("rematch");
+    expect(white.last("error")).toEqual({ message: "Game has not ended" });
+
+    await white.send("resign");
+    await white.send("rematch");
+    expect(white.received("rematch_request_sent")).toHaveLength(1);
+    expect(black.last("rematch_requested")).toEqual({ from: "white" });
+    expect(black.received("rematch_start")).toEqual([]);
+
+    await black.send("rematch");
+    const { gameId } = black.last("rematch_start");
+    expect(gameId).not.toBe(firstGame);
+    expect(black.last("rematch_start")).toEqual({
+      gameId,
+      your_color: "white",
+      opponent: alice.id,
+    });
+    expect(white.last("rematch_start")).toEqual({
+      gameId,
+      your_color: "black",
+      opponent: bob.id,
+    });
+
+    // Bob opens the new game as white
+    await white.send("bid", { time: 300 });
+    await black.send("bid", { time: 300 });
+    await play(black, "e4");
+    expect(white.last("update").fen.split(" ")[1]).toBe("b");
+  });
+
+  test("a player who reconnects in time resumes their game", async () => {
+    const { white, black } = await playHumanGame();
+    await play(white, "e4");
+
+    white.drop();
+    expect(black.last("opponent_disconnected")).toMatchObject({
+      reconnectTimeout: expect.any(Number),
+    });
+
+    const returned = await connect(alice);
+    expect(returned.last("init")).toMatchObject({
+      gameId: white.data.gameId,
+      phase: "playing",
+      history: ["e4"],
+      your_color: "white",
+      opponent: bob.id,
+    });
+    expect(black.last("reconnected")).toEqual({ color: "white" });
+
+    // The game carries on with the new socket
+    await play(black, "e5");
+    await play(returned, "Nf3");
+    expect(black.last("update").fen.split(" ")[1]).toBe("b");
+    expect(black.received("win")).toEqual([]);
+  });
+
+  test("chat from white reaches black, within the rate limit", async () => {
+    const { white, black } = await playHumanGame();
+    await white.send("chat", { text: "  good luck  " });
+    expect(black.received("chat")).toEqual([
+      { from: "white", userId: alice.id, username: "alice", text: "good luck" },
+    ]);
+    expect(white.received("chat")).toEqual([]);
+
+    // Four more fill the window, the sixth is refused
+    for (let i = 0; i < 5; i++) await white.send("chat", { text: `${i}` });
+    expect(black.received("chat")).toHaveLength(5);
+    expect(white.last("error")).toEqual({
+      message: "You are sending messages too fast",
+    });
+  });
+
+  test("a ping timeout cleans up like any other disconnect", async () => {
+    const queued = await connect(carol);
+    expect(chess.listOpenGames().map((p) => p.userId)).toEqual([carol.id]);
+    // What Socket.IO reports when the heartbeat goes unanswered
+    queued.drop("ping timeout");
+    expect(chess.listOpenGames()).toEqual([]);
+
+    const { white, black } = await playHumanGame();
+    white.drop("ping timeout");
+    expect(black.last("opponent_disconnected")).toMatchObject({
+      message: "Opponent disconnected, waiting for them to reconnect",
+    });
+  });
+
+  test("a user's second tab never pairs with their first", async () => {
+    const first = await connect(alice);
+    const second = await connect(alice);
+    expect(second.last("error")).toMatchObject({ code: "game_limit" });
+    expect(second.connected).toBe(false);
+    expect(first.received("paired")).toEqual([]);
+    expect(chess.listOpenGames().map((p) => p.userId)).toEqual([alice.id]);
+
+    const opponent = await connect(bob);
+    expect(first.last("paired")).toEqual({ opponent: bob.id });
+    expect(opponent.last("paired")).toEqual({ opponent: alice.id });
+  });
+
+  test("a game can be aborted until the first move", async () => {
+    const { white, black } = await playHumanGame();
+    await black.send("abort");
+    expect(white.last("aborted")).toEqual({ by: "black" });
+    expect(chess.listUserGames(alice.id)).toEqual([]);
+
+    const next = await playHumanGame();
+    await play(next.white, "e4");
+    await next.black.send("abort");
+    expect(next.black.last("error")).toEqual({
+      message: "Game can't be aborted after the first move, resign instead",
+    });
+    expect(next.white.received("aborted")).toEqual([]);
+  });
+
+  test("a second simultaneous game is refused", async () => {
+    const { white, black } = await playHumanGame();
+    const gameId = white.data.gameId;
+
+    // Even against the engine, which never queues
+    const extra = await connect(alice, { vs: "bot" });
+    expect(extra.last("error")).toEqual({
+      code: "game_limit",
+      message: "You are already in a chess game or waiting for one",
+    });
+    expect(extra.connected).toBe(false);
+
+    expect(chess.listUserGames(alice.id).map((g) => g.gameId)).toEqual([
+      gameId,
+    ]);
+    await play(white, "e4");
+    expect(black.last("update").fen.split(" ")[1]).toBe("b");
+  });
+
+  test("public moves reach the feed, private ones don't", async () => {
+    const feed = new FakeNamespace("/sockets/chess-feed");
+    const { setupChessFeed } = await import("./chess-feed");
+    setupChessFeed(feed as unknown as Namespace);
+    const watcher = await feed.connect();
+
+    const { white, black } = await playHumanGame();
+    await play(white, "e4");
+    await play(black, "e5");
+    expect(watcher.received("move")).toEqual([
+      { gameId: white.data.gameId, ply: 1, san: "e4" },
+      { gameId: white.data.gameId, ply: 2, san: "e5" },
+    ]);
+    await white.send("resign");
+
+    const { code } = chess.createInvite(alice.id);
+    const host = await connect(alice, { invite: code });
+    const guest = await connect(bob, { invite: code });
+    await host.send("bid", { time: 300 });
+    await guest.send("bid", { time: 300 });
+    // Invite games give out colours at random
+    const first = host.last("start").your_color === "white" ? host : guest;
+    await play(first, "d4");
+    expect(first.last("update").fen.split(" ")[1]).toBe("b");
+    expect(watcher.received("move")).toHaveLength(2);
+  });
+});
@@ -1,14 +1,58 @@
+import { randomInt } from "node:crypto";
 import { Socket, Namespace } from "socket.io";
 import { Chess } from "chess.js";
 import { auth } from "../auth"; // Better-auth instance
-import { updateUserStats } from "../utils/profile";
+import {
+  updateUserStats,
+  updateChessRatings,
+  getChessRating,
+  DEFAULT_CHESS_RATING,
+} from "../utils/profile";
 import { sendNotification } from "../utils/notifications";
+import {
+  resolveMove,
+  tryMove,
+  MoveInput,
+  getLegalMoves,
+  getDrawReason,
+  getClaimableDraw,
+  getPositionStatus,
+  isGameFinished,
+  replayMoves,
+  resolveStartPosition,
+  StartPosition,
+  STANDARD_START,
+  ColorPreference,
+  COLOR_PREFERENCES,
+  assignColors,
+} from "../utils/chess";
+import {
+  findBestMove,
+  BOT_DIFFICULTY_DEPTH,
+  BotDifficulty,
+} from "../utils/chess-engine";
+import { registerGauge } from "../utils/metrics";
+import { saveChessGame } from "../utils/chess-history";
+import { classifyOpening } from "../utils/chess-openings";
+import { ChessMoveTime } from "../db/models";
+import { broadcastChessMove } from "./chess-feed";
+import {
+  rejectMalformedMessages,
+  handshakeHeaders,
+  hasCredentials,
+} from "./index";
 
 interface Player {
   socket: Socket;
   username: string;
   userId: string; // Better-auth user ID
   bid?: number; // Time bid in seconds
+  disconnectTimeout?: NodeJS.Timeout; // Forfeits the game if the player doesn't reconnect
+  isBot?: boolean; // Server-side engine opponent
+  botDepth?: number; // Search depth used by the engine
+  colorPreference?: ColorPreference; // Requested color when matchmaking
+  rating?: number; // Elo rating when they joined the queue
+  queuedAt?: number; // When they started waiting in the queue
 }
 
 interface Game {
@@ -23,17 +67,803 @@ interface Game {
   biddingTimerInterval?: NodeJS.Timeout; // Bidding timer interval
   biddingTimeLeft: number; // Remaining bidding time in seconds
   drawOfferFrom?: "white" | "black" | null; // Track pending draw offer
+  takebackFrom?: "white" | "black" | null; // Pending takeback request
+  rematchRequestedBy: Set<"white" | "black">; // Players asking for a rematch
+  rematchTimeout?: NodeJS.Timeout; // Removes the finished game when the window closes
+  spectators: Map<string, Socket>; // Read-only viewers, keyed by socket ID
+  isPrivate?: boolean; // Created from an invite code, kept out of the public queue
+  lastActivity: number; // Timestamp of the last move, bid or message
+  startedAt?: Date; // When the playing phase began
+  timeControl?: number; // Seconds per side chosen by bidding
+  firstMoveMade?: boolean; // Once set the game can no longer be aborted
+  turnStartedAt?: number; // When the side to move started thinking
+  moveTimes: ChessMoveTime[]; // Think time and clock for each ply
+  start: StartPosition; // Variant and FEN the game started from
 }
 
 const MIN_BID = 60; // Minimum time in seconds
+const REMATCH_WINDOW_MS = 30_000; // How long a finished game stays open for a rematch
+const RECONNECT_GRACE_MS = parseInt(
+  process.env.CHESS_RECONNECT_GRACE_MS || "30000"
+); // How long a disconnected player has to come back
+const MAX_CHAT_LENGTH = 500; // Max characters per chat message
+const CHAT_RATE_LIMIT = 5; // Max chat messages per window, per socket
+const CHAT_RATE_WINDOW_MS = 10_000;
+const BOT_GAME_TIME = 600; // Seconds per side in games against the engine
+const BOT_MOVE_DELAY_MS = 500; // Pause before the engine replies
+const INVITE_TTL_MS = 10 * 60 * 1000; // Unused invite codes expire after 10 minutes
+// Codes get read out and typed in, so look-alikes (0/O, 1/I/L) are left out
+const INVITE_ALPHABET = "ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LENGTH = 8;
+const GAME_IDLE_TIMEOUT_MS = parseInt(
+  process.env.CHESS_GAME_IDLE_TIMEOUT_MS || String(30 * 60 * 1000)
+); // Games with no activity for this long are removed
+const SWEEP_INTERVAL_MS = parseInt(
+  process.env.CHESS_SWEEP_INTERVAL_MS || "60000"
+); // How often abandoned games are looked for
+const RATING_BAND_BASE = 100; // Rating gap accepted as soon as someone queues
+const RATING_BAND_GROWTH = 50; // Extra gap accepted per RATING_BAND_STEP_MS
+const RATING_BAND_STEP_MS = 10_000;
+const MATCH_INTERVAL_MS = 2000; // How often waiting players are re-matched
+const MAX_GAMES_PER_USER = parseInt(
+  process.env.CHESS_MAX_GAMES_PER_USER || "1"
+); // Games (including queue and invite waits) one account can be in at once
+const ABORT_WINDOW_MS = parseInt(
+  process.env.CHESS_ABORT_WINDOW_MS || "30000"
+); // Games with no move this long after starting are aborted
 
 const waitingQueue: Player[] = [];
 const activeGames: Map<string, Game> = new Map(); // Key: gameId
 
+interface Invite {
+  code: string;
+  createdBy: string; // User ID of whoever created the invite
+  expiresAt: number;
+  waiting?: Player; // First player to connect with the code
+  expiryTimeout: NodeJS.Timeout;
+  start: StartPosition; // Chosen by whoever created the invite
+}
+
+const invites: Map<string, Invite> = new Map(); // Key: invite code
+
+let chessNamespace: Namespace | undefined; // Set by setupChess
+
+registerGauge(
+  "chess_active_games",
+  "Chess games currently in progress or awaiting a rematch",
+  () => activeGames.size
+);
+registerGauge(
+  "chess_waiting_players",
+  "Players waiting in the public chess queue",
+  () => waitingQueue.length
+);
+
+/**
+ * Number of games currently being bid on or played.
+ */
+export function countActiveGames() {
+  let count = 0;
+  for (const game of activeGames.values()) {
+    if (game.phase !== "ended") count++;
+  }
+  return count;
+}
+
+/**
+ * Players waiting in the public queue for an opponent, longest-waiting first.
+ * Private invites are never listed.
+ */
+export function listOpenGames() {
+  return waitingQueue
+    .filter((player) => player.socket.connected)
+    .map((player) => ({
+      userId: player.userId,
+      username: player.username,
+      rating: player.rating ?? DEFAULT_CHESS_RATING,
+      colorPreference: player.colorPreference ?? "random",
+      waitingSince: new Date(player.queuedAt ?? Date.now()),
+    }));
+}
+
+type PresenceStatus = "playing" | "waiting" | "spectating" | "idle";
+
+// When a user has several sockets, the busiest one decides their status
+const PRESENCE_PRIORITY: PresenceStatus[] = [
+  "playing",
+  "waiting",
+  "spectating",
+  "idle",
+];
+
+/**
+ * Users connected to the chess socket and what they're doing. Sockets
+ * that stop answering heartbeats are disconnected by Socket.IO, so stale
+ * clients drop off on their own.
+ */
+export function listOnlinePlayers() {
+  const waiting = new Set(waitingQueue.map((p) => p.socket.id));
+  for (const invite of invites.values()) {
+    if (invite.waiting) waiting.add(invite.waiting.socket.id);
+  }
+  const spectating = new Set<string>();
+  for (const game of activeGames.values()) {
+    for (const id of game.spectators.keys()) spectating.add(id);
+  }
+
+  const online = new Map<
+    string,
+    { userId: string; username: string; status: PresenceStatus }
+  >();
+  for (const socket of chessNamespace?.sockets.values() ?? []) {
+    const { userId, username, gameId } = socket.data ?? {};
+    if (!userId || !socket.connected) continue; // Not authenticated yet
+
+    const game = gameId ? activeGames.get(gameId) : undefined;
+    const status: PresenceStatus =
+      game && game.phase !== "ended"
+        ? "playing"
+        : waiting.has(socket.id)
+        ? "waiting"
+        : spectating.has(socket.id)
+        ? "spectating"
+        : "idle";
+
+    const current = online.get(userId);
+    if (
+      !current ||
+      PRESENCE_PRIORITY.indexOf(status) <
+        PRESENCE_PRIORITY.indexOf(current.status)
+    ) {
+      online.set(userId, { userId, username, status });
+    }
+  }
+  return [...online.values()].sort((a, b) =>
+    a.username.localeCompare(b.username)
+  );
+}
+
+/**
+ * Unfinished games `userId` is playing in, for resuming after a page
+ * reload. Reconnecting to the chess socket puts them back in the game.
+ */
+export function listUserGames(userId: string) {
+  const games = [];
+  for (const game of activeGames.values()) {
+    if (game.phase === "ended") continue;
+    const color =
+      game.white.userId === userId
+        ? "white"
+        : game.black.userId === userId
+        ? "black"
+        : null;
+    if (!color) continue;
+
+    const opponent = game[color === "white" ? "black" : "white"];
+    games.push({
+      gameId: game.id,
+      color,
+      opponent: {
+        userId: opponent.userId,
+        username: opponent.username,
+        isBot: !!opponent.isBot,
+      },
+      phase: game.phase,
+      turn: game.chess.turn() === "w" ? "white" : "black",
+      fen: game.chess.fen(),
+      whiteTime: game.whiteTime,
+      blackTime: game.blackTime,
+      isPrivate: !!game.isPrivate,
+    });
+  }
+  return games;
+}
+
+/**
+ * An unguessable invite code that isn't already in use.
+ */
+function generateInviteCode(): string {
+  let code: string;
+  do {
+    code = Array.from(
+      { length: INVITE_CODE_LENGTH },
+      () => INVITE_ALPHABET[randomInt(INVITE_ALPHABET.length)]
+    ).join("");
+  } while (invites.has(code));
+  return code;
+}
+
+/**
+ * Creates a private game invite. Only players connecting with
+ * `?invite=<code>` are paired through it, never the public queue.
+ */
+export function createInvite(
+  userId: string,
+  start: StartPosition = STANDARD_START
+) {
+  const code = generateInviteCode();
+  const expiresAt = Date.now() + INVITE_TTL_MS;
+
+  const expiryTimeout = setTimeout(() => {
+    const invite = invites.get(code);
+    if (!invite) return;
+    invites.delete(code);
+    if (invite.waiting?.socket.connected) {
+      invite.waiting.socket.emit("error", { message: "Invite expired" });
+      invite.waiting.socket.disconnect();
+    }
+  }, INVITE_TTL_MS);
+
+  invites.set(code, {
+    code,
+    createdBy: userId,
+    expiresAt,
+    expiryTimeout,
+    start,
+  });
+  console.log(`Invite ${code} created by ${userId} (${start.variant})`);
+
+  return {
+    code,
+    expiresAt: new Date(expiresAt),
+    variant: start.variant,
+    fen: start.fen,
+  };
+}
+
 function generateGameId(): string {
   return Math.random().toString(36).substring(2, 15);
 }
 
+/**
+ * Creates a game in the bidding phase and starts the bidding timer.
+ */
+function createGame(
+  whitePlayer: Player,
+  blackPlayer: Player,
+  start: StartPosition = STANDARD_START
+): Game {
+  const gameId = generateGameId();
+  const game: Game = {
+    id: gameId,
+    white: whitePlayer,
+    black: blackPlayer,
+    chess: new Chess(start.fen),
+    start,
+    phase: "bidding",
+    whiteTime: 0, // To be set after bidding
+    blackTime: 0, // To be set after bidding
+    biddingTimerInterval: undefined,
+    biddingTimeLeft: 10,
+    drawOfferFrom: null,
+    takebackFrom: null,
+    rematchRequestedBy: new Set(),
+    spectators: new Map(),
+    lastActivity: Date.now(),
+    moveTimes: [],
+  };
+
+  activeGames.set(gameId, game);
+
+  // Set gameId on both sockets
+  whitePlayer.socket.data.gameId = gameId;
+  blackPlayer.socket.data.gameId = gameId;
+
+  console.log(
+    `Game ${gameId} created: ${whitePlayer.userId} (white) vs ${blackPlayer.userId} (black)`
+  );
+
+  // Bot games skip bidding, the caller starts them straight away
+  if (!isBotGame(game)) {
+    startBiddingTimer(game);
+  }
+
+  return game;
+}
+
+/**
+ * Creates the engine opponent. Its socket is a stub so the rest of the game
+ * code can treat it like any other player.
+ */
+function createBotPlayer(difficulty: BotDifficulty): Player {
+  const socket = {
+    id: `bot-${generateGameId()}`,
+    connected: true,
+    data: { username: "Chess Bot", userId: "bot", gameId: null },
+    emit: () => true,
+    disconnect: () => socket,
+  };
+
+  return {
+    socket: socket as unknown as Socket,
+    username: "Chess Bot",
+    userId: "bot",
+    isBot: true,
+    botDepth: BOT_DIFFICULTY_DEPTH[difficulty],
+  };
+}
+
+/**
+ * Largest rating gap a queued player accepts, widening the longer they wait.
+ */
+function ratingBand(player: Player, now: number): number {
+  const waited = now - (player.queuedAt ?? now);
+  return (
+    RATING_BAND_BASE +
+    Math.floor(waited / RATING_BAND_STEP_MS) * RATING_BAND_GROWTH
+  );
+}
+
+/**
+ * Index of the queued player closest in rating to `player` whose color
+ * preference doesn't clash and who is within either player's rating band,
+ * or -1 if nobody suitable is waiting. Ties go to whoever has waited longest.
+ */
+function findCompatibleOpponent(player: Player, now = Date.now()): number {
+  const preference = player.colorPreference ?? "random";
+  const rating = player.rating ?? DEFAULT_CHESS_RATING;
+
+  let bestIndex = -1;
+  let bestGap = Infinity;
+  waitingQueue.forEach((p, i) => {
+    // Never pair a user with themselves (e.g. from a second tab)
+    if (p === player || p.userId === player.userId) return;
+    const other = p.colorPreference ?? "random";
+    // Only two players asking for the same explicit color clash
+    if (preference !== "random" && other === preference) return;
+
+    const gap = Math.abs((p.rating ?? DEFAULT_CHESS_RATING) - rating);
+    const band = Math.max(ratingBand(p, now), ratingBand(player, now));
+    if (gap <= band && gap < bestGap) {
+      bestIndex = i;
+      bestGap = gap;
+    }
+  });
+  return bestIndex;
+}
+
+/**
+ * Creates a game for two players taken from the queue and asks for bids.
+ */
+function pairPlayers(newPlayer: Player, opponent: Player): Game {
+  // Assign colors from both preferences (but don't start yet)
+  const { white: whitePlayer, black: blackPlayer } = assignColors(
+    newPlayer,
+    opponent
+  );
+
+  const game = createGame(whitePlayer, blackPlayer);
+
+  // Notify both players of pairing and request bids
+  const pairedData = { opponent: blackPlayer.userId };
+  whitePlayer.socket.emit("paired", pairedData);
+  const pairedDataBlack = { opponent: whitePlayer.userId };
+  blackPlayer.socket.emit("paired", pairedDataBlack);
+  return game;
+}
+
+/**
+ * Pairs queued players whose rating bands have widened enough to meet.
+ * Exported so matching can be driven manually.
+ */
+export function matchWaitingPlayers(now = Date.now()) {
+  for (let i = 0; i < waitingQueue.length; i++) {
+    if (!waitingQueue[i].socket.connected) {
+      waitingQueue.splice(i--, 1);
+      continue;
+    }
+    const opponentIndex = findCompatibleOpponent(waitingQueue[i], now);
+    if (opponentIndex === -1) continue;
+
+    const player = waitingQueue[i];
+    const opponent = waitingQueue[opponentIndex];
+    waitingQueue.splice(Math.max(i, opponentIndex), 1);
+    waitingQueue.splice(Math.min(i, opponentIndex), 1);
+    pairPlayers(player, opponent);
+    i--; // The queue shifted, so look at this position again
+  }
+}
+
+/**
+ * Starts the background task that re-runs matchmaking as bands widen.
+ */
+export function startChessMatchmaker() {
+  return setInterval(() => matchWaitingPlayers(), MATCH_INTERVAL_MS);
+}
+
+function isBotGame(game: Game): boolean {
+  return !!(game.white.isBot || game.black.isBot);
+}
+
+/**
+ * Copy of a player without any per-game state, used when starting a rematch.
+ */
+function freshPlayer(player: Player): Player {
+  return {
+    socket: player.socket,
+    username: player.username,
+    userId: player.userId,
+    isBot: player.isBot,
+    botDepth: player.botDepth,
+  };
+}
+
+/**
+ * Moves a game into the playing phase with the given time per side.
+ */
+function startGame(game: Game, selectedTime: number) {
+  stopBiddingTimer(game);
+
+  game.whiteTime = selectedTime;
+  game.blackTime = selectedTime;
+  game.phase = "playing";
+  game.startedAt = new Date();
+  game.timeControl = selectedTime;
+  game.turnStartedAt = Date.now();
+
+  const fen = game.chess.fen();
+  const startDataWhite = {
+    fen,
+    your_color: "white",
+    opponent: game.black.userId,
+    time: selectedTime,
+    variant: game.start.variant,
+    whiteTime: game.whiteTime,
+    blackTime: game.blackTime,
+  };
+  game.white.socket.emit("start", startDataWhite);
+
+  const startDataBlack = {
+    fen,
+    your_color: "black",
+    opponent: game.white.userId,
+    time: selectedTime,
+    variant: game.start.variant,
+    whiteTime: game.whiteTime,
+    blackTime: game.blackTime,
+  };
+  game.black.socket.emit("start", startDataBlack);
+
+  console.log(
+    `Game ${game.id} started with time ${selectedTime} seconds per player`
+  );
+
+  // Start white's timer
+  startTimer(game);
+
+  // The engine may be playing white
+  scheduleBotMove(game);
+}
+
+/**
+ * Validates and plays a move for the given side, then broadcasts it.
+ * Human and engine moves both go through here.
+ * Throws with a client-facing message if the move can't be played.
+ */
+function applyMove(game: Game, playerColor: "w" | "b", data: MoveInput) {
+  if (game.phase !== "playing") {
+    throw new Error("Game not in playing phase");
+  }
+  if (game.chess.turn() !== playerColor) {
+    throw new Error("Not your turn");
+  }
+
+  // Reject malformed payloads and missing promotion pieces specifically
+  const requestedMove = resolveMove(game.chess, data);
+
+  // Stop timer before validating move (time already spent)
+  stopTimer(game);
+
+  let move;
+  try {
+    move = game.chess.move(requestedMove);
+    if (!move) {
+      throw new Error("Invalid move");
+    }
+  } catch (error) {
+    // Restart timer if move invalid
+    startTimer(game);
+    throw new Error("Invalid move");
+  }
+
+  const now = Date.now();
+  const moveTime = {
+    thinkMs: now - (game.turnStartedAt ?? now),
+    clock: playerColor === "w" ? game.whiteTime : game.blackTime,
+  };
+  game.moveTimes.push(moveTime);
+  game.turnStartedAt = now;
+  game.lastActivity = now;
+  game.firstMoveMade = true;
+  const fen = game.chess.fen();
+
+  // Broadcast update
+  broadcast(game, "update", {
+    fen,
+    ...getPositionStatus(game.chess),
+    moveTime,
+  });
+  // Invite games stay out of the public feed
+  if (!game.isPrivate) {
+    broadcastChessMove({
+      gameId: game.id,
+      ply: game.chess.history().length,
+      san: move.san,
+    });
+  }
+
+  // Restart timer for opponent
+  startTimer(game);
+
+  // Check game over
+  if (isGameFinished(game.chess)) {
+    let winnerColor: "white" | "black" | null = null;
+    let reason = "";
+    if (game.chess.isCheckmate()) {
+      winnerColor = playerColor === "w" ? "white" : "black";
+      reason = "checkmate";
+    } else {
+      reason = getDrawReason(game.chess) ?? "draw";
+    }
+    endGame(game, winnerColor, reason);
+  } else {
+    // Clear any pending draw offer or takeback request after a move
+    game.drawOfferFrom = null;
+    game.takebackFrom = null;
+    offerDrawClaim(game);
+    scheduleBotMove(game);
+  }
+}
+
+/**
+ * Tells the side to move it may claim a threefold repetition or fifty-move
+ * draw. The game carries on unless they send "claim_draw"; the engine never
+ * claims.
+ */
+function offerDrawClaim(game: Game) {
+  const reason = getClaimableDraw(game.chess);
+  if (!reason) return;
+
+  const toMove = game[game.chess.turn() === "w" ? "white" : "black"];
+  if (toMove.isBot) return;
+  toMove.socket.emit(
+    reason === "threefold_repetition"
+      ? "threefold_available"
+      : "fifty_move_available",
+    { reason }
+  );
+}
+
+/**
+ * Undoes the requester's last move (and the opponent's reply to it, if any)
+ * by replaying the history without those plies, so repetition tracking
+ * stays correct.
+ */
+function applyTakeback(game: Game, requester: "white" | "black") {
+  const history = game.chess.history({ verbose: true });
+  // Custom positions can start with black to move, so ask the move itself
+  const lastMove = history[history.length - 1];
+  const plies = lastMove.color === requester[0] ? 1 : 2;
+
+  stopTimer(game);
+  game.chess = replayMoves(
+    history.slice(0, history.length - plies).map((move) => move.san),
+    game.start.fen
+  );
+  game.moveTimes = game.moveTimes.slice(0, history.length - plies);
+  game.takebackFrom = null;
+  game.drawOfferFrom = null;
+  game.lastActivity = Date.now();
+  game.turnStartedAt = game.lastActivity;
+
+  broadcast(game, "takeback_accepted", { plies });
+  broadcast(game, "update", {
+    fen: game.chess.fen(),
+    ...getPositionStatus(game.chess),
+  });
+  startTimer(game);
+}
+
+/**
+ * Lets the engine reply if it is the side to move.
+ */
+function scheduleBotMove(game: Game) {
+  const botColor = game.chess.turn() === "w" ? "white" : "black";
+  const bot = game[botColor];
+  if (!bot.isBot) return;
+
+  setTimeout(() => {
+    if (activeGames.get(game.id) !== game || game.phase !== "playing") return;
+
+    const move = findBestMove(game.chess, bot.botDepth ?? 1);
+    if (!move) return;
+
+    try {
+      applyMove(game, botColor === "white" ? "w" : "b", { move });
+    } catch (error) {
+      console.error(`Bot move failed in game ${game.id}:`, error);
+    }
+  }, BOT_MOVE_DELAY_MS);
+}
+
+/**
+ * Removes a finished game once its rematch window has closed (or been used).
+ */
+function removeFinishedGame(game: Game) {
+  if (game.rematchTimeout) {
+    clearTimeout(game.rematchTimeout);
+    game.rematchTimeout = undefined;
+  }
+
+  closeSpectators(game);
+
+  // Only clear the gameId if the socket hasn't moved on to another game
+  if (game.white.socket.data.gameId === game.id) {
+    game.white.socket.data.gameId = null;
+  }
+  if (game.black.socket.data.gameId === game.id) {
+    game.black.socket.data.gameId = null;
+  }
+  activeGames.delete(game.id);
+}
+
+/**
+ * Emits an event to both players and every spectator of a game.
+ */
+function broadcast(game: Game, event: string, data?: any) {
+  game.white.socket.emit(event, data);
+  game.black.socket.emit(event, data);
+  emitToSpectators(game, event, data);
+}
+
+function emitToSpectators(game: Game, event: string, data?: any) {
+  for (const spectator of game.spectators.values()) {
+    spectator.emit(event, data);
+  }
+}
+
+function broadcastSpectatorCount(game: Game) {
+  broadcast(game, "spectator_count", { count: game.spectators.size });
+}
+
+/**
+ * Snapshot of a game sent with "init" to spectators and reconnecting players.
+ */
+function getGameState(game: Game) {
+  return {
+    gameId: game.id,
+    fen: game.chess.fen(),
+    phase: game.phase,
+    turn: game.chess.turn() === "w" ? "white" : "black",
+    variant: game.start.variant,
+    initialFen: game.start.fen,
+    history: game.chess.history(),
+    moveTimes: game.moveTimes,
+    ...getPositionStatus(game.chess),
+    white: { userId: game.white.userId, username: game.white.username },
+    black: { userId: game.black.userId, username: game.black.username },
+    whiteTime: game.whiteTime,
+    blackTime: game.blackTime,
+  };
+}
+
+/**
+ * Attaches a read-only viewer to a game and sends them the current state.
+ */
+function addSpectator(game: Game, socket: Socket) {
+  game.spectators.set(socket.id, socket);
+  socket.emit("init", getGameState(game));
+  broadcastSpectatorCount(game);
+}
+
+/**
+ * Finds a game the user dropped out of and can still reconnect to.
+ */
+function findReconnectableGame(
+  userId: string
+): { game: Game; color: "white" | "black" } | undefined {
+  for (const game of activeGames.values()) {
+    if (game.phase !== "playing") continue;
+    if (game.white.userId === userId && game.white.disconnectTimeout) {
+      return { game, color: "white" };
+    }
+    if (game.black.userId === userId && game.black.disconnectTimeout) {
+      return { game, color: "black" };
+    }
+  }
+  return undefined;
+}
+
+/**
+ * How many live connections a user has that are queued, waiting on an
+ * invite or playing an unfinished game.
+ */
+function countUserGames(userId: string): number {
+  const isLive = (player: Player) =>
+    player.userId === userId && isConnected(player);
+
+  let count = waitingQueue.filter(isLive).length;
+  for (const invite of invites.values()) {
+    if (invite.waiting && isLive(invite.waiting)) count++;
+  }
+  for (const game of activeGames.values()) {
+    if (game.phase === "ended") continue;
+    if (isLive(game.white) || isLive(game.black)) count++;
+  }
+  return count;
+}
+
+/**
+ * Takes a socket out of the public queue or off the invite it is waiting
+ * on. Returns false if it wasn't waiting to be paired.
+ */
+function stopWaiting(socket: Socket): boolean {
+  const userId = socket.data.userId;
+
+  // Free the invite slot so someone else can use the code
+  const inviteCode = socket.data.inviteCode;
+  const invite = inviteCode ? invites.get(inviteCode) : undefined;
+  if (invite?.waiting?.socket.id === socket.id) {
+    invite.waiting = undefined;
+    socket.data.inviteCode = null;
+    console.log(`${userId} stopped waiting on invite ${inviteCode}`);
+    return true;
+  }
+
+  const queueIndex = waitingQueue.findIndex((p) => p.socket.id === socket.id);
+  if (queueIndex !== -1) {
+    waitingQueue.splice(queueIndex, 1);
+    console.log(`Removed from waiting queue: ${userId}`);
+    return true;
+  }
+  return false;
+}
+
+/**
+ * Swaps a reconnecting player's socket into their game and resends the state.
+ */
+function reconnectPlayer(
+  game: Game,
+  color: "white" | "black",
+  socket: Socket
+) {
+  const player = game[color];
+  const opponentPlayer = color === "white" ? game.black : game.white;
+
+  clearTimeout(player.disconnectTimeout);
+  player.disconnectTimeout = undefined;
+  player.socket = socket;
+  socket.data.gameId = game.id;
+
+  socket.emit("init", {
+    ...getGameState(game),
+    your_color: color,
+    opponent: opponentPlayer.userId,
+  });
+  opponentPlayer.socket.emit("reconnected", { color });
+  emitToSpectators(game, "reconnected", { color });
+
+  console.log(`${player.userId} reconnected to game ${game.id} as ${color}`);
+}
+
+function removeSpectator(game: Game, socket: Socket) {
+  if (!game.spectators.delete(socket.id)) return;
+  if (activeGames.get(game.id) === game) {
+    broadcastSpectatorCount(game);
+  }
+}
+
+/**
+ * Disconnects all spectators when a game is removed.
+ */
+function closeSpectators(game: Game) {
+  for (const spectator of game.spectators.values()) {
+    spectator.emit("game_closed", { gameId: game.id });
+    spectator.disconnect();
+  }
+  game.spectators.clear();
+}
+
 function startTimer(game: Game) {
   if (game.timerInterval) {
     clearInterval(game.timerInterval);
@@ -43,6 +873,15 @@ function startTimer(game: Game) {
     game.chess.turn() === "w" ? "whiteTime" : "blackTime";
 
   game.timerInterval = setInterval(() => {
+    if (
+      !game.firstMoveMade &&
+      game.startedAt &&
+      Date.now() - game.startedAt.getTime() >= ABORT_WINDOW_MS
+    ) {
+      abortGame(game, null);
+      return;
+    }
+
     game[currentPlayerTime] -= 1;
     broadcastTime(game);
 
@@ -89,51 +928,20 @@ function startBiddingTimer(game: Game) {
         MIN_BID,
         Math.min(game.white.bid, game.black.bid)
       );
-      game.whiteTime = selectedTime;
-      game.blackTime = selectedTime;
-      game.phase = "playing";
-
-      const fen = game.chess.fen();
-      const startDataWhite = {
-        fen,
-        your_color: "white",
-        opponent: game.black.userId,
-        time: selectedTime,
-        whiteTime: game.whiteTime,
-        blackTime: game.blackTime,
-      };
-      game.white.socket.emit("start", startDataWhite);
-
-      const startDataBlack = {
-        fen,
-        your_color: "black",
-        opponent: game.white.userId,
-        time: selectedTime,
-        whiteTime: game.whiteTime,
-        blackTime: game.blackTime,
-      };
-      game.black.socket.emit("start", startDataBlack);
-
-      console.log(
-        `Game ${game.id} started with default time ${selectedTime} seconds per player due to bidding timeout`
-      );
-
-      // Start white's timer
-      startTimer(game);
+      console.log(`Game ${game.id} bidding timed out, using default bids`);
+      startGame(game, selectedTime);
     }
   }, 1000);
 }
 
 function broadcastBiddingTime(game: Game) {
   const biddingData = { timeLeft: game.biddingTimeLeft };
-  game.white.socket.emit("bidding_time_update", biddingData);
-  game.black.socket.emit("bidding_time_update", biddingData);
+  broadcast(game, "bidding_time_update", biddingData);
 }
 
 function broadcastTime(game: Game) {
   const timeData = { whiteTime: game.whiteTime, blackTime: game.blackTime };
-  game.white.socket.emit("time_update", timeData);
-  game.black.socket.emit("time_update", timeData);
+  broadcast(game, "time_update", timeData);
 }
 
 async function endGame(
@@ -144,6 +952,12 @@ async function endGame(
   stopTimer(game);
   game.phase = "ended";
 
+  // Nobody can forfeit by disconnecting once the game is over
+  clearTimeout(game.white.disconnectTimeout);
+  clearTimeout(game.black.disconnectTimeout);
+  game.white.disconnectTimeout = undefined;
+  game.black.disconnectTimeout = undefined;
+
   const winner = winnerColor
     ? winnerColor === "white"
       ? game.white.userId
@@ -166,6 +980,18 @@ async function endGame(
       game.black.socket.emit("draw", { reason });
     }
   }
+  if (winner) {
+    emitToSpectators(game, "win", endData);
+  } else {
+    emitToSpectators(game, "draw", { reason });
+  }
+
+  // Games against the engine don't count towards stats or ratings
+  if (isBotGame(game)) {
+    scheduleGameRemoval(game);
+    console.log(`Game ${game.id} ended: ${reason}`);
+    return;
+  }
 
   // Update user stats in database
   try {
@@ -185,31 +1011,261 @@ async function endGame(
       // Draw: no wins or losses
       console.log("Game ended in draw, no stats updated");
     }
+
+    // Ratings change for every result, including draws and disconnects
+    const whiteScore = winnerColor === "white" ? 1 : winnerColor ? 0 : 0.5;
+    const ratings = await updateChessRatings(
+      game.white.userId,
+      game.black.userId,
+      whiteScore
+    );
+    const ratingData = {
+      whiteRating: ratings.white,
+      blackRating: ratings.black,
+      whiteDelta: ratings.delta,
+      blackDelta: -ratings.delta,
+    };
+    if (game.white.socket.connected) {
+      game.white.socket.emit("rating_update", ratingData);
+    }
+    if (game.black.socket.connected) {
+      game.black.socket.emit("rating_update", ratingData);
+    }
   } catch (error) {
     console.error("Error updating user stats:", error);
   }
 
-  // Clear gameId for both players
-  game.white.socket.data.gameId = null;
-  game.black.socket.data.gameId = null;
-  activeGames.delete(game.id);
+  // Games that never left bidding have no moves worth keeping
+  if (game.startedAt) {
+    await saveChessGame({
+      white: { userId: game.white.userId, username: game.white.username },
+      black: { userId: game.black.userId, username: game.black.username },
+      winner: winnerColor,
+      reason,
+      moves: game.chess.history(),
+      moveTimes: game.moveTimes,
+      variant: game.start.variant,
+      initialFen: game.start.fen,
+      // Opening names only apply to the standard starting position
+      opening:
+        game.start.variant === "standard"
+          ? classifyOpening(game.chess.history())
+          : null,
+      finalFen: game.chess.fen(),
+      timeControl: game.timeControl ?? 0,
+      isPrivate: !!game.isPrivate,
+      startedAt: game.startedAt,
+      createdAt: new Date(),
+    }).catch((error) => console.error("Error saving chess game:", error));
+  }
+
+  scheduleGameRemoval(game);
   console.log(`Game ${game.id} ended: ${reason}`);
 }
 
+/**
+ * Cancels a game in which no move has been played: nobody wins, ratings and
+ * stats are untouched and nothing is saved to the history. `by` is the
+ * player who aborted, or null when the abort window ran out.
+ */
+function abortGame(game: Game, by: "white" | "black" | null) {
+  stopTimer(game);
+  game.phase = "ended";
+
+  clearTimeout(game.white.disconnectTimeout);
+  clearTimeout(game.black.disconnectTimeout);
+  game.white.disconnectTimeout = undefined;
+  game.black.disconnectTimeout = undefined;
+
+  broadcast(game, "aborted", { by });
+
+  scheduleGameRemoval(game);
+  console.log(`Game ${game.id} aborted${by ? ` by ${by}` : ""}`);
+}
+
+/**
+ * Keeps a finished game around briefly so both players can request a rematch.
+ */
+function scheduleGameRemoval(game: Game) {
+  game.rematchTimeout = setTimeout(() => {
+    if (game.rematchRequestedBy.size > 0) {
+      if (game.white.socket.connected) {
+        game.white.socket.emit("rematch_expired");
+      }
+      if (game.black.socket.connected) {
+        game.black.socket.emit("rematch_expired");
+      }
+    }
+    removeFinishedGame(game);
+  }, REMATCH_WINDOW_MS);
+}
+
+/**
+ * Whether a player has a live connection (the engine never counts).
+ */
+function isConnected(player: Player): boolean {
+  return !player.isBot && player.socket.connected;
+}
+
+/**
+ * Removes a game that nobody is playing any more, telling anyone still
+ * connected why it went away.
+ */
+function reapGame(game: Game, reason: string) {
+  stopTimer(game);
+  stopBiddingTimer(game);
+  clearTimeout(game.white.disconnectTimeout);
+  clearTimeout(game.black.disconnectTimeout);
+  game.white.disconnectTimeout = undefined;
+  game.black.disconnectTimeout = undefined;
+  game.phase = "ended";
+
+  for (const player of [game.white, game.black]) {
+    if (isConnected(player)) {
+      player.socket.emit("game_abandoned", { gameId: game.id, reason });
+    }
+  }
+
+  removeFinishedGame(game);
+  console.log(`Game ${game.id} removed: ${reason}`);
+}
+
+/**
+ * Whether a running clock will still end the game on its own. A long think
+ * on a long time control is not abandonment; the flag settles it instead.
+ */
+function hasTimeLeft(game: Game) {
+  if (game.phase === "bidding") {
+    return !!game.biddingTimerInterval && game.biddingTimeLeft > 0;
+  }
+  if (game.phase !== "playing" || !game.timerInterval) return false;
+  return (game.chess.turn() === "w" ? game.whiteTime : game.blackTime) > 0;
+}
+
+/**
+ * Removes abandoned games and stale queue entries.
+ * Exported so the interval can be driven manually.
+ */
+export function sweepChessGames(now = Date.now()) {
+  for (const game of activeGames.values()) {
+    // Players inside their reconnect grace period are handled by that timer
+    const awaitingReconnect =
+      game.white.disconnectTimeout || game.black.disconnectTimeout;
+
+    if (
+      !isConnected(game.white) &&
+      !isConnected(game.black) &&
+      !awaitingReconnect
+    ) {
+      reapGame(game, "no players connected");
+    } else if (
+      now - game.lastActivity > GAME_IDLE_TIMEOUT_MS &&
+      !hasTimeLeft(game)
+    ) {
+      reapGame(game, "idle timeout");
+    }
+  }
+
+  // Queue entries whose socket dropped without a disconnect event
+  for (let i = waitingQueue.length - 1; i >= 0; i--) {
+    if (!waitingQueue[i].socket.connected) {
+      waitingQueue.splice(i, 1);
+    }
+  }
+}
+
+/**
+ * Starts the background task that periodically sweeps abandoned games.
+ */
+export function startChessSweeper() {
+  const interval = setInterval(() => sweepChessGames(), SWEEP_INTERVAL_MS);
+  console.log(`Chess game sweeper running every ${SWEEP_INTERVAL_MS}ms`);
+  return interval;
+}
+
+/**
+ * Notifies every chess client that the server is going down and stops all
+ * clocks so nobody loses on time while the process exits. Results of
+ * unfinished games are not recorded.
+ */
+export function shutdownChess() {
+  const shutdownData = { message: "Server is restarting, the game has ended" };
+
+  for (const game of activeGames.values()) {
+    stopTimer(game);
+    stopBiddingTimer(game);
+    clearTimeout(game.white.disconnectTimeout);
+    clearTimeout(game.black.disconnectTimeout);
+    clearTimeout(game.rematchTimeout);
+    game.phase = "ended";
+    broadcast(game, "server_shutdown", shutdownData);
+  }
+  activeGames.clear();
+
+  for (const player of waitingQueue) {
+    player.socket.emit("server_shutdown", shutdownData);
+  }
+  waitingQueue.length = 0;
+
+  for (const invite of invites.values()) {
+    clearTimeout(invite.expiryTimeout);
+    invite.waiting?.socket.emit("server_shutdown", shutdownData);
+  }
+  invites.clear();
+}
+
+/**
+ * Forfeits every game involving `userId` and removes them from the queue and
+ * any pending invite. Used when an account is deleted.
+ */
+export async function forfeitUserGames(userId: string) {
+  for (const game of [...activeGames.values()]) {
+    const color =
+      game.white.userId === userId
+        ? "white"
+        : game.black.userId === userId
+        ? "black"
+        : null;
+    if (!color) continue;
+
+    if (game.phase === "playing") {
+      await endGame(game, color === "white" ? "black" : "white", "forfeit");
+    } else if (game.phase === "bidding") {
+      reapGame(game, "player left");
+    }
+    game[color].socket.disconnect();
+  }
+
+  for (let i = waitingQueue.length - 1; i >= 0; i--) {
+    if (waitingQueue[i].userId === userId) {
+      waitingQueue.splice(i, 1)[0].socket.disconnect();
+    }
+  }
+
+  for (const invite of [...invites.values()]) {
+    if (invite.createdBy === userId || invite.waiting?.userId === userId) {
+      clearTimeout(invite.expiryTimeout);
+      invite.waiting?.socket.disconnect();
+      invites.delete(invite.code);
+    }
+  }
+}
+
 export function setupChess(nsp: Namespace) {
+  chessNamespace = nsp;
   nsp.on("connection", async (socket: Socket) => {
     console.log(`User connected: ${socket.id}`);
+    rejectMalformedMessages(socket);
 
-    const cookie = socket.handshake.headers.cookie;
-    if (!cookie) {
-      console.log("No cookie, disconnecting");
-      socket.emit("error", { message: "No session cookie provided" });
+    const headers = handshakeHeaders(socket);
+    if (!hasCredentials(headers)) {
+      console.log("No credentials, disconnecting");
+      socket.emit("error", { message: "No session cookie or token provided" });
       return socket.disconnect();
     }
 
     let sessionResponse;
     try {
-      const headers = new Headers(socket.handshake.headers as any);
       sessionResponse = await auth.api.getSession({ headers });
       if (!sessionResponse?.user) {
         throw new Error("No valid session data found");
@@ -227,56 +1283,177 @@ export function setupChess(nsp: Namespace) {
     const userId = user.id; // Better-auth user ID
     console.log(`Authenticated user: ${username} (ID: ${userId})`);
 
-    socket.data = { username, userId, gameId: null };
+    socket.data = { username, userId, gameId: null, inviteCode: null };
+
+    // Spectators attach to an existing game instead of joining the queue
+    const spectateGameId = socket.handshake.query.spectate;
+    if (typeof spectateGameId === "string" && spectateGameId) {
+      const spectatedGame = activeGames.get(spectateGameId);
+      if (!spectatedGame) {
+        socket.emit("error", { message: "Game not found" });
+        return socket.disconnect();
+      }
+
+      const isParticipant =
+        spectatedGame.white.userId === userId ||
+        spectatedGame.black.userId === userId;
+      if (!isParticipant) {
+        addSpectator(spectatedGame, socket);
+        console.log(`${userId} is spectating game ${spectatedGame.id}`);
+
+        socket.on("move", () => {
+          socket.emit("error", { message: "Spectators cannot make moves" });
+        });
+        socket.on("disconnect", () => {
+          removeSpectator(spectatedGame, socket);
+        });
+        return;
+      }
+    }
+
+    // The last await before pairing: everything below runs in one go, so
+    // other connections can't change the queue, invites or games between
+    // the checks and the pairing that relies on them
+    const rating = await getChessRating(userId).catch(
+      () => DEFAULT_CHESS_RATING
+    );
+    if (!socket.connected) return; // Left while the rating was loading
 
     // Pairing logic
     let game: Game | undefined;
+    const reconnectable = findReconnectableGame(userId);
 
-    if (waitingQueue.length > 0) {
-      const opponent = waitingQueue.shift()!;
-      const gameId = generateGameId();
-
-      // Randomly assign colors (but don't start yet)
-      const isNewWhite = Math.random() < 0.5;
-      const newPlayer = { socket, username, userId };
-      const whitePlayer = isNewWhite ? newPlayer : opponent;
-      const blackPlayer = isNewWhite ? opponent : newPlayer;
-
-      game = {
-        id: gameId,
-        white: whitePlayer,
-        black: blackPlayer,
-        chess: new Chess(),
-        phase: "bidding",
-        whiteTime: 0, // To be set after bidding
-        blackTime: 0, // To be set after bidding
-        biddingTimerInterval: undefined,
-        biddingTimeLeft: 10,
-        drawOfferFrom: null,
-      };
+    // Extra connections from the same user must not be paired again
+    if (!reconnectable && countUserGames(userId) >= MAX_GAMES_PER_USER) {
+      socket.emit("error", {
+        code: "game_limit",
+        message:
+          MAX_GAMES_PER_USER === 1
+            ? "You are already in a chess game or waiting for one"
+            : `You can be in at most ${MAX_GAMES_PER_USER} chess games at once`,
+      });
+      return socket.disconnect();
+    }
 
-      activeGames.set(gameId, game);
+    const { vs, difficulty, color, invite: inviteCode, variant, fen } =
+      socket.handshake.query;
 
-      // Set gameId on both sockets
-      whitePlayer.socket.data.gameId = gameId;
-      blackPlayer.socket.data.gameId = gameId;
+    const colorPreference = color ?? "random";
+    if (
+      typeof colorPreference !== "string" ||
+      !COLOR_PREFERENCES.includes(colorPreference as ColorPreference)
+    ) {
+      socket.emit("error", {
+        message: `Invalid color, must be one of: ${COLOR_PREFERENCES.join(
+          ", "
+        )}`,
+      });
+      return socket.disconnect();
+    }
 
-      console.log(
-        `Game ${gameId} created in bidding phase: ${whitePlayer.userId} (white) vs ${blackPlayer.userId} (black)`
-      );
+    // Against the engine the player picks the position; invites carry the
+    // creator's choice and the public queue always plays standard chess
+    let botStart = STANDARD_START;
+    if (variant !== undefined || fen !== undefined) {
+      if (vs !== "bot") {
+        socket.emit("error", {
+          message:
+            "Starting positions can only be chosen against the engine or when creating an invite",
+        });
+        return socket.disconnect();
+      }
+      try {
+        botStart = resolveStartPosition(
+          typeof variant === "string" ? variant : undefined,
+          typeof fen === "string" ? fen : undefined
+        );
+      } catch (error: any) {
+        socket.emit("error", { message: error.message });
+        return socket.disconnect();
+      }
+    }
 
-      // Start bidding timer
-      startBiddingTimer(game);
+    const newPlayer: Player = {
+      socket,
+      username,
+      userId,
+      colorPreference: colorPreference as ColorPreference,
+      rating,
+    };
+    const opponentIndex = findCompatibleOpponent(newPlayer);
+
+    if (reconnectable) {
+      // Resume the game this user dropped out of instead of pairing again
+      game = reconnectable.game;
+      reconnectPlayer(game, reconnectable.color, socket);
+    } else if (typeof inviteCode === "string" && inviteCode) {
+      // Private games only pair players presenting the same code
+      const invite = invites.get(inviteCode.toUpperCase());
+      if (!invite) {
+        socket.emit("error", { message: "Invite not found or expired" });
+        return socket.disconnect();
+      }
 
-      // Notify both players of pairing and request bids
-      const pairedData = { opponent: blackPlayer.userId };
-      whitePlayer.socket.emit("paired", pairedData);
-      const pairedDataBlack = { opponent: whitePlayer.userId };
-      blackPlayer.socket.emit("paired", pairedDataBlack);
+      if (!invite.waiting) {
+        invite.waiting = newPlayer;
+        socket.data.inviteCode = invite.code;
+        socket.emit("waiting", { invite: invite.code });
+        console.log(`${userId} waiting on invite ${invite.code}`);
+      } else if (invite.waiting.userId === userId) {
+        socket.emit("error", { message: "Already waiting on this invite" });
+        return socket.disconnect();
+      } else {
+        const opponent = invite.waiting;
+        clearTimeout(invite.expiryTimeout);
+        invites.delete(invite.code);
+        opponent.socket.data.inviteCode = null;
+
+        const { white: whitePlayer, black: blackPlayer } = assignColors(
+          newPlayer,
+          opponent
+        );
+
+        game = createGame(whitePlayer, blackPlayer, invite.start);
+        game.isPrivate = true;
+
+        whitePlayer.socket.emit("paired", { opponent: blackPlayer.userId });
+        blackPlayer.socket.emit("paired", { opponent: whitePlayer.userId });
+      }
+    } else if (vs === "bot") {
+      if (
+        typeof difficulty === "string" &&
+        !(difficulty in BOT_DIFFICULTY_DEPTH)
+      ) {
+        socket.emit("error", {
+          message: `Invalid difficulty, must be one of: ${Object.keys(
+            BOT_DIFFICULTY_DEPTH
+          ).join(", ")}`,
+        });
+        return socket.disconnect();
+      }
+
+      // Single-player game against the built-in engine
+      const bot = createBotPlayer(
+        typeof difficulty === "string"
+          ? (difficulty as BotDifficulty)
+          : "medium"
+      );
+      const colors = assignColors(newPlayer, bot);
+
+      game = createGame(colors.white, colors.black, botStart);
+      socket.emit("paired", { opponent: bot.userId });
+      startGame(game, BOT_GAME_TIME);
+    } else if (opponentIndex !== -1) {
+      const [opponent] = waitingQueue.splice(opponentIndex, 1);
+      game = pairPlayers(newPlayer, opponent);
     } else {
-      // Add to waiting queue
-      waitingQueue.push({ socket, username, userId });
-      console.log(`${userId} added to waiting queue`);
+      // Nobody suitable is waiting yet; the matchmaker keeps looking as the
+      // accepted rating gap widens
+      newPlayer.queuedAt = Date.now();
+      waitingQueue.push(newPlayer);
+      console.log(
+        `${userId} added to waiting queue (color: ${newPlayer.colorPreference}, rating: ${newPlayer.rating})`
+      );
       socket.emit("waiting");
 
       // Send notifications for chess match created event
@@ -307,6 +1484,7 @@ export function setupChess(nsp: Namespace) {
 
       const isWhite = socket.id === game.white.socket.id;
       (isWhite ? game.white : game.black).bid = bid;
+      game.lastActivity = Date.now();
 
       console.log(`${userId} bid ${bid} seconds`);
 
@@ -319,37 +1497,7 @@ export function setupChess(nsp: Namespace) {
           MIN_BID,
           Math.min(game.white.bid, game.black.bid)
         );
-        game.whiteTime = selectedTime;
-        game.blackTime = selectedTime;
-        game.phase = "playing";
-
-        const fen = game.chess.fen();
-        const startDataWhite = {
-          fen,
-          your_color: "white",
-          opponent: game.black.userId,
-          time: selectedTime,
-          whiteTime: game.whiteTime,
-          blackTime: game.blackTime,
-        };
-        game.white.socket.emit("start", startDataWhite);
-
-        const startDataBlack = {
-          fen,
-          your_color: "black",
-          opponent: game.white.userId,
-          time: selectedTime,
-          whiteTime: game.whiteTime,
-          blackTime: game.blackTime,
-        };
-        game.black.socket.emit("start", startDataBlack);
-
-        console.log(
-          `Game ${gameId} started with time ${selectedTime} seconds per player`
-        );
-
-        // Start white's timer
-        startTimer(game);
+        startGame(game, selectedTime);
       }
     });
 
@@ -397,6 +1545,12 @@ export function setupChess(nsp: Namespace) {
         return;
       }
 
+      // The engine always plays on
+      if ((isWhite ? game.black : game.white).isBot) {
+        socket.emit("draw_declined");
+        return;
+      }
+
       game.drawOfferFrom = playerColor;
       opponentSocket.emit("draw_offered", { from: playerColor });
       socket.emit("draw_offer_sent");
@@ -455,8 +1609,31 @@ export function setupChess(nsp: Namespace) {
       socket.emit("draw_offer_cancelled");
     });
 
-    // Handle move
-    socket.on("move", (data: { move: any }) => {
+    // Handle abort, only allowed before the first move
+    socket.on("abort", () => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
+        return;
+      }
+
+      const game = activeGames.get(gameId)!;
+      if (game.phase !== "playing") {
+        socket.emit("error", { message: "Game not in playing phase" });
+        return;
+      }
+      if (game.firstMoveMade) {
+        socket.emit("error", {
+          message: "Game can't be aborted after the first move, resign instead",
+        });
+        return;
+      }
+
+      abortGame(game, socket.id === game.white.socket.id ? "white" : "black");
+    });
+
+    // Handle a claim of threefold repetition or the fifty-move rule
+    socket.on("claim_draw", () => {
       const gameId = socket.data.gameId;
       if (!gameId || !activeGames.has(gameId)) {
         socket.emit("error", { message: "No active game" });
@@ -471,74 +1648,308 @@ export function setupChess(nsp: Namespace) {
 
       const playerColor = socket.id === game.white.socket.id ? "w" : "b";
       if (game.chess.turn() !== playerColor) {
-        socket.emit("error", { message: "Not your turn" });
+        socket.emit("error", { message: "Only the side to move can claim" });
+        return;
+      }
+
+      const reason = getClaimableDraw(game.chess);
+      if (!reason) {
+        socket.emit("error", { message: "No draw can be claimed" });
+        return;
+      }
+
+      endGame(game, null, reason);
+    });
+
+    // Handle takeback requests
+    socket.on("takeback_request", () => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
+        return;
+      }
+
+      const game = activeGames.get(gameId)!;
+      if (game.phase !== "playing") {
+        socket.emit("error", { message: "Game not in playing phase" });
+        return;
+      }
+
+      const isWhite = socket.id === game.white.socket.id;
+      const playerColor = isWhite ? "white" : "black";
+      const opponent = isWhite ? game.black : game.white;
+
+      if (game.takebackFrom) {
+        socket.emit("error", {
+          message: "A takeback request is already pending",
+        });
+        return;
+      }
+
+      // Only a player who has moved has something to take back
+      const hasMoved = game.chess
+        .history({ verbose: true })
+        .some((move) => move.color === playerColor[0]);
+      if (!hasMoved) {
+        socket.emit("error", { message: "No move to take back" });
+        return;
+      }
+
+      // The engine never agrees to takebacks
+      if (opponent.isBot) {
+        socket.emit("takeback_declined");
+        return;
+      }
+
+      game.takebackFrom = playerColor;
+      opponent.socket.emit("takeback_requested", { from: playerColor });
+      socket.emit("takeback_request_sent");
+    });
+
+    // Handle the reply to a takeback request
+    socket.on("takeback_respond", (data: { accept: boolean }) => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
+        return;
+      }
+
+      const game = activeGames.get(gameId)!;
+      if (game.phase !== "playing" || !game.takebackFrom) {
+        socket.emit("error", { message: "No pending takeback request" });
+        return;
+      }
+
+      const isWhite = socket.id === game.white.socket.id;
+      const playerColor = isWhite ? "white" : "black";
+
+      if (game.takebackFrom === playerColor) {
+        socket.emit("error", {
+          message: "Cannot respond to your own takeback request",
+        });
+        return;
+      }
+
+      if (data?.accept === true) {
+        applyTakeback(game, game.takebackFrom);
+        return;
+      }
+
+      game.takebackFrom = null;
+      const opponentSocket = isWhite ? game.black.socket : game.white.socket;
+      opponentSocket.emit("takeback_declined");
+      socket.emit("takeback_request_cancelled");
+    });
+
+    // Handle chat between players (spectators only receive)
+    const chatTimestamps: number[] = [];
+    socket.on("chat", (data: { text: string }) => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
+        return;
+      }
+
+      const text = typeof data?.text === "string" ? data.text.trim() : "";
+      if (!text) {
+        socket.emit("error", { message: "Message cannot be empty" });
+        return;
+      }
+      if (text.length > MAX_CHAT_LENGTH) {
+        socket.emit("error", {
+          message: `Message too long, max ${MAX_CHAT_LENGTH} characters`,
+        });
+        return;
+      }
+
+      // Sliding window rate limit to prevent spam
+      const now = Date.now();
+      while (
+        chatTimestamps.length > 0 &&
+        now - chatTimestamps[0] > CHAT_RATE_WINDOW_MS
+      ) {
+        chatTimestamps.shift();
+      }
+      if (chatTimestamps.length >= CHAT_RATE_LIMIT) {
+        socket.emit("error", { message: "You are sending messages too fast" });
+        return;
+      }
+      chatTimestamps.push(now);
+
+      const game = activeGames.get(gameId)!;
+      game.lastActivity = Date.now();
+      const isWhite = socket.id === game.white.socket.id;
+      const opponentSocket = isWhite ? game.black.socket : game.white.socket;
+
+      const chatData = {
+        from: isWhite ? "white" : "black",
+        userId,
+        username,
+        text,
+      };
+      opponentSocket.emit("chat", chatData);
+      emitToSpectators(game, "chat", chatData);
+    });
+
+    // Handle rematch request after a game has ended
+    socket.on("rematch", () => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No finished game to rematch" });
+        return;
+      }
+
+      const game = activeGames.get(gameId)!;
+      if (game.phase !== "ended") {
+        socket.emit("error", { message: "Game has not ended" });
+        return;
+      }
+
+      const isWhite = socket.id === game.white.socket.id;
+      const playerColor = isWhite ? "white" : "black";
+      const opponentPlayer = isWhite ? game.black : game.white;
+
+      if (!opponentPlayer.socket.connected) {
+        socket.emit("error", { message: "Opponent has left the game" });
+        return;
+      }
+
+      game.rematchRequestedBy.add(playerColor);
+      if (opponentPlayer.isBot) {
+        // The engine accepts every rematch
+        game.rematchRequestedBy.add(isWhite ? "black" : "white");
+      }
+
+      if (game.rematchRequestedBy.size < 2) {
+        opponentPlayer.socket.emit("rematch_requested", { from: playerColor });
+        socket.emit("rematch_request_sent");
+        return;
+      }
+
+      // Both players agreed: swap colors and start a fresh game
+      removeFinishedGame(game);
+      // A rematch replays the same starting position
+      const rematch = createGame(
+        freshPlayer(game.black),
+        freshPlayer(game.white),
+        game.start
+      );
+      rematch.isPrivate = game.isPrivate;
+
+      rematch.white.socket.emit("rematch_start", {
+        gameId: rematch.id,
+        your_color: "white",
+        opponent: rematch.black.userId,
+      });
+      rematch.black.socket.emit("rematch_start", {
+        gameId: rematch.id,
+        your_color: "black",
+        opponent: rematch.white.userId,
+      });
+
+      if (isBotGame(rematch)) {
+        startGame(rematch, BOT_GAME_TIME);
+      }
+    });
+
+    // Handle legal move listing for the current position
+    socket.on("legal_moves", () => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
         return;
       }
 
-      // Stop timer before validating move (time already spent)
-      stopTimer(game);
+      const game = activeGames.get(gameId)!;
+      if (game.phase === "ended") {
+        socket.emit("error", { message: "Game has ended" });
+        return;
+      }
+
+      const playerColor = socket.id === game.white.socket.id ? "w" : "b";
+      socket.emit("legal_moves", {
+        fen: game.chess.fen(),
+        turn: game.chess.turn() === "w" ? "white" : "black",
+        yourTurn: game.chess.turn() === playerColor,
+        moves: getLegalMoves(game.chess),
+      });
+    });
+
+    // Handle move
+    socket.on("move", (data: MoveInput) => {
+      const gameId = socket.data.gameId;
+      if (!gameId || !activeGames.has(gameId)) {
+        socket.emit("error", { message: "No active game" });
+        return;
+      }
+
+      const game = activeGames.get(gameId)!;
+      const playerColor = socket.id === game.white.socket.id ? "w" : "b";
 
       try {
-        const move = game.chess.move(data.move);
-        if (!move) {
-          throw new Error("Invalid move");
-        }
+        applyMove(game, playerColor, data);
+      } catch (error: any) {
+        socket.emit("error", { message: error.message });
+      }
+    });
 
-        const fen = game.chess.fen();
-
-        // Broadcast update
-        game.white.socket.emit("update", { fen });
-        game.black.socket.emit("update", { fen });
-
-        // Restart timer for opponent
-        startTimer(game);
-
-        // Check game over
-        if (game.chess.isGameOver()) {
-          let winnerColor: "white" | "black" | null = null;
-          let reason = "";
-          if (game.chess.isCheckmate()) {
-            winnerColor = playerColor === "w" ? "white" : "black";
-            reason = "checkmate";
-          } else if (game.chess.isDraw()) {
-            reason = "draw"; // Includes stalemate, insufficient material, etc.
-          } else {
-            reason = "draw"; // Other conditions
-          }
-          endGame(game, winnerColor, reason);
+    // Check a dragged move before committing it; nothing is played
+    socket.on(
+      "try_move",
+      (data: { from?: unknown; to?: unknown; promotion?: unknown }) => {
+        const { from = null, to = null } = data ?? {};
+        const game = activeGames.get(socket.data.gameId);
+        const playerColor =
+          game && socket.id === game.white.socket.id ? "w" : "b";
+        let result;
+        if (!game || game.phase !== "playing") {
+          result = { legal: false, reason: "no_active_game" };
+        } else if (game.chess.turn() !== playerColor) {
+          result = { legal: false, reason: "not_your_turn" };
         } else {
-          // Clear any pending draw offer after a move
-          game.drawOfferFrom = null;
+          result = tryMove(game.chess, data);
         }
-      } catch (error) {
-        // Restart timer if move invalid
-        startTimer(game);
-        socket.emit("error", { message: "Invalid move" });
+        socket.emit("try_move_result", { from, to, ...result });
+      }
+    );
+
+    // Leave the queue (or an invite) before being paired
+    socket.on("cancel_search", () => {
+      if (!stopWaiting(socket)) {
+        socket.emit("error", { message: "Not waiting for an opponent" });
+        return;
       }
+      socket.emit("search_cancelled", { reason: "aborted" });
+      socket.disconnect();
     });
 
     socket.on("disconnect", (reason) => {
       console.log(`User disconnected: ${socket.id}, Reason: ${reason}`);
-      const userId = socket.data.userId;
-
-      // Remove from queue if waiting
-      const queueIndex = waitingQueue.findIndex(
-        (p) => p.socket.id === socket.id
-      );
-      if (queueIndex !== -1) {
-        waitingQueue.splice(queueIndex, 1);
-        console.log(`Removed from waiting queue: ${userId}`);
-        return;
-      }
+      if (stopWaiting(socket)) return;
 
       // Handle active game disconnect
       const gameId = socket.data.gameId;
       if (gameId && activeGames.has(gameId)) {
         const game = activeGames.get(gameId)!;
+
+        // Ignore sockets that have already been replaced by a reconnect
+        if (
+          socket.id !== game.white.socket.id &&
+          socket.id !== game.black.socket.id
+        ) {
+          return;
+        }
+
         const isWhite = socket.id === game.white.socket.id;
         const opponentPlayer = isWhite ? game.black : game.white;
 
-        if (game.phase === "bidding") {
+        if (game.phase === "ended") {
+          // A finished game can no longer be rematched once a player leaves
+          if (opponentPlayer.socket.connected) {
+            opponentPlayer.socket.emit("rematch_unavailable");
+          }
+          removeFinishedGame(game);
+        } else if (game.phase === "bidding") {
           // Stop bidding timer
           stopBiddingTimer(game);
 
@@ -548,19 +1959,44 @@ export function setupChess(nsp: Namespace) {
               message: "Opponent disconnected during bidding",
             });
             opponentPlayer.socket.data.gameId = null;
+          }
+          // Players from a private game never join the public queue
+          if (opponentPlayer.socket.connected && !game.isPrivate) {
+            opponentPlayer.queuedAt ??= Date.now();
             waitingQueue.push(opponentPlayer);
             console.log(
               `Opponent ${opponentPlayer.userId} added back to queue`
             );
           }
+          closeSpectators(game);
           activeGames.delete(gameId);
           console.log(
             `Game ${gameId} deleted due to disconnect in bidding phase`
           );
         } else {
-          // Opponent wins if game started
+          // Give the player a grace period to reconnect before forfeiting
+          const disconnectedPlayer = isWhite ? game.white : game.black;
           const winnerColor = isWhite ? "black" : "white";
-          endGame(game, winnerColor, "opponent disconnected");
+
+          disconnectedPlayer.disconnectTimeout = setTimeout(() => {
+            disconnectedPlayer.disconnectTimeout = undefined;
+            if (activeGames.get(gameId) === game && game.phase === "playing") {
+              // Opponent wins if the player never came back
+              endGame(game, winnerColor, "opponent disconnected");
+            }
+          }, RECONNECT_GRACE_MS);
+
+          const disconnectData = {
+            message: "Opponent disconnected, waiting for them to reconnect",
+            reconnectTimeout: RECONNECT_GRACE_MS / 1000,
+          };
+          if (opponentPlayer.socket.connected) {
+            opponentPlayer.socket.emit("opponent_disconnected", disconnectData);
+          }
+          emitToSpectators(game, "opponent_disconnected", disconnectData);
+          console.log(
+            `${disconnectedPlayer.userId} disconnected from game ${gameId}, waiting ${RECONNECT_GRACE_MS}ms`
+          );
         }
       }
     });
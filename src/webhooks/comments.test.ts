@@ -0,0 +1,46 @@
+import { beforeAll, expect, test } from "bun:test";
+import type { Namespace } from "socket.io";
+import { describeWithDb, resetTestDatabase } from "../test-utils/db";
+import { writeTestPost } from "../test-utils/posts";
+import { FakeNamespace } from "../test-utils/sockets";
+
+describeWithDb("comment sockets", () => {
+  let comments: typeof import("./comments");
+  let nsp: FakeNamespace;
+
+  beforeAll(async () => {
+    await resetTestDatabase();
+    await writeTestPost("followed");
+    await writeTestPost("elsewhere");
+    await writeTestPost("unpublished", { draft: true });
+    comments = await import("./comments");
+    nsp = new FakeNamespace("/sockets/comments");
+    comments.setupComments(nsp as unknown as Namespace);
+  });
+
+  test("new comments reach sockets following the post", async () => {
+    const reader = await nsp.connect({ query: { postId: "followed" } });
+    expect(await reader.waitFor("subscribed")).toEqual({ postId: "followed" });
+    const bystander = await nsp.connect();
+    await bystander.send("subscribe", { postId: "elsewhere" });
+    await bystander.waitFor("subscribed");
+
+    const comment = { _id: "c1", blogId: "followed", content: "<p>Hi</p>" };
+    comments.broadcastComment("comment", comment);
+    expect(reader.last("comment")).toEqual(comment);
+    expect(bystander.received("comment")).toEqual([]);
+
+    // Once unsubscribed nothing more arrives
+    await reader.send("unsubscribe", { postId: "followed" });
+    comments.broadcastComment("comment_updated", comment);
+    expect(reader.received("comment_updated")).toEqual([]);
+  });
+
+  test("drafts can't be followed", async () => {
+    const socket = await nsp.connect();
+    await socket.send("subscribe", { postId: "unpublished" });
+    expect(await socket.waitFor("error")).toEqual({
+      message: "Blog post not found",
+    });
+  });
+});
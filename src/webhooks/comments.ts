@@ -0,0 +1,74 @@
+import { Socket, Namespace } from "socket.io";
+import { rejectMalformedMessages } from "./index";
+import { getBlogsFromFiles } from "../routes/blog";
+
+const POST_ID_PATTERN = /^[\w-]+$/;
+const MAX_SUBSCRIPTIONS = 10; // Posts a single socket can follow at once
+
+/**
+ * Reference to the /sockets/comments namespace, set once it is set up.
+ */
+let commentsNamespace: Namespace | undefined;
+
+const postRoom = (postId: string) => `post:${postId}`;
+
+// Drafts and scheduled posts can't be followed, same as GET /blog/:id
+async function isReadablePost(postId: string): Promise<boolean> {
+  if (!POST_ID_PATTERN.test(postId)) return false;
+  return (await getBlogsFromFiles()).some((post) => post.id === postId);
+}
+
+async function subscribe(socket: Socket, postId: unknown) {
+  if (typeof postId !== "string" || !(await isReadablePost(postId))) {
+    socket.emit("error", { message: "Blog post not found" });
+    return;
+  }
+
+  // socket.rooms always holds the socket's own ID as well
+  if (socket.rooms.size > MAX_SUBSCRIPTIONS) {
+    socket.emit("error", {
+      message: `Can follow at most ${MAX_SUBSCRIPTIONS} posts at once`,
+    });
+    return;
+  }
+
+  socket.join(postRoom(postId));
+  socket.emit("subscribed", { postId });
+}
+
+/**
+ * Sets up the /sockets/comments namespace, which pushes new comments on a
+ * post to everyone reading it. Clients pass `postId` in the handshake query
+ * or send `subscribe` / `unsubscribe` with `{ postId }`. Socket.IO removes
+ * a socket from its rooms when it disconnects.
+ */
+export function setupComments(nsp: Namespace) {
+  commentsNamespace = nsp;
+  nsp.on("connection", (socket: Socket) => {
+    rejectMalformedMessages(socket);
+
+    const { postId } = socket.handshake.query;
+    if (postId) subscribe(socket, postId);
+
+    socket.on("subscribe", (data: { postId?: unknown }) => {
+      subscribe(socket, data?.postId);
+    });
+
+    socket.on("unsubscribe", (data: { postId?: unknown }) => {
+      if (typeof data?.postId !== "string") return;
+      socket.leave(postRoom(data.postId));
+      socket.emit("unsubscribed", { postId: data.postId });
+    });
+  });
+}
+
+/**
+ * Sends a comment (as returned by the comment listings) to every client
+ * following its post.
+ */
+export function broadcastComment(
+  event: "comment" | "comment_updated",
+  comment: { blogId: string; [key: string]: unknown }
+) {
+  commentsNamespace?.to(postRoom(comment.blogId)).emit(event, comment);
+}
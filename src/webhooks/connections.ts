@@ -1,5 +1,6 @@
 import { Socket, Namespace } from "socket.io";
 import { auth } from "../auth";
+import { handshakeHeaders } from "./index";
 import { UserDocument } from "../db/connect";
 
 // User document type without the "_id" field
@@ -40,8 +41,8 @@ export function setupConnections(nsp: Namespace) {
     let userData: UserData;
 
     try {
-      // Attempt to authenticate using cookie/session
-      const headers = new Headers(socket.handshake.headers as any);
+      // Attempt to authenticate using the session cookie or token
+      const headers = handshakeHeaders(socket);
       userData = (await auth.api.getSession({ headers }))?.user;
     } catch (error: any) {
       console.log(
@@ -0,0 +1,100 @@
+import { describe, expect, test } from "bun:test";
+import type { Socket } from "socket.io";
+import { FakeNamespace } from "../test-utils/sockets";
+import {
+  handshakeHeaders,
+  hasCredentials,
+  rejectMalformedMessages,
+} from "./index";
+
+function handshake(
+  headers: Record<string, string>,
+  auth: Record<string, unknown> = {}
+) {
+  return { handshake: { headers, auth } } as unknown as Socket;
+}
+
+describe("handshakeHeaders", () => {
+  test("passes the handshake headers through", () => {
+    const headers = handshakeHeaders(
+      handshake({ cookie: "better-auth.session_token=abc" })
+    );
+    expect(headers.get("cookie")).toBe("better-auth.session_token=abc");
+    expect(hasCredentials(headers)).toBe(true);
+  });
+
+  test("turns an auth payload token into a bearer header", () => {
+    const headers = handshakeHeaders(handshake({}, { token: "abc" }));
+    expect(headers.get("authorization")).toBe("Bearer abc");
+    expect(hasCredentials(headers)).toBe(true);
+  });
+
+  test("keeps an explicit Authorization header", () => {
+    const headers = handshakeHeaders(
+      handshake({ authorization: "Bearer header" }, { token: "payload" })
+    );
+    expect(headers.get("authorization")).toBe("Bearer header");
+  });
+
+  test("ignores tokens that aren't strings", () => {
+    for (const token of [undefined, "", 123, { token: "abc" }]) {
+      const headers = handshakeHeaders(handshake({}, { token }));
+      expect(hasCredentials(headers)).toBe(false);
+    }
+  });
+});
+
+describe("rejectMalformedMessages", () => {
+  async function connect() {
+    const nsp = new FakeNamespace("/test");
+    nsp.on("connection", (socket) => rejectMalformedMessages(socket));
+    const socket = await nsp.connect();
+    const handled: unknown[] = [];
+    socket.on("move", (data: unknown) => handled.push(data));
+    return { socket, handled };
+  }
+
+  test("lets JSON objects and empty payloads through", async () => {
+    const { socket, handled } = await connect();
+    await socket.send("move", { san: "e4" });
+    await socket.send("move");
+    expect(handled).toEqual([{ san: "e4" }, undefined]);
+    expect(socket.received("error")).toEqual([]);
+  });
+
+  test("drops binary frames", async () => {
+    const { socket, handled } = await connect();
+    await socket.send("move", new Uint8Array([1, 2, 3]));
+    await socket.send("move", { san: "e4" }, new ArrayBuffer(4));
+    expect(handled).toEqual([]);
+    expect(socket.received("error")).toEqual([
+      { message: "Binary messages are not supported (event: move)" },
+      { message: "Binary messages are not supported (event: move)" },
+    ]);
+  });
+
+  test("drops payloads that aren't objects", async () => {
+    const { socket, handled } = await connect();
+    for (const payload of ["e4", 42, null, ["e4"]]) {
+      await socket.send("move", payload);
+    }
+    expect(handled).toEqual([]);
+    expect(socket.last("error")).toEqual({
+      message: "Message payload must be a JSON object (event: move)",
+    });
+  });
+
+  test("disconnects after five invalid messages in a row", async () => {
+    const { socket } = await connect();
+    for (let i = 0; i < 4; i++) await socket.send("move", "e4");
+    expect(socket.connected).toBe(true);
+
+    // A valid message starts the count again
+    await socket.send("move", { san: "e4" });
+    for (let i = 0; i < 4; i++) await socket.send("move", "e4");
+    expect(socket.connected).toBe(true);
+
+    await socket.send("move", "e4");
+    expect(socket.connected).toBe(false);
+  });
+});
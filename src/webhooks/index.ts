@@ -1,9 +1,93 @@
 // Backend: src/webhooks/index.ts (webhook boilerplate/objects)
 
 import { Server as Engine } from "@socket.io/bun-engine";
+import type { Socket } from "socket.io";
+import { socketInvalidMessages } from "../utils/metrics";
+
+// Heartbeat: the server pings every interval and drops clients that don't
+// answer within the timeout, which fires the usual "disconnect" handlers
+export const PING_INTERVAL_MS = parseInt(
+  process.env.SOCKET_PING_INTERVAL_MS || "25000"
+);
+export const PING_TIMEOUT_MS = parseInt(
+  process.env.SOCKET_PING_TIMEOUT_MS || "20000"
+);
+
+// Larger messages are refused and the connection is closed by the engine
+export const MAX_MESSAGE_BYTES = parseInt(
+  process.env.SOCKET_MAX_MESSAGE_BYTES || "16384"
+);
+const MAX_INVALID_MESSAGES = 5; // Consecutive bad messages before disconnecting
 
 export const engine = new Engine({
   path: "/sockets/", // Changed to /sockets/ for namespaced routes
+  pingInterval: PING_INTERVAL_MS,
+  pingTimeout: PING_TIMEOUT_MS,
+  maxHttpBufferSize: MAX_MESSAGE_BYTES,
 });
 
+function isBinary(value: unknown): boolean {
+  return (
+    value instanceof ArrayBuffer ||
+    ArrayBuffer.isView(value) ||
+    value instanceof Blob
+  );
+}
+
+/**
+ * Drops binary frames and payloads that aren't a plain JSON object,
+ * replying with an error. Clients that keep sending them are disconnected.
+ */
+export function rejectMalformedMessages(socket: Socket) {
+  let invalidCount = 0;
+
+  socket.use(([event, ...args], next) => {
+    const payload = args[0];
+    let problem: string | null = null;
+    if (args.some(isBinary)) {
+      problem = "Binary messages are not supported";
+    } else if (
+      payload !== undefined &&
+      (payload === null ||
+        typeof payload !== "object" ||
+        Array.isArray(payload))
+    ) {
+      problem = "Message payload must be a JSON object";
+    }
+
+    if (!problem) {
+      invalidCount = 0;
+      return next();
+    }
+
+    invalidCount++;
+    socketInvalidMessages.inc({ namespace: socket.nsp.name });
+    socket.emit("error", { message: `${problem} (event: ${event})` });
+    if (invalidCount >= MAX_INVALID_MESSAGES) {
+      console.warn(
+        `Disconnecting ${socket.id} after repeated invalid messages`
+      );
+      socket.disconnect(true);
+    }
+  });
+}
+
+/**
+ * Headers to authenticate a socket's handshake with. Besides the session
+ * cookie, a session token can be sent as `Authorization: Bearer <token>` or
+ * in the Socket.IO `auth` payload (`{ token }`), which unlike a query
+ * parameter never ends up in URLs or access logs.
+ */
+export function handshakeHeaders(socket: Socket): Headers {
+  const headers = new Headers(socket.handshake.headers as any);
+  const token = socket.handshake.auth?.token;
+  if (typeof token === "string" && token && !headers.has("authorization")) {
+    headers.set("authorization", `Bearer ${token}`);
+  }
+  return headers;
+}
+
+export const hasCredentials = (headers: Headers) =>
+  headers.has("cookie") || headers.has("authorization");
+
 export const { websocket } = engine.handler();
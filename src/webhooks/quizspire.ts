@@ -1,5 +1,6 @@
 import { Socket, Namespace } from "socket.io";
 import { auth } from "../auth";
+import { handshakeHeaders, hasCredentials } from "./index";
 import { connectToDatabase } from "../db/connect";
 import { ObjectId } from "mongodb";
 import {
@@ -294,10 +295,9 @@ export function setupQuizspire(nsp: Namespace) {
     let username: string = "Guest";
     let isAuthenticated = false;
 
-    const cookie = socket.handshake.headers.cookie;
-    if (cookie) {
+    const headers = handshakeHeaders(socket);
+    if (hasCredentials(headers)) {
       try {
-        const headers = new Headers(socket.handshake.headers as any);
         const sessionResponse = await auth.api.getSession({ headers });
         if (sessionResponse?.user) {
           const user = sessionResponse.user;